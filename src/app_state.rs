@@ -1,11 +1,34 @@
+use crate::commit_message;
+use crate::config::Config;
+use crate::details_cache::DetailsCache;
+use crate::diff::{self, CommitDetails, DetailLine, DiffLayout, FileStatus};
+use crate::fuzzy::{self, FuzzyMatch};
+use crate::git_commands::{self, BlameLine, RepoStatus, TreeEntry};
+use crate::highlight;
+use crate::hooks::{HookEvent, HookRun};
+use crate::identity::{Identity, IdentityScope};
+use crate::keymap::Keymap;
+use crate::text_input::TextInput;
+use crate::theme::{self, Theme};
+use crate::ui::{Split, ViewLayout};
+use chrono::{DateTime, Local};
 use git2::Repository;
+use log::debug;
+use ratatui::layout::{Margin, Position};
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::thread::JoinHandle;
+
+const MAX_FILTER_MATCHES: usize = 200;
+const MAX_NOTIFICATIONS: usize = 200; // Messages kept in the history
+const NOTIFICATION_SECONDS: i64 = 5; // How long the status bar shows a message
 
 pub enum Panel {
     CommitLog,
     Branches,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum UIState {
     Normal,
     CommitMessage,
@@ -17,10 +40,413 @@ pub enum UIState {
     ConfirmMerge,
     Error,
     FilterByFile,
+    Blame,
+    FileTree,
+    FileContents,
+    ConfirmFixup,
+    HookOutput,
+    SetIdentity,
+    Messages,
 }
 
-pub struct CommitState {
+/// Selection and scroll position of a list that keeps the selected item visible.
+#[derive(Clone)]
+pub struct ListCursor {
+    pub selected: usize,
+    pub visible_range: (usize, usize),
+    pub visible_count: usize,
+}
+
+impl ListCursor {
+    pub fn new(selected: usize, len: usize) -> Self {
+        Self {
+            selected: selected.min(len.saturating_sub(1)),
+            visible_range: (0, 0),
+            visible_count: 10,
+        }
+    }
+
+    // Scroll just enough to keep the selected item visible
+    pub fn update_visible_range(&mut self, len: usize) {
+        let (mut start, _) = self.visible_range;
+        if self.selected < start {
+            start = self.selected;
+        } else if self.selected >= start + self.visible_count {
+            start = self.selected + 1 - self.visible_count;
+        }
+        let end = usize::min(start + self.visible_count, len);
+        self.visible_range = (start, end);
+    }
+
+    pub fn select_previous(&mut self, items: usize, len: usize) {
+        self.selected = self.selected.saturating_sub(items);
+        self.update_visible_range(len);
+    }
+
+    pub fn select_next(&mut self, items: usize, len: usize) {
+        self.selected = usize::min(self.selected + items, len.saturating_sub(1));
+        self.update_visible_range(len);
+    }
+
+    /// Scrolls the view by `lines`, up or down, leaving the selection where it is unless it
+    /// would leave the view.
+    pub fn scroll(&mut self, lines: usize, down: bool, len: usize) {
+        let count = self.visible_count;
+        scroll_view(
+            &mut self.visible_range,
+            &mut self.selected,
+            count,
+            len,
+            lines,
+            down,
+        );
+    }
+}
+
+// Move a view of `count` rows over `len` items by `lines`, taking the selection along only
+// when it would leave the view. The view doesn't scroll past the last item.
+fn scroll_view(
+    range: &mut (usize, usize),
+    selected: &mut usize,
+    count: usize,
+    len: usize,
+    lines: usize,
+    down: bool,
+) {
+    let start = if down {
+        (range.0 + lines).min(len.saturating_sub(count).max(range.0))
+    } else {
+        range.0.saturating_sub(lines)
+    };
+    let end = usize::min(start + count, len);
+    *range = (start, end);
+    *selected = (*selected).clamp(start, end.saturating_sub(1).max(start));
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NotificationKind {
+    Info,
+    Success,
+    Error,
+}
+
+/// The result of an operation, shown in the status bar for a few seconds and kept in the
+/// message history.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub kind: NotificationKind,
     pub message: String,
+    pub time: DateTime<Local>,
+}
+
+#[derive(Clone)]
+pub struct BlameState {
+    pub path: String,
+    pub revision: String,
+    pub lines: Vec<BlameLine>,
+    pub cursor: ListCursor,
+}
+
+impl BlameState {
+    pub fn new(path: String, revision: String, lines: Vec<BlameLine>, selected: usize) -> Self {
+        Self {
+            path,
+            revision,
+            cursor: ListCursor::new(selected, lines.len()),
+            lines,
+        }
+    }
+
+    pub fn selected_line(&self) -> Option<&BlameLine> {
+        self.lines.get(self.cursor.selected)
+    }
+}
+
+#[derive(Clone)]
+pub struct FileTreeState {
+    pub revision: String,
+    pub directory: String, // Directory being browsed, empty for the root
+    pub entries: Vec<TreeEntry>,
+    pub cursor: ListCursor,
+}
+
+impl FileTreeState {
+    pub fn new(revision: String, directory: String, entries: Vec<TreeEntry>) -> Self {
+        Self {
+            revision,
+            directory,
+            cursor: ListCursor::new(0, entries.len()),
+            entries,
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&TreeEntry> {
+        self.entries.get(self.cursor.selected)
+    }
+
+    // Directory containing the browsed one, `None` at the root
+    pub fn parent_directory(&self) -> Option<String> {
+        if self.directory.is_empty() {
+            return None;
+        }
+        Some(
+            self.directory
+                .rsplit_once('/')
+                .map_or(String::new(), |(parent, _)| parent.to_string()),
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct FileContentsState {
+    pub path: String,
+    pub revision: String,
+    pub lines: Vec<String>,
+    pub cursor: ListCursor,
+}
+
+/// An operation stopped by a failing hook, which can be retried with hooks bypassed.
+#[derive(Clone)]
+pub enum HookRetry {
+    Commit(CommitKind, String), // Commit kind and message
+    Push,                       // The commit was made, only the push is left
+    Fixup { target: String, autosquash: bool },
+    Merge { target: String }, // Merge the current branch into `target`
+}
+
+/// An operation that runs hooks, running on a worker thread so the UI keeps responding
+/// and the hooks' output shows up while they run.
+pub struct RunningOperation {
+    pub operation: HookRetry,
+    pub events: Receiver<HookEvent>,
+    pub worker: JoinHandle<(Result<(), String>, Vec<HookRun>)>, // The result and the hooks run
+}
+
+/// Output of the hooks run by the last operation.
+pub struct HookOutputState {
+    pub lines: Vec<(String, Option<bool>)>, // Output lines; hook headers carry their success
+    pub cursor: ListCursor,
+    pub retry: Option<HookRetry>,
+    pub running: Option<usize>, // Header line of the hook still running
+}
+
+impl HookOutputState {
+    pub fn new(runs: &[HookRun], error: Option<String>, retry: Option<HookRetry>) -> Self {
+        let mut lines = Vec::new();
+        if let Some(error) = error {
+            lines.push((error, Some(false)));
+            lines.push((String::new(), None));
+        }
+        for run in runs {
+            lines.push((format!("{} ({})", run.name, run.status), Some(run.success)));
+            lines.extend(run.output.lines().map(|line| (line.to_string(), None)));
+            lines.push((String::new(), None));
+        }
+        let cursor = ListCursor::new(0, lines.len());
+        Self {
+            lines,
+            cursor,
+            retry,
+            running: None,
+        }
+    }
+
+    /// Adds the progress of a running hook, following the output unless scrolled up.
+    pub fn push_event(&mut self, event: HookEvent) {
+        let following = self.cursor.selected + 1 >= self.lines.len();
+        match event {
+            HookEvent::Started(name) => {
+                self.running = Some(self.lines.len());
+                self.lines.push((format!("{} (running…)", name), None));
+            }
+            HookEvent::Output(line) => self.lines.push((line, None)),
+            HookEvent::Finished { success, status } => {
+                if let Some((header, result)) = self
+                    .running
+                    .take()
+                    .and_then(|index| self.lines.get_mut(index))
+                {
+                    *header = header.replace("running…", &status);
+                    *result = Some(success);
+                }
+                self.lines.push((String::new(), None));
+            }
+        }
+        if following {
+            self.cursor.selected = self.lines.len().saturating_sub(1);
+            self.cursor.update_visible_range(self.lines.len());
+        }
+    }
+}
+
+// A view to return to when the view opened on top of it is closed
+struct ViewSnapshot {
+    ui_state: UIState,
+    commit_details: Option<Rc<CommitDetails>>,
+    details_from_log: bool,
+    commit_details_visible_range: (usize, usize),
+    selected_diff_file: usize,
+    blame: Option<BlameState>,
+    file_tree: Option<FileTreeState>,
+    file_contents: Option<FileContentsState>,
+}
+
+/// What confirming the commit message does.
+#[derive(Clone, PartialEq)]
+pub enum CommitKind {
+    New,            // Commit all changes and push
+    Amend,          // Replace HEAD with the staged changes and the new message
+    Reword(String), // Change the message of this commit
+}
+
+pub struct CommitState {
+    pub kind: CommitKind,
+    pub message: TextInput,
+    pub history: Vec<String>, // Messages of earlier commits made in ezgit, oldest first
+    pub history_index: Option<usize>, // History entry shown in the editor, if any
+    pub draft: String,        // Message being written before recalling history
+    pub picker: Option<ConventionalPicker>,
+}
+
+impl CommitState {
+    pub fn new(kind: CommitKind, message: &str, history: Vec<String>) -> Self {
+        Self {
+            kind,
+            message: TextInput::with_text(message, true),
+            history,
+            history_index: None,
+            draft: String::new(),
+            picker: None,
+        }
+    }
+
+    /// Checks the message being edited. Only new commits must have a body, so that amending
+    /// or rewording an older subject-only commit doesn't mean writing one.
+    pub fn check(&self) -> commit_message::MessageCheck {
+        commit_message::check(self.message.text(), self.kind == CommitKind::New)
+    }
+
+    /// Shows the previous message from the history in the editor.
+    pub fn recall_previous(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.message.text().to_string();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.message.set_text(&self.history[index]);
+    }
+
+    /// Shows the next message from the history, or the draft after the newest one.
+    pub fn recall_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.message.set_text(&self.history[index + 1]);
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.message.set_text(&self.draft);
+            }
+            None => {}
+        }
+    }
+}
+
+/// Name and email typed into the identity prompt.
+pub struct IdentityPrompt {
+    pub scope: IdentityScope,
+    pub name: TextInput,
+    pub email: TextInput,
+    pub editing_email: bool, // Which of the two fields has the cursor
+}
+
+impl IdentityPrompt {
+    pub fn new(scope: IdentityScope, current: Option<Identity>) -> Self {
+        let (name, email) =
+            current.map_or_else(Default::default, |identity| (identity.name, identity.email));
+        Self {
+            scope,
+            name: TextInput::with_text(&name, false),
+            email: TextInput::with_text(&email, false),
+            editing_email: false,
+        }
+    }
+
+    pub fn focused_input(&mut self) -> &mut TextInput {
+        if self.editing_email {
+            &mut self.email
+        } else {
+            &mut self.name
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.name.text().trim().is_empty() && self.email.text().contains('@')
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickerStage {
+    Type,
+    Scope,
+}
+
+/// Picks the `type(scope): ` prefix of a conventional commit subject.
+pub struct ConventionalPicker {
+    pub stage: PickerStage,
+    pub options: Vec<(String, String)>, // Option and its description
+    pub selected: usize,
+    pub commit_type: String, // Chosen in the type stage
+    scopes: Vec<String>,
+}
+
+impl ConventionalPicker {
+    pub fn new(scopes: Vec<String>) -> Self {
+        Self {
+            stage: PickerStage::Type,
+            options: commit_message::COMMIT_TYPES
+                .iter()
+                .map(|(name, description)| (name.to_string(), description.to_string()))
+                .collect(),
+            selected: 0,
+            commit_type: String::new(),
+            scopes,
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.options.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Chooses the selected type and moves on to the scopes.
+    pub fn choose_type(&mut self) {
+        self.commit_type = self.options[self.selected].0.clone();
+        self.stage = PickerStage::Scope;
+        self.options = std::iter::once(("(no scope)".to_string(), String::new()))
+            .chain(
+                self.scopes
+                    .iter()
+                    .map(|scope| (scope.clone(), String::new())),
+            )
+            .collect();
+        self.selected = 0;
+    }
+
+    /// The selected scope, `None` for the "no scope" option.
+    pub fn selected_scope(&self) -> Option<&str> {
+        (self.selected > 0).then(|| self.options[self.selected].0.as_str())
+    }
 }
 
 pub enum InputMode {
@@ -29,6 +455,7 @@ pub enum InputMode {
 }
 
 pub struct AppState {
+    pub repo_path: String,             // Repository all git commands run in
     pub selected_index: usize,         // Selected commit index
     pub commit_log: Vec<String>,       // Commit log
     pub visible_range: (usize, usize), // Visible range of commits
@@ -39,20 +466,56 @@ pub struct AppState {
     pub branch_visible_range: (usize, usize),
     pub branch_visible_count: usize,
     pub focused_panel: Panel, // Currently focused panel
-    pub selected_commit_details: Option<String>,
+    pub selected_commit_details: Option<Rc<CommitDetails>>,
+    pub commit_details_lines: Vec<DetailLine>, // Rendered rows of the selected commit details
+    pub syntax_highlight: bool,
+    pub diff_layout: DiffLayout,
+    pub diff_stat_only: bool, // Show only the diffstat in commit details
+    pub selected_diff_file: usize, // File highlighted in the diffstat
     pub ui_state: UIState,
     pub commit_state: Option<CommitState>,
+    pub fixup_target: Option<String>, // Commit to create a fixup commit for
+    pub rewrite_upstream: Option<String>, // Upstream already containing the commit about to be rewritten
+    pub hook_output: Option<HookOutputState>,
+    pub operation: Option<RunningOperation>, // Operation running hooks in the background
+    pub identity_prompt: Option<IdentityPrompt>,
+    pub keymap: Keymap,
+    pub config: Config,
+    pub theme: Theme,
+    pub key_guide_scroll: u16,
     pub commit_details_visible_range: (usize, usize),
     pub commit_details_total_lines: usize,
     pub commit_details_visible_count: usize,
     pub input_mode: InputMode,
-    pub branch_name: String,
+    pub branch_name: String,             // Currently checked out branch
+    pub branch_input: TextInput,         // Name typed into the create branch prompt
+    pub filter_input: TextInput,         // Path typed into the file filter prompt
+    pub tracked_paths: Vec<String>,      // Candidates for the file filter prompt
+    pub filter_matches: Vec<FuzzyMatch>, // Tracked paths matching `filter_input`, best first
+    pub selected_filter_match: usize,
+    pub filter_match_picked: bool, // The user moved to a match since the input last changed
     pub error_message: Option<String>,
+    pub compare_base: Option<String>, // Revision marked as the base of a comparison
+    pub blame: Option<BlameState>,
+    pub file_tree: Option<FileTreeState>,
+    pub file_contents: Option<FileContentsState>,
+    view_history: Vec<ViewSnapshot>, // Views to return to with Esc, innermost last
+    pub layout: ViewLayout,          // The views as last drawn, to find what the mouse points at
+    pub dragging: Option<Split>,     // Divider held down with the mouse
+    pub dragged_commit_log_percent: Option<u16>, // Split set with the mouse, over the config's
+    pub dragged_details_percent: Option<u16>,
+    pub details_from_log: bool, // The details are of a commit in the log, which is shown with them
+    pub details_cache: DetailsCache,
+    pub preview_commit: Option<String>, // Commit whose details the preview shows
+    pub repo_status: Option<RepoStatus>, // `None` if it couldn't be read
+    pub notifications: Vec<Notification>, // Oldest first
+    pub messages_scroll: u16,
 }
 
 impl AppState {
     pub fn new(commit_log: Vec<String>, branches: Vec<String>, repo_path: &str) -> Self {
         // Determine the current branch
+        #[allow(clippy::manual_unwrap_or_default)]
         let current_branch = match Repository::open(repo_path).and_then(|repo| {
             repo.head().and_then(|head| {
                 head.shorthand()
@@ -70,7 +533,8 @@ impl AppState {
             .position(|branch| branch == &current_branch)
             .unwrap_or(0); // Default to the first branch if the current branch isn't found
 
-        Self {
+        let mut app_state = Self {
+            repo_path: repo_path.to_string(),
             selected_index: 0,
             commit_log,
             visible_range: (0, 0),
@@ -82,40 +546,224 @@ impl AppState {
             branch_visible_count: 10,
             focused_panel: Panel::CommitLog,
             selected_commit_details: None,
+            commit_details_lines: Vec::new(),
+            syntax_highlight: true,
+            diff_layout: DiffLayout::Unified,
+            diff_stat_only: false,
+            selected_diff_file: 0,
             ui_state: UIState::Normal,
             commit_state: None,
+            fixup_target: None,
+            rewrite_upstream: None,
+            hook_output: None,
+            operation: None,
+            identity_prompt: None,
+            keymap: Keymap::default(),
+            config: Config::default(),
+            theme: Theme::default(),
+            key_guide_scroll: 0,
             commit_details_visible_range: (0, 0),
             commit_details_total_lines: 0,
             commit_details_visible_count: 10,
             input_mode: InputMode::Command,
             branch_name: current_branch,
+            branch_input: TextInput::new(false),
+            filter_input: TextInput::new(false),
+            tracked_paths: Vec::new(),
+            filter_matches: Vec::new(),
+            selected_filter_match: 0,
+            filter_match_picked: false,
             error_message: None,
+            compare_base: None,
+            blame: None,
+            file_tree: None,
+            file_contents: None,
+            view_history: Vec::new(),
+            layout: ViewLayout::default(),
+            dragging: None,
+            dragged_commit_log_percent: None,
+            dragged_details_percent: None,
+            details_from_log: false,
+            details_cache: DetailsCache::default(),
+            preview_commit: None,
+            repo_status: None,
+            notifications: Vec::new(),
+            messages_scroll: 0,
+        };
+        app_state.refresh_status();
+        app_state
+    }
+
+    /// Reads the branch, upstream and working tree state again for the status bar.
+    pub fn refresh_status(&mut self) {
+        match git_commands::get_repo_status(&self.repo_path) {
+            Ok(status) => self.repo_status = Some(status),
+            Err(err) => {
+                debug!("Error reading the repository status: {}", err);
+                self.repo_status = None;
+            }
+        }
+    }
+
+    /// Reports the result of an operation in the status bar and the message history.
+    pub fn notify(&mut self, kind: NotificationKind, message: impl Into<String>) {
+        let message = message.into();
+        debug!("{:?}: {}", kind, message);
+        if self.notifications.len() == MAX_NOTIFICATIONS {
+            self.notifications.remove(0);
+        }
+        self.notifications.push(Notification {
+            kind,
+            message,
+            time: Local::now(),
+        });
+    }
+
+    /// The latest notification, while it is recent enough to show in the status bar.
+    pub fn current_notification(&self) -> Option<&Notification> {
+        self.notifications.last().filter(|notification| {
+            (Local::now() - notification.time).num_seconds() < NOTIFICATION_SECONDS
+        })
+    }
+
+    /// Switches to `ui_state`, remembering the current view so that `close_view` can return to it.
+    /// Opening a view from the normal state starts a new history.
+    pub fn open_view(&mut self, ui_state: UIState) {
+        if self.ui_state == UIState::Normal {
+            self.view_history.clear();
+        } else {
+            self.view_history.push(ViewSnapshot {
+                ui_state: self.ui_state,
+                commit_details: self.selected_commit_details.clone(),
+                details_from_log: self.details_from_log,
+                commit_details_visible_range: self.commit_details_visible_range,
+                selected_diff_file: self.selected_diff_file,
+                blame: self.blame.clone(),
+                file_tree: self.file_tree.clone(),
+                file_contents: self.file_contents.clone(),
+            });
+        }
+        self.ui_state = ui_state;
+    }
+
+    /// Returns to the view the current one was opened from, or to the normal state.
+    pub fn close_view(&mut self) {
+        match self.view_history.pop() {
+            Some(snapshot) => {
+                if let Some(details) = snapshot.commit_details {
+                    self.set_selected_commit_details(details);
+                    self.details_from_log = snapshot.details_from_log;
+                    self.commit_details_visible_range = snapshot.commit_details_visible_range;
+                    self.selected_diff_file = snapshot.selected_diff_file;
+                }
+                self.blame = snapshot.blame;
+                self.file_tree = snapshot.file_tree;
+                self.file_contents = snapshot.file_contents;
+                self.ui_state = snapshot.ui_state;
+            }
+            None => self.ui_state = UIState::Normal,
         }
     }
 
+    /// Scrolls every view for the sizes it was last drawn at, keeping selections visible.
+    pub fn apply_layout(&mut self, layout: &ViewLayout) {
+        self.layout = *layout;
+        if let Some(rows) = layout.commit_log_rows {
+            self.visible_count = rows;
+        }
+        self.update_visible_range();
+
+        if let Some(rows) = layout.branch_rows {
+            self.branch_visible_count = rows;
+        }
+        self.update_branch_visible_range();
+
+        let detail_rows = layout
+            .detail_rows
+            .unwrap_or(self.commit_details_visible_count);
+        self.update_commit_details_visible_range(detail_rows);
+
+        let cursors = [
+            self.blame
+                .as_mut()
+                .map(|blame| (&mut blame.cursor, blame.lines.len())),
+            self.file_tree
+                .as_mut()
+                .map(|tree| (&mut tree.cursor, tree.entries.len())),
+            self.file_contents
+                .as_mut()
+                .map(|contents| (&mut contents.cursor, contents.lines.len())),
+            self.hook_output
+                .as_mut()
+                .map(|output| (&mut output.cursor, output.lines.len())),
+        ];
+        for (cursor, len) in cursors.into_iter().flatten() {
+            if let Some(rows) = layout.list_rows {
+                cursor.visible_count = rows;
+            }
+            cursor.update_visible_range(len);
+        }
+
+        if let Some(rows) = layout.key_guide_rows {
+            let lines = layout
+                .key_guide_lines
+                .unwrap_or_else(|| self.keymap.guide().len());
+            let max_scroll = lines.saturating_sub(rows);
+            self.key_guide_scroll = self.key_guide_scroll.min(max_scroll as u16);
+        }
+        if let Some(rows) = layout.messages_rows {
+            let max_scroll = self.notifications.len().saturating_sub(rows);
+            self.messages_scroll = self.messages_scroll.min(max_scroll as u16);
+        }
+    }
+
+    // Keep the Commit Log where the wheel scrolled it while the selection is in view, and
+    // show the page of the selection otherwise
     pub fn update_visible_range(&mut self) {
-        let start = self
-            .selected_index
-            .saturating_sub(self.selected_index % self.visible_count);
+        let (mut start, _) = self.visible_range;
+        if !(start..start + self.visible_count).contains(&self.selected_index) {
+            start = self
+                .selected_index
+                .saturating_sub(self.selected_index % self.visible_count);
+        }
         let end = usize::min(start + self.visible_count, self.commit_log.len());
         self.visible_range = (start, end);
     }
 
-    pub fn scroll_up(&mut self) {
-        if self.visible_range.0 > 0 {
-            self.visible_range.0 -= 1;
-            self.visible_range.1 = usize::min(self.visible_range.1 - 1, self.commit_log.len());
-        }
+    /// Scrolls the focused panel of the main view by `lines`, up or down, leaving its selection
+    /// where it is unless it would leave the view.
+    pub fn scroll_panel(&mut self, lines: usize, down: bool) {
+        let (range, selected, count, len) = match self.focused_panel {
+            Panel::CommitLog => (
+                &mut self.visible_range,
+                &mut self.selected_index,
+                self.visible_count,
+                self.commit_log.len(),
+            ),
+            Panel::Branches => (
+                &mut self.branch_visible_range,
+                &mut self.selected_branch,
+                self.branch_visible_count,
+                self.branches.len(),
+            ),
+        };
+        scroll_view(range, selected, count, len, lines, down);
     }
 
-    pub fn scroll_down(&mut self) {
-        if self.visible_range.1 < self.commit_log.len() {
-            self.visible_range.0 += 1;
-            self.visible_range.1 = usize::min(self.visible_range.1 + 1, self.commit_log.len());
+    // Keep the Branches where the wheel scrolled them while the selection is in view, and
+    // center the selection otherwise
+    pub fn update_branch_visible_range(&mut self) {
+        let (start, _) = self.branch_visible_range;
+        if (start..start + self.branch_visible_count).contains(&self.selected_branch) {
+            let end = (start + self.branch_visible_count).min(self.branches.len());
+            self.branch_visible_range = (start, end);
+        } else {
+            self.center_selected_branch();
         }
     }
 
-    pub fn update_branch_visible_range(&mut self) {
+    // Moving through the Branches keeps the selection in the middle
+    fn center_selected_branch(&mut self) {
         let start = self
             .selected_branch
             .saturating_sub(self.branch_visible_count / 2);
@@ -126,15 +774,112 @@ impl AppState {
     pub fn select_previous_branch(&mut self) {
         if self.selected_branch > 0 {
             self.selected_branch -= 1;
-            self.update_branch_visible_range();
+            self.center_selected_branch();
         }
     }
 
     pub fn select_next_branch(&mut self) {
         if self.selected_branch < self.branches.len() - 1 {
             self.selected_branch += 1;
-            self.update_branch_visible_range();
+            self.center_selected_branch();
+        }
+    }
+
+    /// The panel of the main view at a screen position.
+    pub fn panel_at(&self, column: u16, row: u16) -> Option<Panel> {
+        let position = Position::new(column, row);
+        if self
+            .layout
+            .commit_log_area
+            .is_some_and(|area| area.contains(position))
+        {
+            Some(Panel::CommitLog)
+        } else if self
+            .layout
+            .branch_area
+            .is_some_and(|area| area.contains(position))
+        {
+            Some(Panel::Branches)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a screen position is on the commit details previewed in the main view.
+    pub fn is_over_preview(&self, column: u16, row: u16) -> bool {
+        self.ui_state == UIState::Normal
+            && self
+                .layout
+                .details_area
+                .is_some_and(|area| area.contains(Position::new(column, row)))
+    }
+
+    /// The divider on the screen at a position, if any.
+    pub fn divider_at(&self, column: u16, row: u16) -> Option<Split> {
+        [self.layout.commit_log_divider, self.layout.details_divider]
+            .into_iter()
+            .flatten()
+            .find(|divider| divider.contains(column, row))
+            .map(|divider| divider.split)
+    }
+
+    /// Moves the divider being dragged to a screen position.
+    pub fn drag_divider(&mut self, column: u16, row: u16) {
+        let divider = match self.dragging {
+            Some(Split::CommitLog) => self.layout.commit_log_divider,
+            Some(Split::Details) => self.layout.details_divider,
+            None => None,
+        };
+        let Some(divider) = divider else {
+            return;
+        };
+        let percent = divider.percent_at(column, row);
+        match divider.split {
+            Split::CommitLog => self.dragged_commit_log_percent = Some(percent),
+            Split::Details => self.dragged_details_percent = Some(100 - percent), // The details come second
+        }
+    }
+
+    /// The share of a split's setting, as dragged with the mouse or else as configured.
+    pub fn split_percent(&self, split: Split) -> u16 {
+        match split {
+            Split::CommitLog => self
+                .dragged_commit_log_percent
+                .unwrap_or(self.config.layout.commit_log_percent),
+            Split::Details => self
+                .dragged_details_percent
+                .unwrap_or(self.config.layout.details_percent),
+        }
+    }
+
+    /// Focuses the panel at a screen position and selects the item on that row. Returns
+    /// whether an item was clicked, rather than the border or the empty space below the items.
+    pub fn click_panel(&mut self, column: u16, row: u16) -> bool {
+        let Some(panel) = self.panel_at(column, row) else {
+            return false;
+        };
+        let (area, (start, end)) = match panel {
+            Panel::CommitLog => (self.layout.commit_log_area, self.visible_range),
+            Panel::Branches => (self.layout.branch_area, self.branch_visible_range),
+        };
+        let clicked = match panel {
+            Panel::CommitLog => &mut self.selected_index,
+            Panel::Branches => &mut self.selected_branch,
+        };
+        self.focused_panel = panel;
+
+        let Some(inner) = area.map(|area| area.inner(Margin::new(1, 1))) else {
+            return false;
+        };
+        if !inner.contains(Position::new(column, row)) {
+            return false; // On the border
         }
+        let index = start + (row - inner.y) as usize;
+        if index >= end {
+            return false;
+        }
+        *clicked = index;
+        true
     }
 
     pub fn focus_next_panel(&mut self) {
@@ -147,33 +892,338 @@ impl AppState {
     pub fn select_previous(&mut self) {
         if self.selected_index > 0 {
             self.selected_index -= 1;
-            if self.selected_index < self.visible_range.0 {
-                self.scroll_up();
-            }
+            self.update_visible_range();
         }
     }
 
     pub fn select_next(&mut self) {
         if self.selected_index < self.commit_log.len() - 1 {
             self.selected_index += 1;
-            if self.selected_index >= self.visible_range.1 {
-                self.scroll_down();
+            self.update_visible_range();
+        }
+    }
+
+    // Hash of the commit highlighted in the commit log
+    pub fn selected_commit_hash(&self) -> Option<String> {
+        self.commit_log
+            .get(self.selected_index)
+            .and_then(|commit| commit.split(" | ").next())
+            .map(|hash| hash.trim().to_string())
+    }
+
+    // Commit hash or branch name highlighted in the focused panel
+    pub fn selected_revision(&self) -> Option<String> {
+        match self.focused_panel {
+            Panel::CommitLog => self.selected_commit_hash(),
+            Panel::Branches => self.branches.get(self.selected_branch).cloned(),
+        }
+    }
+
+    /// The text input of the prompt currently on screen, if any.
+    pub fn active_text_input(&mut self) -> Option<&mut TextInput> {
+        match self.ui_state {
+            UIState::CommitMessage => self.commit_state.as_mut().map(|state| &mut state.message),
+            UIState::CreateBranch => Some(&mut self.branch_input),
+            UIState::FilterByFile => Some(&mut self.filter_input),
+            UIState::SetIdentity => self
+                .identity_prompt
+                .as_mut()
+                .map(|prompt| prompt.focused_input()),
+            _ => None,
+        }
+    }
+
+    /// Re-ranks the tracked paths against the typed filter and highlights the best match.
+    pub fn update_filter_matches(&mut self) {
+        self.filter_matches = fuzzy::rank(
+            self.filter_input.text().trim(),
+            &self.tracked_paths,
+            MAX_FILTER_MATCHES,
+        );
+        self.selected_filter_match = 0;
+        self.filter_match_picked = false;
+    }
+
+    pub fn selected_filter_path(&self) -> Option<&str> {
+        self.filter_matches
+            .get(self.selected_filter_match)
+            .map(|matched| self.tracked_paths[matched.index].as_str())
+    }
+
+    pub fn select_previous_filter_match(&mut self) {
+        self.selected_filter_match = self.selected_filter_match.saturating_sub(1);
+        self.filter_match_picked = true;
+    }
+
+    pub fn select_next_filter_match(&mut self) {
+        if self.selected_filter_match + 1 < self.filter_matches.len() {
+            self.selected_filter_match += 1;
+        }
+        self.filter_match_picked = true;
+    }
+
+    /// Tab completion: extends the input to the longest common prefix of the paths
+    /// starting with it, or replaces it with the highlighted match.
+    pub fn complete_filter_input(&mut self) {
+        let input = self.filter_input.text().trim();
+        let prefixed: Vec<&String> = self
+            .tracked_paths
+            .iter()
+            .filter(|path| path.starts_with(input))
+            .collect();
+
+        let completion = match prefixed.split_first() {
+            Some((first, rest)) if !input.is_empty() => {
+                let common_len = rest.iter().fold(first.chars().count(), |len, path| {
+                    first
+                        .chars()
+                        .zip(path.chars())
+                        .take(len)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                let common: String = first.chars().take(common_len).collect();
+                (common.len() > input.len()).then_some(common)
+            }
+            _ => None,
+        };
+
+        match completion.or_else(|| self.selected_filter_path().map(String::from)) {
+            Some(completed) => {
+                self.filter_input.set_text(&completed);
+                self.update_filter_matches();
             }
+            None => debug!("No completion for '{}'", input),
         }
     }
 
-    pub fn set_selected_commit_details(&mut self, details: String) {
-        self.selected_commit_details = Some(details);
+    /// Switches to new settings, including their theme.
+    pub fn set_config(&mut self, config: Config) {
+        self.theme = theme::active(&config);
+        self.config = config;
+        self.rebuild_commit_details_lines();
+    }
+
+    pub fn set_selected_commit_details(&mut self, details: impl Into<Rc<CommitDetails>>) {
+        self.selected_commit_details = Some(details.into());
+        self.preview_commit = None; // Set again if the details are for the preview
+        self.commit_details_visible_range = (0, 0);
+        self.selected_diff_file = 0;
+        self.rebuild_commit_details_lines();
     }
 
     pub fn clear_selected_commit_details(&mut self) {
         self.selected_commit_details = None;
+        self.preview_commit = None;
+        self.commit_details_lines.clear();
+        self.commit_details_total_lines = 0;
+    }
+
+    // Recompute the displayed rows after the details or a display option changed
+    pub fn rebuild_commit_details_lines(&mut self) {
+        if let Some(details) = &mut self.selected_commit_details {
+            // Details from the cache are shared, so they are copied only to change them
+            match (self.syntax_highlight, &self.theme.syntax_theme) {
+                (true, Some(syntax_theme)) => {
+                    highlight::highlight_files(&mut Rc::make_mut(details).files, syntax_theme)
+                }
+                _ if highlight::is_highlighted(&details.files) => {
+                    highlight::clear_highlights(&mut Rc::make_mut(details).files)
+                }
+                _ => {}
+            }
+            self.commit_details_lines =
+                diff::build_detail_lines(details, self.diff_layout, self.diff_stat_only);
+        } else {
+            self.commit_details_lines.clear();
+        }
+        self.commit_details_total_lines = self.commit_details_lines.len();
+        let start = usize::min(
+            self.commit_details_visible_range.0,
+            self.commit_details_total_lines,
+        );
+        self.scroll_commit_details_to(start);
+    }
+
+    pub fn toggle_diff_layout(&mut self) {
+        self.diff_layout = match self.diff_layout {
+            DiffLayout::Unified => DiffLayout::SideBySide,
+            DiffLayout::SideBySide => DiffLayout::Unified,
+        };
+        self.rebuild_commit_details_lines();
+    }
+
+    pub fn toggle_syntax_highlight(&mut self) {
+        self.syntax_highlight = !self.syntax_highlight;
+        self.rebuild_commit_details_lines();
+    }
+
+    // Scroll the commit details so that `line` is the first visible row
+    fn scroll_commit_details_to(&mut self, line: usize) {
+        let end = usize::min(
+            line + self.commit_details_visible_count,
+            self.commit_details_total_lines,
+        );
+        self.commit_details_visible_range = (line, end);
+    }
+
+    fn commit_details_positions(&self, is_target: fn(&DetailLine) -> bool) -> Vec<usize> {
+        self.commit_details_lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| is_target(line))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn jump_commit_details_forward(&mut self, is_target: fn(&DetailLine) -> bool) {
+        let current = self.commit_details_visible_range.0;
+        if let Some(&next) = self
+            .commit_details_positions(is_target)
+            .iter()
+            .find(|&&pos| pos > current)
+        {
+            self.scroll_commit_details_to(next);
+        }
+    }
+
+    fn jump_commit_details_backward(&mut self, is_target: fn(&DetailLine) -> bool) {
+        let current = self.commit_details_visible_range.0;
+        if let Some(&previous) = self
+            .commit_details_positions(is_target)
+            .iter()
+            .rev()
+            .find(|&&pos| pos < current)
+        {
+            self.scroll_commit_details_to(previous);
+        }
+    }
+
+    pub fn toggle_diff_stat_only(&mut self) {
+        self.diff_stat_only = !self.diff_stat_only;
+        self.commit_details_visible_range = (0, 0);
+        self.rebuild_commit_details_lines();
+    }
+
+    fn diff_file_count(&self) -> usize {
+        self.selected_commit_details
+            .as_ref()
+            .map_or(0, |details| details.files.len())
+    }
+
+    /// Revision and path to blame for the file highlighted in the diffstat: the new side of
+    /// the diff, or the old side for deleted files. Content that is only in the working tree
+    /// or the index has no commit to blame it at, so that is an error.
+    pub fn selected_diff_file_revision(&self) -> Option<Result<(String, String), String>> {
+        let details = self.selected_commit_details.as_ref()?;
+        let file = details.files.get(self.selected_diff_file)?;
+        let revision = match file.status {
+            FileStatus::Deleted => details.old_revision.clone(),
+            _ => details.revision.clone(),
+        };
+        Some(
+            revision
+                .map(|revision| (revision, file.path.clone()))
+                .ok_or(format!(
+                    "Cannot blame '{}': this version of it is not committed",
+                    file.path
+                )),
+        )
+    }
+
+    pub fn select_next_diff_file(&mut self) {
+        if self.selected_diff_file + 1 < self.diff_file_count() {
+            self.selected_diff_file += 1;
+        }
+        self.scroll_to_selected_diff_file();
+    }
+
+    pub fn select_previous_diff_file(&mut self) {
+        self.selected_diff_file = self.selected_diff_file.saturating_sub(1);
+        self.scroll_to_selected_diff_file();
+    }
+
+    // Scroll just enough to keep the diffstat row of the selected file visible
+    fn scroll_to_selected_diff_file(&mut self) {
+        let selected = self.selected_diff_file;
+        let Some(row) = self
+            .commit_details_lines
+            .iter()
+            .position(|line| matches!(line, DetailLine::Stat { file, .. } if *file == selected))
+        else {
+            return;
+        };
+        let (start, _) = self.commit_details_visible_range;
+        if row < start {
+            self.scroll_commit_details_to(row);
+        } else if row >= start + self.commit_details_visible_count {
+            self.scroll_commit_details_to(row + 1 - self.commit_details_visible_count);
+        }
+    }
+
+    // Show the full diff scrolled to the file highlighted in the diffstat
+    pub fn open_selected_diff_file(&mut self) {
+        if self.diff_stat_only {
+            self.diff_stat_only = false;
+            self.rebuild_commit_details_lines();
+        }
+        let selected = self.selected_diff_file;
+        if let Some(position) = self
+            .commit_details_lines
+            .iter()
+            .position(|line| matches!(line, DetailLine::FileHeader(index, _) if *index == selected))
+        {
+            self.scroll_commit_details_to(position);
+        }
+    }
+
+    // Highlight the file whose diff is at the top of the view
+    fn select_diff_file_at_scroll_position(&mut self) {
+        let top = self.commit_details_visible_range.0;
+        if let Some(DetailLine::FileHeader(index, _)) = self
+            .commit_details_lines
+            .iter()
+            .take(top + 1)
+            .rev()
+            .find(|line| matches!(line, DetailLine::FileHeader(..)))
+        {
+            self.selected_diff_file = *index;
+        }
+    }
+
+    pub fn jump_to_next_file(&mut self) {
+        if self.diff_stat_only {
+            self.select_next_diff_file();
+        } else {
+            self.jump_commit_details_forward(|line| matches!(line, DetailLine::FileHeader(..)));
+            self.select_diff_file_at_scroll_position();
+        }
+    }
+
+    pub fn jump_to_previous_file(&mut self) {
+        if self.diff_stat_only {
+            self.select_previous_diff_file();
+        } else {
+            self.jump_commit_details_backward(|line| matches!(line, DetailLine::FileHeader(..)));
+            self.select_diff_file_at_scroll_position();
+        }
+    }
+
+    pub fn jump_to_next_hunk(&mut self) {
+        self.jump_commit_details_forward(|line| matches!(line, DetailLine::HunkHeader(_)));
+    }
+
+    pub fn jump_to_previous_hunk(&mut self) {
+        self.jump_commit_details_backward(|line| matches!(line, DetailLine::HunkHeader(_)));
     }
 
     pub fn update_commit_details_visible_range(&mut self, chunk_height: usize) {
         self.commit_details_visible_count = chunk_height; // Set the number of visible lines
         let (start, _) = self.commit_details_visible_range;
-        let end = usize::min(start + self.commit_details_visible_count, self.commit_details_total_lines);
+        let end = usize::min(
+            start + self.commit_details_visible_count,
+            self.commit_details_total_lines,
+        );
         self.commit_details_visible_range = (start, end);
     }
 
@@ -238,6 +1288,80 @@ impl AppState {
         }
     }
 
+    /// Selects the first item of the list on screen, or scrolls to the top of the details.
+    pub fn jump_to_top(&mut self) {
+        self.jump_to_edge(false);
+    }
+
+    /// Selects the last item of the list on screen, or scrolls to the end of the details.
+    pub fn jump_to_bottom(&mut self) {
+        self.jump_to_edge(true);
+    }
+
+    fn jump_to_edge(&mut self, bottom: bool) {
+        // Moving a cursor by the length of its list always reaches the edge
+        let jump = |cursor: &mut ListCursor, len: usize| {
+            if bottom {
+                cursor.select_next(len, len);
+            } else {
+                cursor.select_previous(len, len);
+            }
+        };
+        match self.ui_state {
+            UIState::CommitDetails if bottom => {
+                self.page_down_commit_details(self.commit_details_total_lines)
+            }
+            UIState::CommitDetails => self.page_up_commit_details(self.commit_details_total_lines),
+            UIState::Blame => {
+                if let Some(blame) = &mut self.blame {
+                    jump(&mut blame.cursor, blame.lines.len());
+                }
+            }
+            UIState::FileTree => {
+                if let Some(tree) = &mut self.file_tree {
+                    jump(&mut tree.cursor, tree.entries.len());
+                }
+            }
+            UIState::FileContents => {
+                if let Some(contents) = &mut self.file_contents {
+                    jump(&mut contents.cursor, contents.lines.len());
+                }
+            }
+            UIState::HookOutput => {
+                if let Some(output) = &mut self.hook_output {
+                    jump(&mut output.cursor, output.lines.len());
+                }
+            }
+            UIState::KeyGuide => self.key_guide_scroll = if bottom { u16::MAX } else { 0 },
+            UIState::Messages => {
+                self.messages_scroll = if bottom {
+                    self.notifications.len() as u16
+                } else {
+                    0
+                }
+            }
+            UIState::Normal => match self.focused_panel {
+                Panel::CommitLog => {
+                    self.selected_index = if bottom {
+                        self.commit_log.len().saturating_sub(1)
+                    } else {
+                        0
+                    };
+                    self.update_visible_range();
+                }
+                Panel::Branches => {
+                    self.selected_branch = if bottom {
+                        self.branches.len().saturating_sub(1)
+                    } else {
+                        0
+                    };
+                    self.update_branch_visible_range();
+                }
+            },
+            _ => {}
+        }
+    }
+
     // Scroll the commit details down by a page
     pub fn page_down_commit_details(&mut self, lines_per_page: usize) {
         let (start, end) = self.commit_details_visible_range;
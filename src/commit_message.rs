@@ -0,0 +1,413 @@
+use crate::identity::{self, Role};
+use git2::Repository;
+use std::process::Command;
+use std::{env, fs};
+use unicode_width::UnicodeWidthStr;
+
+pub const SUBJECT_LIMIT: usize = 50; // Columns before the subject line is flagged
+pub const BODY_LIMIT: usize = 72; // Columns before a body line is flagged
+
+pub const SIGNED_OFF_BY: &str = "Signed-off-by";
+pub const CO_AUTHORED_BY: &str = "Co-authored-by";
+
+const EDITOR_HELP: &str = "
+# Please enter the commit message for your changes. Lines starting
+# with '#' will be ignored, and an empty message aborts the commit.
+#
+# Keep the subject line within 50 columns, leave the second line blank
+# and wrap the body at 72 columns.
+";
+
+/// Problems found in a commit message. Errors block the commit, warnings don't.
+#[derive(Default)]
+pub struct MessageCheck {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks a message against our conventions: a subject line of at most 50 columns,
+/// a blank second line and a body wrapped at 72 columns. New commits need a body; when an
+/// existing message is edited, a missing body is only a warning, since older commits may
+/// not have one.
+pub fn check(message: &str, require_body: bool) -> MessageCheck {
+    let mut check = MessageCheck::default();
+    let lines: Vec<&str> = message.lines().collect();
+
+    let subject = lines.first().copied().unwrap_or("");
+    if subject.trim().is_empty() {
+        check.errors.push("The subject line is empty".to_string());
+    } else if subject.width() > SUBJECT_LIMIT {
+        check.warnings.push(format!(
+            "The subject line is longer than {} columns",
+            SUBJECT_LIMIT
+        ));
+    }
+
+    if lines.get(1).is_some_and(|line| !line.trim().is_empty()) {
+        check
+            .errors
+            .push("The second line must be blank".to_string());
+    }
+
+    let trailers = &lines[lines.len() - trailer_count(&lines)..];
+    for trailer in trailers {
+        if let Some((key, value)) = trailer.split_once(':') {
+            if value.trim().is_empty() {
+                check.errors.push(format!("The {} trailer is empty", key));
+            }
+        }
+    }
+
+    let body = &lines[lines.len().min(1)..lines.len() - trailers.len()];
+    if body.iter().all(|line| line.trim().is_empty()) {
+        if require_body {
+            check.errors.push("A message body is required".to_string());
+        } else {
+            check.warnings.push("The message has no body".to_string());
+        }
+    }
+    let long_lines = body.iter().filter(|line| line.width() > BODY_LIMIT).count();
+    if long_lines > 0 {
+        check.warnings.push(format!(
+            "{} body line(s) longer than {} columns",
+            long_lines, BODY_LIMIT
+        ));
+    }
+
+    check
+}
+
+fn is_trailer(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(key, value)| {
+        !key.is_empty()
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && (value.is_empty() || value.starts_with(' '))
+    })
+}
+
+// Number of lines at the end of the message forming a trailer block, which must be
+// a paragraph of its own after the subject
+fn trailer_count(lines: &[&str]) -> usize {
+    let end = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let start = lines[..end]
+        .iter()
+        .rposition(|line| line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    if start > 0 && lines[start..end].iter().all(|line| is_trailer(line)) {
+        lines.len() - start
+    } else {
+        0
+    }
+}
+
+/// Appends a `key: value` trailer, starting a trailer block if the message has none.
+/// An empty value leaves the trailer for the user to complete.
+pub fn add_trailer(message: &str, key: &str, value: &str) -> String {
+    let trailer = format!("{}: {}", key, value);
+    let message = message.trim_end();
+    if !value.is_empty() && message.lines().any(|line| line == trailer) {
+        return message.to_string();
+    }
+
+    let lines: Vec<&str> = message.lines().collect();
+    if !lines.is_empty() && trailer_count(&lines) > 0 {
+        format!("{}\n{}", message, trailer)
+    } else {
+        format!("{}\n\n{}", message, trailer)
+    }
+}
+
+/// The `Name <email>` of the committer, for sign-off trailers like `git commit -s`.
+pub fn user_identity(repo_path: &str) -> Result<String, String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    identity::resolve(&repo, Role::Committer).map(|identity| identity.to_string())
+}
+
+/// Strips trailing whitespace and repeated blank lines, like `git commit --cleanup=whitespace`.
+pub fn cleanup(message: &str) -> String {
+    git2::message_prettify(message, None).unwrap_or_else(|_| message.to_string())
+}
+
+/// Lets the user edit `message` in their editor through `.git/COMMIT_EDITMSG`, like
+/// `git commit` does. The terminal must be out of raw mode while this runs.
+pub fn edit_in_editor(repo_path: &str, message: &str) -> Result<String, String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&path, format!("{}\n{}", message.trim_end(), EDITOR_HELP))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    // Run through the shell so that editors configured with arguments work
+    let editor = editor_command(&repo);
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(&path)
+        .status()
+        .map_err(|e| format!("Failed to start editor '{}': {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", editor, status));
+    }
+
+    let edited = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let message = git2::message_prettify(edited, Some(b'#'))
+        .map_err(|e| format!("Failed to clean up commit message: {}", e))?;
+    Ok(message.trim_end().to_string())
+}
+
+// Same lookup order as git: GIT_EDITOR, core.editor, VISUAL, EDITOR
+fn editor_command(repo: &Repository) -> String {
+    let non_empty = |value: String| (!value.trim().is_empty()).then_some(value);
+    env::var("GIT_EDITOR")
+        .ok()
+        .and_then(non_empty)
+        .or_else(|| {
+            repo.config()
+                .ok()?
+                .get_string("core.editor")
+                .ok()
+                .and_then(non_empty)
+        })
+        .or_else(|| env::var("VISUAL").ok().and_then(non_empty))
+        .or_else(|| env::var("EDITOR").ok().and_then(non_empty))
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+const MAX_HISTORY: usize = 50;
+
+/// Conventional commit types offered by the type picker, with their meaning.
+pub const COMMIT_TYPES: &[(&str, &str)] = &[
+    ("feat", "A new feature"),
+    ("fix", "A bug fix"),
+    ("docs", "Documentation only changes"),
+    ("style", "Formatting, no code change"),
+    (
+        "refactor",
+        "A code change that neither fixes a bug nor adds a feature",
+    ),
+    ("perf", "A code change that improves performance"),
+    ("test", "Adding or correcting tests"),
+    ("build", "Build system or dependency changes"),
+    ("ci", "CI configuration changes"),
+    ("chore", "Other changes that don't modify source or tests"),
+    ("revert", "Reverts a previous commit"),
+];
+
+/// The message to start new commits with: the file named by `commit.template`, or a
+/// `.gitmessage` at the root of the working tree. Comment lines are dropped.
+pub fn load_template(repo_path: &str) -> Option<String> {
+    let repo = Repository::open(repo_path).ok()?;
+    let workdir = repo.workdir()?.to_path_buf();
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("commit.template").ok())
+        .map(|path| workdir.join(path)); // Relative paths are relative to the working tree
+    let path = configured.unwrap_or_else(|| workdir.join(".gitmessage"));
+
+    let template = fs::read_to_string(&path).ok()?;
+    let template = git2::message_prettify(template, Some(b'#')).ok()?;
+    let template = template.trim_end();
+    (!template.is_empty()).then(|| template.to_string())
+}
+
+fn history_path(repo: &Repository) -> std::path::PathBuf {
+    repo.path().join("ezgit").join("commit_history")
+}
+
+/// Messages of earlier commits made with ezgit in this repository, oldest first.
+pub fn load_history(repo_path: &str) -> Vec<String> {
+    Repository::open(repo_path)
+        .ok()
+        .and_then(|repo| fs::read_to_string(history_path(&repo)).ok())
+        .map(|contents| {
+            contents
+                .split('\0') // Messages span lines, so entries are NUL separated
+                .filter(|message| !message.trim().is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Remembers a committed message for recall in later commit prompts.
+pub fn save_to_history(repo_path: &str, message: &str) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let path = history_path(&repo);
+
+    let message = message.trim_end().to_string();
+    let mut history = load_history(repo_path);
+    history.retain(|entry| *entry != message);
+    history.push(message);
+    let skip = history.len().saturating_sub(MAX_HISTORY);
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+    }
+    fs::write(&path, history[skip..].join("\0"))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Length of a `type(scope)!: ` prefix at the start of the subject, if there is one
+fn conventional_prefix_len(subject: &str) -> Option<usize> {
+    let colon = subject.find(": ")?;
+    let prefix = &subject[..colon];
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let commit_type = match prefix.split_once('(') {
+        Some((commit_type, scope)) if scope.ends_with(')') && scope.len() > 1 => commit_type,
+        Some(_) => return None,
+        None => prefix,
+    };
+    (!commit_type.is_empty() && commit_type.chars().all(|c| c.is_ascii_lowercase()))
+        .then_some(colon + 2)
+}
+
+/// The scope of a conventional commit subject like `fix(parser): ...`.
+pub fn parse_scope(subject: &str) -> Option<&str> {
+    let prefix = &subject[..conventional_prefix_len(subject)?];
+    let (_, scope) = prefix.split_once('(')?;
+    scope.split_once(')').map(|(scope, _)| scope)
+}
+
+/// Replaces the conventional prefix of the subject line with `type(scope): `.
+pub fn set_conventional_prefix(message: &str, commit_type: &str, scope: Option<&str>) -> String {
+    let (subject, rest) = match message.split_once('\n') {
+        Some((subject, rest)) => (subject, Some(rest)),
+        None => (message, None),
+    };
+    let description = &subject[conventional_prefix_len(subject).unwrap_or(0)..];
+    let subject = match scope {
+        Some(scope) => format!("{}({}): {}", commit_type, scope, description),
+        None => format!("{}: {}", commit_type, description),
+    };
+    match rest {
+        Some(rest) => format!("{}\n{}", subject, rest),
+        None => subject,
+    }
+}
+
+/// Scopes to offer in the scope picker: those used in `subjects`, most used first,
+/// followed by the top-level directories of the project.
+pub fn scope_candidates(subjects: &[String], directories: &[String]) -> Vec<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for scope in subjects.iter().filter_map(|subject| parse_scope(subject)) {
+        match counts.iter_mut().find(|(existing, _)| existing == scope) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((scope.to_string(), 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count)); // Stable, so ties keep the most recent first
+
+    let mut scopes: Vec<String> = counts.into_iter().map(|(scope, _)| scope).collect();
+    for directory in directories {
+        if !scopes.contains(directory) {
+            scopes.push(directory.clone());
+        }
+    }
+    scopes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_requires_a_subject_a_blank_line_and_a_body() {
+        let check = check("", true);
+        assert!(check
+            .errors
+            .contains(&"The subject line is empty".to_string()));
+        assert!(check
+            .errors
+            .contains(&"A message body is required".to_string()));
+
+        let check = super::check("Subject\nNo blank line", true);
+        assert_eq!(check.errors, vec!["The second line must be blank"]);
+
+        let check = super::check("Subject\n\nBody", true);
+        assert!(check.errors.is_empty() && check.warnings.is_empty());
+    }
+
+    #[test]
+    fn a_missing_body_is_a_warning_when_it_isnt_required() {
+        let check = check("Subject\n\nSigned-off-by: A <a@example.com>", false);
+        assert!(check.errors.is_empty());
+        assert_eq!(check.warnings, vec!["The message has no body"]);
+    }
+
+    #[test]
+    fn check_warns_about_long_lines() {
+        let message = format!(
+            "{}\n\n{}\n{}",
+            "s".repeat(51),
+            "b".repeat(73),
+            "b".repeat(72)
+        );
+        let check = check(&message, true);
+        assert!(check.errors.is_empty());
+        assert_eq!(
+            check.warnings,
+            vec![
+                "The subject line is longer than 50 columns",
+                "1 body line(s) longer than 72 columns",
+            ]
+        );
+    }
+
+    #[test]
+    fn trailers_are_not_a_body() {
+        let check = check("Subject\n\nSigned-off-by: A <a@x>", true);
+        assert_eq!(check.errors, vec!["A message body is required"]);
+
+        let check = super::check("Subject\n\nBody\n\nCo-authored-by:", true);
+        assert_eq!(check.errors, vec!["The Co-authored-by trailer is empty"]);
+    }
+
+    #[test]
+    fn add_trailer_joins_an_existing_block_once() {
+        let message = add_trailer("Subject\n\nBody\n", SIGNED_OFF_BY, "A <a@x>");
+        assert_eq!(message, "Subject\n\nBody\n\nSigned-off-by: A <a@x>");
+        let message = add_trailer(&message, CO_AUTHORED_BY, "B <b@x>");
+        assert_eq!(
+            message,
+            "Subject\n\nBody\n\nSigned-off-by: A <a@x>\nCo-authored-by: B <b@x>"
+        );
+        assert_eq!(add_trailer(&message, SIGNED_OFF_BY, "A <a@x>"), message);
+    }
+
+    #[test]
+    fn conventional_prefixes_are_parsed_and_replaced() {
+        assert_eq!(parse_scope("fix(parser): handle EOF"), Some("parser"));
+        assert_eq!(parse_scope("feat!: breaking"), None);
+        assert_eq!(parse_scope("Fix(parser): not conventional"), None);
+        assert_eq!(
+            set_conventional_prefix("fix(parser): handle EOF\n\nBody", "feat", Some("lexer")),
+            "feat(lexer): handle EOF\n\nBody"
+        );
+        assert_eq!(
+            set_conventional_prefix("Handle EOF", "fix", None),
+            "fix: Handle EOF"
+        );
+    }
+
+    #[test]
+    fn scope_candidates_put_the_most_used_first() {
+        let subjects: Vec<String> = ["fix(ui): a", "feat(git): b", "fix(ui): c", "docs: d"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let directories = vec!["src".to_string(), "git".to_string()];
+        assert_eq!(
+            scope_candidates(&subjects, &directories),
+            vec!["ui", "git", "src"]
+        );
+    }
+}
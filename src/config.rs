@@ -0,0 +1,271 @@
+use crate::git_commands;
+use crate::keymap;
+use crate::theme::{self, ThemeSpec};
+use log::LevelFilter;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+use toml::Table;
+
+const REPO_CONFIG_FILE: &str = ".ezgit.toml";
+
+/// Settings from `~/.config/ezgit/config.toml`, overridden by `.ezgit.toml` at the root
+/// of the repository.
+#[derive(Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub log: LogConfig,
+    pub git: GitConfig,
+    pub layout: LayoutConfig,
+    pub theme: ThemeConfig,
+    pub themes: BTreeMap<String, ThemeSpec>, // User themes by name
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub file: String,  // Read on startup only
+    pub level: String, // off, error, warn, info, debug or trace
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            file: "debug.log".to_string(),
+            level: "debug".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitConfig {
+    pub remote: String,               // Remote to push to
+    pub merge_target: Option<String>, // Branch to merge into; main, or master if there is no main
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            merge_target: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    pub commit_log_percent: u16, // Height of the Commit Log; the Branches panel gets the rest
+    pub details_percent: u16,    // Share of the Commit Details; the Commit Log gets the rest
+    pub side_by_side_width: u16, // Terminal width from which the details go beside the log
+    pub preview: bool,           // Preview the selected commit's details in the main view
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            commit_log_percent: 70,
+            details_percent: 60,
+            side_by_side_width: 140,
+            preview: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub name: String, // A built-in theme or one under [themes]
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            name: "dark".to_string(),
+        }
+    }
+}
+
+impl Config {
+    pub fn log_level(&self) -> LevelFilter {
+        LevelFilter::from_str(&self.log.level).unwrap_or(LevelFilter::Debug)
+    }
+
+    /// The branch to merge into: the configured one, or `main` if it exists and `master` if not.
+    pub fn merge_target(&self, branches: &[String]) -> String {
+        match &self.git.merge_target {
+            Some(target) => target.clone(),
+            None if branches.iter().any(|branch| branch == "main") => "main".to_string(),
+            None => "master".to_string(),
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.log.file.trim().is_empty() {
+            errors.push("log.file must not be empty".to_string());
+        }
+        if LevelFilter::from_str(&self.log.level).is_err() {
+            errors.push(format!(
+                "log.level '{}' must be one of off, error, warn, info, debug or trace",
+                self.log.level
+            ));
+        }
+        if self.git.remote.trim().is_empty() {
+            errors.push("git.remote must not be empty".to_string());
+        }
+        if self
+            .git
+            .merge_target
+            .as_ref()
+            .is_some_and(|target| target.trim().is_empty())
+        {
+            errors.push("git.merge_target must not be empty".to_string());
+        }
+        if !(10..=90).contains(&self.layout.commit_log_percent) {
+            errors.push(format!(
+                "layout.commit_log_percent {} must be between 10 and 90",
+                self.layout.commit_log_percent
+            ));
+        }
+        if !(10..=90).contains(&self.layout.details_percent) {
+            errors.push(format!(
+                "layout.details_percent {} must be between 10 and 90",
+                self.layout.details_percent
+            ));
+        }
+        if !(40..=1000).contains(&self.layout.side_by_side_width) {
+            errors.push(format!(
+                "layout.side_by_side_width {} must be between 40 and 1000",
+                self.layout.side_by_side_width
+            ));
+        }
+        for (name, spec) in &self.themes {
+            if let Err(err) = spec.build() {
+                errors.push(format!("themes.{}: {}", name, err));
+            }
+        }
+        errors
+    }
+}
+
+/// The global config file: `config.toml` in the ezgit config directory.
+pub fn global_path() -> Option<PathBuf> {
+    keymap::config_dir().map(|directory| directory.join("config.toml"))
+}
+
+// .ezgit.toml at the root of the working directory, wherever in it `repo_path` is
+fn repo_config_path(repo_path: &str) -> PathBuf {
+    let workdir = git_commands::find_workdir(repo_path).unwrap_or_else(|| repo_path.to_string());
+    PathBuf::from(workdir).join(REPO_CONFIG_FILE)
+}
+
+// Config files in the order they apply, later ones overriding earlier ones
+fn config_paths(repo_path: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = global_path().into_iter().collect();
+    paths.push(repo_config_path(repo_path));
+    paths
+}
+
+/// Reads the global and repository config files. Missing files are fine; invalid ones are
+/// reported with every problem found.
+pub fn load(repo_path: &str) -> Result<Config, String> {
+    let mut merged = Table::new();
+    let mut errors = Vec::new();
+    for path in config_paths(repo_path) {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        // Check each file on its own so that errors name the file they are in
+        let checked = contents
+            .parse::<Table>()
+            .map_err(|e| vec![e.message().to_string()])
+            .and_then(|table| {
+                let config: Config = toml::Value::Table(table.clone())
+                    .try_into()
+                    .map_err(|e: toml::de::Error| vec![e.message().to_string()])?;
+                let problems = config.validate();
+                if problems.is_empty() {
+                    Ok(table)
+                } else {
+                    Err(problems)
+                }
+            });
+        match checked {
+            Ok(table) => merge_tables(&mut merged, table),
+            Err(problems) => errors.extend(
+                problems
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", path.display(), problem)),
+            ),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    let config: Config = toml::Value::Table(merged)
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_string())?;
+
+    // The theme may be defined in a different file than the one that picks it
+    theme::find(&config.theme.name, &config.themes)?;
+    Ok(config)
+}
+
+// Overlay `overrides` on `base`, merging sections key by key
+fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(section)), toml::Value::Table(values)) => {
+                merge_tables(section, values)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Notices changes to the config files and `keys.toml` by their modification times.
+pub struct ConfigWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl ConfigWatcher {
+    pub fn new(repo_path: &str) -> Self {
+        Self {
+            files: config_paths(repo_path)
+                .into_iter()
+                .chain(keymap::keys_path())
+                .map(|path| {
+                    let modified = modified_time(&path);
+                    (path, modified)
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether any config file was created, changed or removed since the last call.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, modified) in &mut self.files {
+            let current = modified_time(path);
+            if current != *modified {
+                *modified = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,48 @@
+use crate::diff::CommitDetails;
+use crate::git_commands;
+use git2::Oid;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+const CAPACITY: usize = 100; // Commits kept; diffs of large commits can be big
+
+/// Preview details of recently shown commits by commit ID, so moving through the Commit Log
+/// with the preview open doesn't recompute their diffs. A commit's diff never changes, so
+/// entries stay valid across refreshes; only the elapsed time in the header gets older.
+/// Signatures aren't checked for the preview, so opening a commit reads it afresh.
+#[derive(Default)]
+pub struct DetailsCache {
+    entries: HashMap<Oid, Rc<CommitDetails>>,
+    order: VecDeque<Oid>, // Oldest first, dropped when the cache is full
+}
+
+impl DetailsCache {
+    /// The preview details of the commit `revision` names, computed if they aren't cached yet.
+    pub fn get(&mut self, repo_path: &str, revision: &str) -> Result<Rc<CommitDetails>, String> {
+        let oid = git_commands::resolve_commit(repo_path, revision)?;
+        if let Some(details) = self.entries.get(&oid) {
+            return Ok(Rc::clone(details));
+        }
+
+        let details = Rc::new(git_commands::get_commit_preview(
+            repo_path,
+            &oid.to_string(),
+        )?);
+        if self.order.len() == CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.push_back(oid);
+        self.entries.insert(oid, Rc::clone(&details));
+        Ok(details)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,629 @@
+use git2::{Delta, Diff, DiffDelta, DiffFindOptions, Patch};
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub content: String,
+    pub syntax: Vec<SyntaxSpan>, // Highlighted fragments of `content`, empty if not highlighted
+    pub emphasis: Vec<(usize, usize)>, // Byte ranges of `content` changed relative to the paired line
+}
+
+/// A highlighted byte range of a diff line, colored as RGB.
+#[derive(Clone, Debug)]
+pub struct SyntaxSpan {
+    pub start: usize,
+    pub end: usize,
+    pub color: (u8, u8, u8),
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffHunk {
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    TypeChange,
+    Untracked,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileDiff {
+    pub path: String,
+    pub old_path: Option<String>, // Source path of renames and copies
+    pub status: FileStatus,
+    pub old_mode: u32,
+    pub new_mode: u32,
+    pub binary: Option<(u64, u64)>, // Old and new size in bytes of a binary file
+    pub submodule: Option<(String, String)>, // Old and new commit of a submodule
+    pub hunks: Vec<DiffHunk>,
+}
+
+const MODE_SYMLINK: u32 = 0o120000;
+const MODE_SUBMODULE: u32 = 0o160000;
+
+impl FileDiff {
+    /// Path shown in the file header, including the source of renames and copies.
+    pub fn display_path(&self) -> String {
+        match &self.old_path {
+            Some(old_path) => format!("{} → {}", old_path, self.path),
+            None => self.path.clone(),
+        }
+    }
+
+    /// One-line description of what happened to the file, e.g. "renamed from a.rs, mode change 100644 → 100755".
+    pub fn status_line(&self) -> String {
+        let mut parts = vec![match self.status {
+            FileStatus::Added => "added".to_string(),
+            FileStatus::Deleted => "deleted".to_string(),
+            FileStatus::Modified => "modified".to_string(),
+            FileStatus::Untracked => "untracked".to_string(),
+            FileStatus::Renamed => format!(
+                "renamed from {}",
+                self.old_path.as_deref().unwrap_or("unknown")
+            ),
+            FileStatus::Copied => format!(
+                "copied from {}",
+                self.old_path.as_deref().unwrap_or("unknown")
+            ),
+            FileStatus::TypeChange => format!(
+                "type change ({} → {})",
+                mode_kind(self.old_mode),
+                mode_kind(self.new_mode)
+            ),
+        }];
+
+        let both_exist = self.old_mode != 0 && self.new_mode != 0;
+        if both_exist && self.old_mode != self.new_mode && self.status != FileStatus::TypeChange {
+            parts.push(format!(
+                "mode change {:o} → {:o}",
+                self.old_mode, self.new_mode
+            ));
+        }
+
+        if let Some((old_size, new_size)) = self.binary {
+            parts.push(match self.status {
+                FileStatus::Added | FileStatus::Untracked => {
+                    format!("binary, {}", format_size(new_size))
+                }
+                FileStatus::Deleted => format!("binary, {}", format_size(old_size)),
+                _ => format!(
+                    "binary, {} → {}",
+                    format_size(old_size),
+                    format_size(new_size)
+                ),
+            });
+        }
+
+        if let Some((old_commit, new_commit)) = &self.submodule {
+            parts.push(format!(
+                "submodule {}..{}",
+                short_id(old_commit),
+                short_id(new_commit)
+            ));
+        }
+
+        parts.join(", ")
+    }
+
+    pub fn added(&self) -> usize {
+        self.count(LineKind::Added)
+    }
+
+    pub fn deleted(&self) -> usize {
+        self.count(LineKind::Removed)
+    }
+
+    fn count(&self, kind: LineKind) -> usize {
+        self.hunks
+            .iter()
+            .flat_map(|hunk| hunk.lines.iter())
+            .filter(|line| line.kind == kind)
+            .count()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiffLayout {
+    Unified,
+    SideBySide,
+}
+
+/// Everything shown in the commit details view.
+#[derive(Clone, Debug)]
+pub struct CommitDetails {
+    pub title: String,
+    pub revision: Option<String>, // Revision holding the new side of the diff, `None` for the working tree and index
+    pub old_revision: Option<String>, // Revision holding the old side, `None` for the index and root commits
+    pub header: String,
+    pub files: Vec<FileDiff>,
+}
+
+/// A single rendered row of the commit details view.
+#[derive(Clone, Debug)]
+pub enum DetailLine {
+    Text(String),
+    Stat {
+        file: usize,        // Index of the file in `CommitDetails::files`
+        label: String,      // Padded path and change count
+        added_bar: usize,   // Length of the '+' histogram bar
+        deleted_bar: usize, // Length of the '-' histogram bar
+    },
+    FileHeader(usize, String),
+    FileStatus(String),
+    HunkHeader(String),
+    Diff(DiffLine),
+    Split(Option<DiffLine>, Option<DiffLine>), // Old and new side of a side-by-side row
+}
+
+/// Collects the deltas of `diff` into files and hunks, keeping git's file order.
+/// Renames and copies are detected before collecting.
+pub fn collect_file_diffs(diff: &mut Diff) -> Result<Vec<FileDiff>, String> {
+    diff.find_similar(Some(DiffFindOptions::new().renames(true).copies(true)))
+        .map_err(|e| format!("Failed to detect renames: {}", e))?;
+
+    let mut files = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let patch = Patch::from_diff(diff, idx).map_err(|e| e.to_string())?;
+        // The patch has loaded the file contents, so its delta knows about binary files
+        let mut file = match &patch {
+            Some(patch) => file_from_delta(&patch.delta()),
+            None => file_from_delta(&diff.get_delta(idx).ok_or("Missing diff delta")?),
+        };
+
+        // Submodule bumps only produce a "Subproject commit" pseudo-diff, which the status line replaces
+        let Some(patch) = patch.filter(|_| file.submodule.is_none() && file.binary.is_none())
+        else {
+            files.push(file);
+            continue;
+        };
+
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx).map_err(|e| e.to_string())?;
+            let header = String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string();
+
+            let mut lines = Vec::with_capacity(line_count);
+            for line_idx in 0..line_count {
+                let line = patch
+                    .line_in_hunk(hunk_idx, line_idx)
+                    .map_err(|e| e.to_string())?;
+                let kind = match line.origin() {
+                    '+' => LineKind::Added,
+                    '-' => LineKind::Removed,
+                    ' ' => LineKind::Context,
+                    _ => continue, // Skip "\ No newline at end of file" markers
+                };
+                let content = String::from_utf8_lossy(line.content())
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string();
+
+                lines.push(DiffLine {
+                    kind,
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    content,
+                    syntax: Vec::new(),
+                    emphasis: Vec::new(),
+                });
+            }
+            mark_word_changes(&mut lines);
+            file.hunks.push(DiffHunk { header, lines });
+        }
+
+        files.push(file);
+    }
+
+    Ok(files)
+}
+
+fn file_from_delta(delta: &DiffDelta) -> FileDiff {
+    let file_path = |file: git2::DiffFile| file.path().map(|path| path.display().to_string());
+    let path = file_path(delta.new_file())
+        .or_else(|| file_path(delta.old_file()))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let status = match delta.status() {
+        Delta::Added => FileStatus::Added,
+        Delta::Deleted => FileStatus::Deleted,
+        Delta::Renamed => FileStatus::Renamed,
+        Delta::Copied => FileStatus::Copied,
+        Delta::Typechange => FileStatus::TypeChange,
+        Delta::Untracked => FileStatus::Untracked,
+        _ => FileStatus::Modified,
+    };
+    let old_path = match status {
+        FileStatus::Renamed | FileStatus::Copied => file_path(delta.old_file()),
+        _ => None,
+    };
+
+    let old_mode = u32::from(delta.old_file().mode());
+    let new_mode = u32::from(delta.new_file().mode());
+
+    let is_binary =
+        delta.flags().is_binary() || delta.old_file().is_binary() || delta.new_file().is_binary();
+    let binary = is_binary.then(|| (delta.old_file().size(), delta.new_file().size()));
+
+    let submodule = (old_mode == MODE_SUBMODULE || new_mode == MODE_SUBMODULE).then(|| {
+        (
+            delta.old_file().id().to_string(),
+            delta.new_file().id().to_string(),
+        )
+    });
+
+    FileDiff {
+        path,
+        old_path,
+        status,
+        old_mode,
+        new_mode,
+        binary,
+        submodule,
+        hunks: Vec::new(),
+    }
+}
+
+fn mode_kind(mode: u32) -> &'static str {
+    match mode {
+        MODE_SYMLINK => "symlink",
+        MODE_SUBMODULE => "submodule",
+        0 => "none",
+        _ => "file",
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(7)]
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Flattens the details into the rows displayed by the commit details view.
+/// With `stat_only`, only the header and the diffstat are included.
+pub fn build_detail_lines(
+    details: &CommitDetails,
+    layout: DiffLayout,
+    stat_only: bool,
+) -> Vec<DetailLine> {
+    let mut lines: Vec<DetailLine> = details
+        .header
+        .lines()
+        .map(|line| DetailLine::Text(line.to_string()))
+        .collect();
+
+    lines.push(DetailLine::Text(String::new()));
+    lines.extend(diffstat_lines(&details.files));
+    if stat_only {
+        return lines;
+    }
+
+    lines.push(DetailLine::Text(String::new()));
+    lines.push(DetailLine::Text("Diff:".to_string()));
+    for (index, file) in details.files.iter().enumerate() {
+        lines.push(DetailLine::Text(String::new()));
+        lines.push(DetailLine::FileHeader(index, file.display_path()));
+        lines.push(DetailLine::FileStatus(file.status_line()));
+        for hunk in &file.hunks {
+            lines.push(DetailLine::HunkHeader(hunk.header.clone()));
+            match layout {
+                DiffLayout::Unified => {
+                    lines.extend(hunk.lines.iter().cloned().map(DetailLine::Diff));
+                }
+                DiffLayout::SideBySide => lines.extend(split_rows(&hunk.lines)),
+            }
+        }
+    }
+
+    lines
+}
+
+const MAX_STAT_PATH_WIDTH: usize = 50;
+const MAX_STAT_BAR_WIDTH: usize = 40;
+
+// One line per file like `git diff --stat`, followed by the totals
+fn diffstat_lines(files: &[FileDiff]) -> Vec<DetailLine> {
+    let paths: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let path = file.display_path();
+            let char_count = path.chars().count();
+            if char_count > MAX_STAT_PATH_WIDTH {
+                // Keep the end of long paths, which holds the file name
+                let skipped = char_count - (MAX_STAT_PATH_WIDTH - 3);
+                format!("...{}", path.chars().skip(skipped).collect::<String>())
+            } else {
+                path
+            }
+        })
+        .collect();
+    let path_width = paths
+        .iter()
+        .map(|path| path.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max_changes = files
+        .iter()
+        .map(|file| file.added() + file.deleted())
+        .max()
+        .unwrap_or(0);
+    let count_width = max_changes.to_string().len();
+
+    // Scale bars down only when the largest change does not fit
+    let scale = |count: usize| {
+        if max_changes <= MAX_STAT_BAR_WIDTH {
+            count
+        } else {
+            (count * MAX_STAT_BAR_WIDTH).div_ceil(max_changes)
+        }
+    };
+
+    let mut lines: Vec<DetailLine> = files
+        .iter()
+        .zip(paths)
+        .enumerate()
+        .map(|(index, (file, path))| {
+            let padding = " ".repeat(path_width - path.chars().count());
+            match file.binary {
+                Some((old_size, new_size)) => DetailLine::Stat {
+                    file: index,
+                    label: format!(
+                        " {}{} | Bin {} → {}",
+                        path,
+                        padding,
+                        format_size(old_size),
+                        format_size(new_size)
+                    ),
+                    added_bar: 0,
+                    deleted_bar: 0,
+                },
+                None => DetailLine::Stat {
+                    file: index,
+                    label: format!(
+                        " {}{} | {:>width$} ",
+                        path,
+                        padding,
+                        file.added() + file.deleted(),
+                        width = count_width
+                    ),
+                    added_bar: scale(file.added()),
+                    deleted_bar: scale(file.deleted()),
+                },
+            }
+        })
+        .collect();
+
+    let added: usize = files.iter().map(|file| file.added()).sum();
+    let deleted: usize = files.iter().map(|file| file.deleted()).sum();
+    lines.push(DetailLine::Text(format!(
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" },
+        added,
+        if added == 1 { "" } else { "s" },
+        deleted,
+        if deleted == 1 { "" } else { "s" },
+    )));
+    lines
+}
+
+// Returns the ranges of consecutive removed lines followed by added lines in a hunk
+fn change_blocks(lines: &[DiffLine]) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].kind == LineKind::Context {
+            i += 1;
+            continue;
+        }
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == LineKind::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == LineKind::Added {
+            i += 1;
+        }
+        blocks.push((removed_start..added_start, added_start..i));
+    }
+    blocks
+}
+
+// Pair removed lines with added lines so that both sides line up
+fn split_rows(lines: &[DiffLine]) -> Vec<DetailLine> {
+    let mut rows = Vec::new();
+    let mut last = 0;
+    for (removed, added) in change_blocks(lines) {
+        rows.extend(
+            lines[last..removed.start]
+                .iter()
+                .map(|line| DetailLine::Split(Some(line.clone()), Some(line.clone()))),
+        );
+        for i in 0..removed.len().max(added.len()) {
+            let old = (i < removed.len()).then(|| lines[removed.start + i].clone());
+            let new = (i < added.len()).then(|| lines[added.start + i].clone());
+            rows.push(DetailLine::Split(old, new));
+        }
+        last = added.end;
+    }
+    rows.extend(
+        lines[last..]
+            .iter()
+            .map(|line| DetailLine::Split(Some(line.clone()), Some(line.clone()))),
+    );
+    rows
+}
+
+type ByteRanges = Vec<(usize, usize)>;
+
+// Longest token sequences compared per line pair; longer lines are left without emphasis
+const MAX_WORD_DIFF_TOKENS: usize = 400;
+
+/// Marks the changed words of each removed line paired with an added line.
+fn mark_word_changes(lines: &mut [DiffLine]) {
+    for (removed, added) in change_blocks(lines) {
+        for i in 0..removed.len().min(added.len()) {
+            let old_idx = removed.start + i;
+            let new_idx = added.start + i;
+            if let Some((old_emphasis, new_emphasis)) =
+                word_diff(&lines[old_idx].content, &lines[new_idx].content)
+            {
+                lines[old_idx].emphasis = old_emphasis;
+                lines[new_idx].emphasis = new_emphasis;
+            }
+        }
+    }
+}
+
+// Splits text into words, whitespace runs and single punctuation characters
+fn tokenize(text: &str) -> Vec<Range<usize>> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut previous_class = None;
+    for (idx, c) in text.char_indices() {
+        let current = class(c);
+        match tokens.last_mut() {
+            Some(token) if previous_class == Some(current) && current != 2 => {
+                token.end = idx + c.len_utf8();
+            }
+            _ => tokens.push(idx..idx + c.len_utf8()),
+        }
+        previous_class = Some(current);
+    }
+    tokens
+}
+
+/// Returns the changed byte ranges of `old` and `new`, or `None` if the lines share
+/// nothing but whitespace and punctuation, in which case emphasis would only be noise.
+fn word_diff(old: &str, new: &str) -> Option<(ByteRanges, ByteRanges)> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len() > MAX_WORD_DIFF_TOKENS || new_tokens.len() > MAX_WORD_DIFF_TOKENS {
+        return None;
+    }
+
+    // Longest common subsequence table over tokens
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+    let mut table = vec![vec![0u16; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_common = vec![false; n];
+    let mut new_common = vec![false; m];
+    let mut shares_word = false;
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+            old_common[i] = true;
+            new_common[j] = true;
+            shares_word |= old[old_tokens[i].clone()]
+                .chars()
+                .any(|c| c.is_alphanumeric());
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    if !shares_word {
+        return None;
+    }
+    Some((
+        changed_ranges(&old_tokens, &old_common),
+        changed_ranges(&new_tokens, &new_common),
+    ))
+}
+
+// Merges adjacent changed tokens into contiguous byte ranges
+fn changed_ranges(tokens: &[Range<usize>], common: &[bool]) -> ByteRanges {
+    let mut ranges: ByteRanges = Vec::new();
+    for (token, _) in tokens.iter().zip(common).filter(|(_, &common)| !common) {
+        match ranges.last_mut() {
+            Some(last) if last.1 == token.start => last.1 = token.end,
+            _ => ranges.push((token.start, token.end)),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_splits_words_spaces_and_punctuation() {
+        let text = "let x_1  = f(a);";
+        let tokens: Vec<&str> = tokenize(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(
+            tokens,
+            vec!["let", " ", "x_1", "  ", "=", " ", "f", "(", "a", ")", ";"]
+        );
+    }
+
+    #[test]
+    fn word_diff_marks_only_the_changed_words() {
+        let (old, new) = word_diff("let count = 1;", "let total = 2;").unwrap();
+        assert_eq!(old, vec![(4, 9), (12, 13)]);
+        assert_eq!(new, vec![(4, 9), (12, 13)]);
+    }
+
+    #[test]
+    fn word_diff_skips_lines_sharing_only_punctuation() {
+        assert_eq!(word_diff("foo();", "bar();"), None);
+    }
+
+    #[test]
+    fn word_diff_handles_multibyte_text() {
+        let (old, new) = word_diff("héllo wörld", "héllo welt").unwrap();
+        assert_eq!(old, vec![(7, 13)]);
+        assert_eq!(new, vec![(7, 11)]);
+    }
+}
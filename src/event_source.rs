@@ -0,0 +1,244 @@
+use crate::input::{self, Action, ClickTracker};
+use crate::keymap::{self, KeyContext, Keymap};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200); // Same as the terminal's key poll
+
+/// Where the event loop gets its actions from.
+pub trait EventSource {
+    /// Waits briefly for the next action. `None` means nothing happened in time, or a key
+    /// only started a sequence.
+    fn next_action(
+        &mut self,
+        keymap: &mut Keymap,
+        contexts: &[KeyContext],
+    ) -> io::Result<Option<Action>>;
+
+    /// Whether the source has run out of actions.
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+/// Key presses and mouse events in the terminal. Keys are looked up in the keymap.
+#[derive(Default)]
+pub struct TerminalSource {
+    clicks: ClickTracker,
+}
+
+impl EventSource for TerminalSource {
+    fn next_action(
+        &mut self,
+        keymap: &mut Keymap,
+        contexts: &[KeyContext],
+    ) -> io::Result<Option<Action>> {
+        input::handle_user_input(keymap, contexts, &mut self.clicks)
+    }
+}
+
+/// Actions from a session file, like one written by `SessionRecorder`.
+pub struct ScriptSource {
+    actions: VecDeque<Action>,
+}
+
+impl ScriptSource {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let script = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&script).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Reads one action per line, as written by `format_action`. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut actions = VecDeque::new();
+        for (number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = parse_action(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            actions.extend(parsed);
+        }
+        Ok(Self { actions })
+    }
+}
+
+impl EventSource for ScriptSource {
+    fn next_action(
+        &mut self,
+        _keymap: &mut Keymap,
+        _contexts: &[KeyContext],
+    ) -> io::Result<Option<Action>> {
+        Ok(self.actions.pop_front())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// Actions sent from another thread. The source is finished once every sender is dropped.
+pub struct ChannelSource {
+    receiver: Receiver<Action>,
+    disconnected: bool,
+}
+
+impl ChannelSource {
+    pub fn new(receiver: Receiver<Action>) -> Self {
+        Self {
+            receiver,
+            disconnected: false,
+        }
+    }
+}
+
+impl EventSource for ChannelSource {
+    fn next_action(
+        &mut self,
+        _keymap: &mut Keymap,
+        _contexts: &[KeyContext],
+    ) -> io::Result<Option<Action>> {
+        match self.receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(action) => Ok(Some(action)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                self.disconnected = true;
+                Ok(None)
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.disconnected
+    }
+}
+
+/// Writes the actions of a session to a file that `ScriptSource` can replay, for bug
+/// reports. Each action is flushed right away, so a crash doesn't lose the steps to it.
+pub struct SessionRecorder {
+    file: File,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let mut file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        writeln!(
+            file,
+            "# ezgit-rs session, replay with: ezgit-rs --replay {}",
+            path.display()
+        )
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, action: &Action) -> io::Result<()> {
+        writeln!(self.file, "{}", format_action(action))?;
+        self.file.flush()
+    }
+}
+
+/// One line of a session file: the action's name in `keys.toml`, `type` or `paste` followed
+/// by the text as a quoted string, a mouse action followed by the column and row, or `resize`
+/// followed by the width and height.
+pub fn format_action(action: &Action) -> String {
+    match action {
+        Action::TextInput(c) => format!("type {:?}", c.to_string()),
+        Action::Paste(text) => format!("paste {:?}", text),
+        Action::Click(column, row) => format!("click {} {}", column, row),
+        Action::DoubleClick(column, row) => format!("double_click {} {}", column, row),
+        Action::ScrollUp(column, row) => format!("scroll_up {} {}", column, row),
+        Action::ScrollDown(column, row) => format!("scroll_down {} {}", column, row),
+        Action::Drag(column, row) => format!("drag {} {}", column, row),
+        Action::Release(column, row) => format!("release {} {}", column, row),
+        Action::Resize(width, height) => format!("resize {} {}", width, height),
+        action => keymap::action_name(action)
+            .map_or_else(|| format!("# unnamed action {:?}", action), String::from),
+    }
+}
+
+/// The actions on one line of a session file. `type` gives one action per character.
+pub fn parse_action(line: &str) -> Result<Vec<Action>, String> {
+    let (name, argument) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(name, argument)| (name, argument.trim()));
+    match name {
+        "type" => Ok(unquote(argument)?.chars().map(Action::TextInput).collect()),
+        "paste" => Ok(vec![Action::Paste(unquote(argument)?)]),
+        "click" => parse_position(argument).map(|(column, row)| vec![Action::Click(column, row)]),
+        "double_click" => {
+            parse_position(argument).map(|(column, row)| vec![Action::DoubleClick(column, row)])
+        }
+        "scroll_up" => {
+            parse_position(argument).map(|(column, row)| vec![Action::ScrollUp(column, row)])
+        }
+        "scroll_down" => {
+            parse_position(argument).map(|(column, row)| vec![Action::ScrollDown(column, row)])
+        }
+        "drag" => parse_position(argument).map(|(column, row)| vec![Action::Drag(column, row)]),
+        "release" => {
+            parse_position(argument).map(|(column, row)| vec![Action::Release(column, row)])
+        }
+        "resize" => {
+            parse_position(argument).map(|(width, height)| vec![Action::Resize(width, height)])
+        }
+        _ if !argument.is_empty() => Err(format!("unexpected text after '{}'", name)),
+        _ => keymap::named_action(name)
+            .map(|action| vec![action])
+            .ok_or(format!("unknown action '{}'", name)),
+    }
+}
+
+// Two numbers separated by whitespace: a column and a row, or a width and a height
+fn parse_position(text: &str) -> Result<(u16, u16), String> {
+    let numbers: Vec<u16> = text
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("expected a column and a row, found '{}'", text))?;
+    match numbers[..] {
+        [column, row] => Ok((column, row)),
+        _ => Err(format!("expected a column and a row, found '{}'", text)),
+    }
+}
+
+// Undo the escapes of a `{:?}`-formatted string
+fn unquote(text: &str) -> Result<String, String> {
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or(format!("expected a quoted string, found '{}'", text))?;
+
+    let mut unquoted = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some('u') => {
+                let code: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                code.strip_prefix('{')
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or(format!("invalid escape '\\u{}}}'", code))?
+            }
+            Some(c) => return Err(format!("invalid escape '\\{}'", c)),
+            None => return Err("string ends with a backslash".to_string()),
+        };
+        unquoted.push(escaped);
+    }
+    Ok(unquoted)
+}
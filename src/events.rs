@@ -1,52 +1,149 @@
-use crate::app_state::{AppState, CommitState, InputMode, Panel, UIState};
-use crate::{git_commands, input};
+use crate::app_state::{
+    AppState, BlameState, CommitKind, CommitState, ConventionalPicker, FileContentsState,
+    FileTreeState, HookOutputState, HookRetry, IdentityPrompt, InputMode, ListCursor,
+    NotificationKind, Panel, PickerStage, RunningOperation, UIState,
+};
+use crate::diff::CommitDetails;
+use crate::git_commands::TreeEntryKind;
+use crate::hooks::{HookEvent, HookRun, Hooks};
+use crate::identity::{self, IdentityScope};
+use crate::keymap::KeyContext;
+use crate::{commit_message, git_commands, input};
 use log::debug;
+use std::sync::mpsc;
+use std::thread;
 
+const MAX_SCOPE_COMMITS: usize = 200; // Recent commits searched for conventional commit scopes
+const SCROLL_LINES: usize = 3; // Lines moved per step of the scroll wheel
 
-pub fn handle_event(app_state: &mut AppState) -> Result<bool, std::io::Error> {
+/// Work that an action leaves to the event loop, because it needs the terminal.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Effect {
+    Quit,
+    OpenEditor, // Edit the commit message in $EDITOR
+}
+
+// Actions that only move through what is shown
+fn is_scrolling(action: &input::Action) -> bool {
+    matches!(
+        action,
+        input::Action::NavigateUp
+            | input::Action::NavigateDown
+            | input::Action::NavigatePageUp
+            | input::Action::NavigatePageDown
+            | input::Action::NavigateTop
+            | input::Action::NavigateBottom
+            | input::Action::ScrollUp(..)
+            | input::Action::ScrollDown(..)
+    )
+}
+
+/// Applies an action to the state, as if its key had been pressed. Nothing here reads input
+/// or touches the terminal; the returned effects are for the event loop to carry out.
+///
+/// This is not a pure function of the state: the git operations an action asks for run from
+/// here and change the repository. Quick ones run right away, and those that run hooks go to
+/// a worker thread whose result `poll_operation` picks up. Only what needs the terminal is
+/// left to the event loop as an effect.
+pub fn update(app_state: &mut AppState, action: input::Action) -> Vec<Effect> {
+    // The views are laid out for the new size on the next draw, whatever the mode
+    if let input::Action::Resize(width, height) = action {
+        app_state.dragging = None; // The divider being dragged may have moved
+        debug!("Terminal resized to {}x{}", width, height);
+        return Vec::new();
+    }
+    // Git operations don't overlap, so only scrolling works until the running one is done
+    if app_state.operation.is_some() && !is_scrolling(&action) {
+        debug!("Ignoring {:?} while an operation runs", action);
+        return Vec::new();
+    }
     match app_state.input_mode {
-        InputMode::Command => handle_command_mode(app_state),
-        InputMode::Text => handle_text_mode(app_state),
+        InputMode::Command => handle_command_mode(app_state, action),
+        InputMode::Text => handle_text_mode(app_state, action),
     }
 }
 
-fn handle_text_mode(app_state: &mut AppState) -> Result<bool, std::io::Error> {
-    match input::handle_user_input(true)? {
-        Some(input::Action::TextInput(c)) => match app_state.ui_state {
-            UIState::CommitMessage => {
-                if let Some(commit_state) = &mut app_state.commit_state {
-                    commit_state.message.push(c); // Add character to commit message
-                }
-            }
-            UIState::CreateBranch => {
-                app_state.branch_name.push(c); // Add character to branch name
-            }
-            UIState::FilterByFile => {
-                app_state.branch_name.push(c);
-            }
-            _ => {}
-        },
-        Some(input::Action::Backspace) => match app_state.ui_state {
-            UIState::CommitMessage => {
+/// Loads the details of the selected commit into the preview, if the preview is on and the
+/// selection has moved since it was loaded. The event loop calls this once no keys are
+/// coming in, so holding an arrow key down doesn't load every commit on the way.
+pub fn refresh_preview(app_state: &mut AppState) {
+    if !app_state.config.layout.preview || app_state.ui_state != UIState::Normal {
+        return;
+    }
+    let Some(commit_hash) = app_state.selected_commit_hash() else {
+        return; // Nothing in the Commit Log
+    };
+    if app_state.preview_commit.as_ref() == Some(&commit_hash) {
+        return;
+    }
+    match app_state
+        .details_cache
+        .get(&app_state.repo_path, &commit_hash)
+    {
+        Ok(details) => app_state.set_selected_commit_details(details),
+        Err(err) => {
+            debug!("Error fetching commit details for the preview: {}", err);
+            app_state.clear_selected_commit_details();
+        }
+    }
+    app_state.preview_commit = Some(commit_hash);
+}
+
+/// Keymap sections to look keys up in, most specific first.
+pub fn key_contexts(app_state: &AppState) -> &'static [KeyContext] {
+    match (&app_state.input_mode, app_state.ui_state) {
+        (InputMode::Text, _) => &[KeyContext::TextInput],
+        (InputMode::Command, UIState::CommitDetails) => {
+            &[KeyContext::CommitDetails, KeyContext::Normal]
+        }
+        (InputMode::Command, _) => &[KeyContext::Normal],
+    }
+}
+
+fn handle_text_mode(app_state: &mut AppState, action: input::Action) -> Vec<Effect> {
+    // The conventional commit picker takes over the keys while it is open
+    if let Some(picker) = app_state
+        .commit_state
+        .as_mut()
+        .and_then(|commit_state| commit_state.picker.as_mut())
+    {
+        match action {
+            input::Action::NavigateUp => picker.select_previous(),
+            input::Action::NavigateDown => picker.select_next(),
+            input::Action::Confirm => choose_picker_option(app_state),
+            input::Action::Cancel | input::Action::PickCommitType => {
                 if let Some(commit_state) = &mut app_state.commit_state {
-                    commit_state.message.pop(); // Remove last character from commit message
+                    commit_state.picker = None;
                 }
             }
-            UIState::CreateBranch => {
-                app_state.branch_name.pop(); // Remove last character from branch name
-            }
-            UIState::FilterByFile => {
-                app_state.branch_name.pop();
-            }
             _ => {}
-        },
-        Some(input::Action::Confirm) => {
+        }
+        return Vec::new();
+    }
+
+    // Enter breaks lines in the commit message, which is submitted with Ctrl+S instead
+    let action = match action {
+        input::Action::Confirm if app_state.ui_state == UIState::CommitMessage => {
+            input::Action::NewLine
+        }
+        input::Action::Submit => input::Action::Confirm,
+        action => action,
+    };
+
+    match action {
+        input::Action::Confirm => {
             match app_state.ui_state {
                 UIState::CommitMessage => {
                     // Transition to ConfirmCommit state after entering commit message
                     if let Some(commit_state) = &app_state.commit_state {
-                        if commit_state.message.trim().is_empty() {
-                            debug!("Cannot confirm: commit message is empty");
+                        let check = commit_state.check();
+                        if !check.errors.is_empty() {
+                            let reason = check.errors.join(", ");
+                            debug!("Cannot confirm: {}", reason);
+                            app_state.notify(
+                                NotificationKind::Error,
+                                format!("Cannot commit yet: {}", reason),
+                            );
                         } else {
                             app_state.ui_state = UIState::ConfirmCommit; // Move to confirmation state
                             debug!("Transitioned to ConfirmCommit state");
@@ -55,77 +152,519 @@ fn handle_text_mode(app_state: &mut AppState) -> Result<bool, std::io::Error> {
                 }
                 UIState::ConfirmCommit => {
                     // Perform the commit and push operation
-                    if let Some(commit_state) = &app_state.commit_state {
-                        match git_commands::commit_and_push(".", &commit_state.message) {
-                            Ok(_) => {
-                                app_state.ui_state = UIState::Normal; // Reset to normal state
-                                app_state.input_mode = InputMode::Command; // Back to command mode
-                                app_state.commit_log = git_commands::get_commit_log("."); // Refresh commit log
-                                debug!("Changes committed and pushed successfully.");
-                            }
-                            Err(err) => {
-                                debug!("Error during commit and push: {}", err);
-                                app_state.ui_state = UIState::Normal; // Reset on failure
-                                app_state.input_mode = InputMode::Command;
-                            }
-                        }
-                    }
+                    finish_commit(app_state);
                 }
                 UIState::CreateBranch => {
-                    if let Err(err) =
-                        git_commands::create_and_switch_branch(".", &app_state.branch_name)
-                    {
-                        debug!("Error creating branch: {}", err);
+                    let new_branch = app_state.branch_input.text().trim().to_string();
+                    if let Err(err) = git_commands::create_and_switch_branch(
+                        &app_state.repo_path,
+                        &app_state.config.git.remote,
+                        &new_branch,
+                    ) {
+                        app_state.notify(
+                            NotificationKind::Error,
+                            format!("Failed to create branch '{}': {}", new_branch, err),
+                        );
                     } else {
-                        app_state.branches = git_commands::get_branches("."); // Refresh branch list
-                        debug!(
-                            "Branch '{}' created and switched successfully",
-                            app_state.branch_name
+                        app_state.branches = git_commands::get_branches(&app_state.repo_path); // Refresh branch list
+                        app_state.refresh_status();
+                        app_state.notify(
+                            NotificationKind::Success,
+                            format!("Created and switched to branch '{}'", new_branch),
                         );
+                        app_state.branch_name = new_branch;
                     }
+                    app_state.branch_input.clear();
                     app_state.ui_state = UIState::Normal; // Return to normal state
                     app_state.input_mode = InputMode::Command;
                 }
                 UIState::FilterByFile => {
-                    let file_path = app_state.branch_name.trim().to_string();
+                    // Use the typed path, e.g. of a deleted file, unless a match was picked
+                    let typed = app_state.filter_input.text().trim().to_string();
+                    let file_path = if app_state.filter_match_picked
+                        && !app_state.tracked_paths.contains(&typed)
+                    {
+                        app_state
+                            .selected_filter_path()
+                            .map(String::from)
+                            .unwrap_or(typed)
+                    } else {
+                        typed
+                    };
+                    app_state.ui_state = UIState::Normal;
+                    app_state.input_mode = InputMode::Command;
                     if !file_path.is_empty() {
-                        let filtered_commits = git_commands::get_commits_for_file(".", &file_path);
-                        if !filtered_commits.is_empty() {
-                            app_state.commit_log = filtered_commits;
-                            app_state.selected_index = 0; // Reset selected index to the top
-                            app_state.visible_range = (0, 0); // Reset visible range
-                            app_state.update_visible_range(); // Update visible range dynamically
-                            debug!("Filtered commits for file: {}", file_path);
-                        } else {
-                            debug!("No commits found for file: {}", file_path);
+                        match git_commands::get_commits_for_file(
+                            &app_state.repo_path,
+                            "HEAD",
+                            &file_path,
+                        ) {
+                            Ok(filtered_commits) if !filtered_commits.is_empty() => {
+                                app_state.notify(
+                                    NotificationKind::Info,
+                                    format!(
+                                        "Showing the {} commits that changed {}; press r for all",
+                                        filtered_commits.len(),
+                                        file_path
+                                    ),
+                                );
+                                app_state.commit_log = filtered_commits;
+                                app_state.selected_index = 0; // Reset selected index to the top
+                                app_state.visible_range = (0, 0); // Reset visible range
+                                app_state.update_visible_range(); // Update visible range dynamically
+                            }
+                            Ok(_) => show_error(
+                                app_state,
+                                format!("No commits found for file: {}", file_path),
+                            ),
+                            Err(e) => show_error(app_state, e),
                         }
                     } else {
                         debug!("No file path provided for filtering");
                     }
-                    app_state.branch_name.clear(); // Clear the input
-                    app_state.ui_state = UIState::Normal;
-                    app_state.input_mode = InputMode::Command;
+                    app_state.filter_input.clear(); // Clear the input
                 }
+                UIState::SetIdentity => save_identity(app_state),
                 _ => {
                     debug!("Confirm action ignored in current UIState");
                 }
             }
         }
 
-        Some(input::Action::Cancel) => {
+        input::Action::SignOff if app_state.ui_state == UIState::CommitMessage => {
+            match commit_message::user_identity(&app_state.repo_path) {
+                Ok(identity) => {
+                    add_commit_trailer(app_state, commit_message::SIGNED_OFF_BY, &identity)
+                }
+                Err(err) => {
+                    app_state.notify(NotificationKind::Error, format!("Cannot sign off: {}", err))
+                }
+            }
+        }
+        input::Action::AddCoAuthor if app_state.ui_state == UIState::CommitMessage => {
+            add_commit_trailer(app_state, commit_message::CO_AUTHORED_BY, "");
+        }
+        input::Action::PickCommitType if app_state.ui_state == UIState::CommitMessage => {
+            let mut subjects: Vec<String> = app_state
+                .commit_state
+                .iter()
+                .flat_map(|commit_state| commit_state.history.iter().rev())
+                .filter_map(|message| message.lines().next().map(String::from))
+                .collect();
+            subjects.extend(git_commands::get_recent_summaries(
+                &app_state.repo_path,
+                MAX_SCOPE_COMMITS,
+            ));
+            let directories: Vec<String> =
+                git_commands::get_tree_entries(&app_state.repo_path, "HEAD", "")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|entry| entry.kind == TreeEntryKind::Directory)
+                    .map(|entry| entry.name)
+                    .collect();
+
+            let scopes = commit_message::scope_candidates(&subjects, &directories);
+            if let Some(commit_state) = &mut app_state.commit_state {
+                commit_state.picker = Some(ConventionalPicker::new(scopes));
+            }
+        }
+        input::Action::OpenEditor if app_state.ui_state == UIState::CommitMessage => {
+            return vec![Effect::OpenEditor];
+        }
+        input::Action::Complete | input::Action::NavigateUp | input::Action::NavigateDown
+            if app_state.ui_state == UIState::SetIdentity =>
+        {
+            if let Some(prompt) = &mut app_state.identity_prompt {
+                prompt.editing_email = !prompt.editing_email;
+            }
+        }
+        input::Action::Complete if app_state.ui_state == UIState::FilterByFile => {
+            app_state.complete_filter_input();
+        }
+        input::Action::NavigateUp if app_state.ui_state == UIState::FilterByFile => {
+            app_state.select_previous_filter_match();
+        }
+        input::Action::NavigateDown if app_state.ui_state == UIState::FilterByFile => {
+            app_state.select_next_filter_match();
+        }
+        // Up on the first line and Down on the last line of the commit message recall history
+        input::Action::NavigateUp => match &mut app_state.commit_state {
+            Some(commit_state) if app_state.ui_state == UIState::CommitMessage => {
+                if !commit_state.message.move_up() {
+                    commit_state.recall_previous();
+                }
+            }
+            _ => {
+                if let Some(text_input) = app_state.active_text_input() {
+                    text_input.move_up();
+                }
+            }
+        },
+        input::Action::NavigateDown => match &mut app_state.commit_state {
+            Some(commit_state) if app_state.ui_state == UIState::CommitMessage => {
+                if !commit_state.message.move_down() {
+                    commit_state.recall_next();
+                }
+            }
+            _ => {
+                if let Some(text_input) = app_state.active_text_input() {
+                    text_input.move_down();
+                }
+            }
+        },
+        input::Action::Cancel => {
             app_state.ui_state = UIState::Normal;
             app_state.commit_state = None;
-            app_state.branch_name.clear();
+            app_state.identity_prompt = None;
+            app_state.filter_input.clear();
+            app_state.branch_input.clear();
             app_state.input_mode = InputMode::Command; // Switch back to Command Mode
         }
-        _ => {}
+        action => edit_text_input(app_state, action),
     }
-    Ok(false)
+    Vec::new()
 }
 
-pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Error> {
-    match input::handle_user_input(false)? {
-        Some(input::Action::Quit) => {
+/// Asks for a name and email on the first run, when git doesn't know who is committing.
+pub fn prompt_for_missing_identity(app_state: &mut AppState) {
+    if !identity::is_configured(&app_state.repo_path) {
+        open_identity_prompt(app_state, IdentityScope::Global);
+    }
+}
+
+fn open_identity_prompt(app_state: &mut AppState, scope: IdentityScope) {
+    let current = identity::current_author(&app_state.repo_path);
+    app_state.identity_prompt = Some(IdentityPrompt::new(scope, current));
+    app_state.ui_state = UIState::SetIdentity;
+    app_state.input_mode = InputMode::Text;
+}
+
+// Enter moves from the name to the email, and saves once both are filled in
+fn save_identity(app_state: &mut AppState) {
+    let Some(prompt) = &mut app_state.identity_prompt else {
+        return;
+    };
+    if !prompt.editing_email {
+        prompt.editing_email = true;
+        return;
+    }
+    if !prompt.is_complete() {
+        app_state.notify(
+            NotificationKind::Error,
+            "Cannot save the identity: a name and an email address are required",
+        );
+        return;
+    }
+
+    let result = identity::save(
+        &app_state.repo_path,
+        prompt.scope,
+        prompt.name.text(),
+        prompt.email.text(),
+    );
+    app_state.identity_prompt = None;
+    app_state.ui_state = UIState::Normal;
+    app_state.input_mode = InputMode::Command;
+    match result {
+        Ok(_) => app_state.notify(NotificationKind::Success, "Identity saved"),
+        Err(err) => show_error(app_state, err),
+    }
+}
+
+// Create, amend or reword the commit with the confirmed message
+fn finish_commit(app_state: &mut AppState) {
+    let Some(commit_state) = app_state.commit_state.take() else {
+        return;
+    };
+    let message = commit_message::cleanup(commit_state.message.text());
+    run_commit(app_state, commit_state.kind, message, true);
+}
+
+fn run_commit(app_state: &mut AppState, kind: CommitKind, message: String, verify: bool) {
+    app_state.input_mode = InputMode::Command; // Back to command mode
+    start_operation(app_state, HookRetry::Commit(kind, message), verify);
+}
+
+// Commit the staged changes as a fixup of the marked commit, optionally squashing it right away
+fn create_fixup(app_state: &mut AppState, autosquash: bool) {
+    let Some(target) = app_state.fixup_target.take() else {
+        return;
+    };
+    start_operation(app_state, HookRetry::Fixup { target, autosquash }, true);
+}
+
+// Run an operation with hooks on a worker thread; `poll_operation` picks up the hooks' output
+// and the result
+fn start_operation(app_state: &mut AppState, operation: HookRetry, verify: bool) {
+    app_state.ui_state = UIState::Normal;
+    app_state.hook_output = None;
+    let repo_path = app_state.repo_path.clone();
+    let remote = app_state.config.git.remote.clone();
+    let work = operation.clone();
+    let (sender, events) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        let mut hooks = Hooks::streaming(verify, sender);
+        let result = match &work {
+            HookRetry::Commit(CommitKind::New, message) => {
+                git_commands::commit_and_push(&repo_path, &remote, message, &mut hooks)
+            }
+            HookRetry::Commit(CommitKind::Amend, message) => {
+                git_commands::amend_head(&repo_path, message, &mut hooks)
+            }
+            HookRetry::Commit(CommitKind::Reword(revision), message) => {
+                git_commands::reword_commit(&repo_path, revision, message, &mut hooks)
+            }
+            HookRetry::Push => git_commands::push_to_remote(&repo_path, &remote, &mut hooks),
+            HookRetry::Fixup {
+                target,
+                autosquash: true,
+            } => git_commands::fixup_and_autosquash(&repo_path, target, &mut hooks),
+            HookRetry::Fixup { target, .. } => {
+                git_commands::create_fixup_commit(&repo_path, target, &mut hooks)
+            }
+            HookRetry::Merge { target } => {
+                git_commands::merge_into_branch(&repo_path, target, &mut hooks)
+            }
+        };
+        (result, hooks.runs)
+    });
+    app_state.operation = Some(RunningOperation {
+        operation,
+        events,
+        worker,
+    });
+}
+
+/// Shows the output of the hooks of the running operation, and finishes the operation once
+/// its worker is done. Returns whether anything changed.
+pub fn poll_operation(app_state: &mut AppState) -> bool {
+    let Some(running) = &app_state.operation else {
+        return false;
+    };
+    let events: Vec<HookEvent> = running.events.try_iter().collect();
+    let done = running.worker.is_finished();
+    let changed = !events.is_empty() || done;
+    for event in events {
+        // Open the hook panel once a hook starts
+        let output = app_state
+            .hook_output
+            .get_or_insert_with(|| HookOutputState::new(&[], None, None));
+        output.push_event(event);
+        app_state.ui_state = UIState::HookOutput;
+    }
+    if done {
+        if let Some(running) = app_state.operation.take() {
+            finish_operation(app_state, running);
+        }
+    }
+    changed
+}
+
+/// Waits for the running operation to finish, for when nothing else needs to happen meanwhile.
+pub fn wait_for_operation(app_state: &mut AppState) {
+    if let Some(running) = app_state.operation.take() {
+        finish_operation(app_state, running);
+    }
+}
+
+fn finish_operation(app_state: &mut AppState, running: RunningOperation) {
+    let RunningOperation {
+        operation, worker, ..
+    } = running;
+    let (result, runs) = match worker.join() {
+        Ok(outcome) => outcome,
+        Err(_) => (
+            Err("The operation stopped unexpectedly".to_string()),
+            Vec::new(),
+        ),
+    };
+    debug!("Operation finished: {:?}", result);
+
+    app_state.ui_state = UIState::Normal;
+    app_state.commit_log = git_commands::get_commit_log(&app_state.repo_path); // Refresh commit log
+    app_state.refresh_status();
+    let failed_hook = runs.iter().any(|run| !run.success);
+    match (operation, result) {
+        (operation, Ok(_)) => {
+            let done = match &operation {
+                HookRetry::Commit(kind, message) => {
+                    if let Err(err) = commit_message::save_to_history(&app_state.repo_path, message)
+                    {
+                        debug!("Error saving commit message history: {}", err);
+                    }
+                    match kind {
+                        CommitKind::New => {
+                            format!("Committed and pushed to {}", app_state.config.git.remote)
+                        }
+                        CommitKind::Amend => "Amended the last commit".to_string(),
+                        CommitKind::Reword(revision) => format!("Reworded {}", revision),
+                    }
+                }
+                HookRetry::Push => format!("Pushed to {}", app_state.config.git.remote),
+                HookRetry::Fixup {
+                    target,
+                    autosquash: true,
+                } => format!("Squashed a fixup commit into {}", target),
+                HookRetry::Fixup { target, .. } => format!("Created a fixup commit for {}", target),
+                HookRetry::Merge { target } => {
+                    let done = format!("Merged '{}' into '{}'", app_state.branch_name, target);
+                    app_state.branches = git_commands::get_branches(&app_state.repo_path);
+                    app_state.branch_name = target.clone(); // The merge leaves the target checked out
+                    app_state.error_message = None; // Clear any previous error messages
+                    done
+                }
+            };
+            app_state.notify(NotificationKind::Success, done);
+            show_hook_output(app_state, &runs, None, None);
+        }
+        (operation, Err(err)) if failed_hook => {
+            // Once committed, only the push is left to retry
+            let retry = match operation {
+                HookRetry::Commit(..) if runs.last().is_some_and(|run| run.name == "pre-push") => {
+                    HookRetry::Push
+                }
+                operation => operation,
+            };
+            show_hook_output(app_state, &runs, Some(err), Some(retry));
+        }
+        (HookRetry::Commit(..), Err(err)) => {
+            show_error(app_state, format!("Commit failed: {}", err))
+        }
+        (HookRetry::Push, Err(err)) => show_error(app_state, format!("Push failed: {}", err)),
+        (_, Err(err)) => show_error(app_state, err),
+    }
+}
+
+// Retry the operation stopped by a failing hook, this time without running the hooks that can stop it
+fn retry_without_hooks(app_state: &mut AppState) {
+    let Some(retry) = app_state.hook_output.take().and_then(|output| output.retry) else {
+        return;
+    };
+    debug!("Retrying without hooks");
+    start_operation(app_state, retry, false);
+}
+
+// Show what the hooks printed when one failed or had something to say
+fn show_hook_output(
+    app_state: &mut AppState,
+    runs: &[HookRun],
+    error: Option<String>,
+    retry: Option<HookRetry>,
+) {
+    let has_output = runs.iter().any(|run| !run.output.trim().is_empty());
+    if error.is_none() && !has_output {
+        app_state.hook_output = None;
+        return;
+    }
+    if let Some(err) = &error {
+        app_state.notify(NotificationKind::Error, err.clone());
+    }
+    app_state.hook_output = Some(HookOutputState::new(runs, error, retry));
+    app_state.ui_state = UIState::HookOutput;
+}
+
+// Remember whether the commit about to be rewritten was pushed, so the confirmation can
+// warn that the branch will need a force push
+fn check_rewrite(app_state: &mut AppState, revision: &str) {
+    app_state.rewrite_upstream =
+        match git_commands::upstream_containing(&app_state.repo_path, revision) {
+            Ok(upstream) => upstream,
+            Err(err) => {
+                debug!("Failed to compare with the upstream: {}", err);
+                None
+            }
+        };
+}
+
+// Open the commit message editor to amend HEAD or reword the selected commit
+fn edit_existing_commit(app_state: &mut AppState, kind: CommitKind) {
+    let revision = match &kind {
+        CommitKind::Reword(revision) => revision.clone(),
+        _ => "HEAD".to_string(),
+    };
+    check_rewrite(app_state, &revision);
+    match git_commands::get_commit_message(&app_state.repo_path, &revision) {
+        Ok(message) => {
+            let mut commit_state = CommitState::new(
+                kind,
+                &message,
+                commit_message::load_history(&app_state.repo_path),
+            );
+            commit_state.message.move_to_first_line_end();
+            app_state.commit_state = Some(commit_state);
+            app_state.ui_state = UIState::CommitMessage;
+            app_state.input_mode = InputMode::Text;
+        }
+        Err(err) => show_error(app_state, err),
+    }
+}
+
+// Apply the highlighted type, or type and scope, of the conventional commit picker
+fn choose_picker_option(app_state: &mut AppState) {
+    let Some(commit_state) = &mut app_state.commit_state else {
+        return;
+    };
+    let Some(picker) = &mut commit_state.picker else {
+        return;
+    };
+
+    match picker.stage {
+        PickerStage::Type => picker.choose_type(),
+        PickerStage::Scope => {
+            let message = commit_message::set_conventional_prefix(
+                commit_state.message.text(),
+                &picker.commit_type,
+                picker.selected_scope(),
+            );
+            commit_state.message.set_text(&message);
+            commit_state.message.move_to_first_line_end();
+            commit_state.picker = None;
+        }
+    }
+}
+
+fn add_commit_trailer(app_state: &mut AppState, key: &str, value: &str) {
+    if let Some(commit_state) = &mut app_state.commit_state {
+        let message = commit_message::add_trailer(commit_state.message.text(), key, value);
+        commit_state.message.set_text(&message);
+    }
+}
+
+// Applies a cursor movement or edit to the prompt on screen
+fn edit_text_input(app_state: &mut AppState, action: input::Action) {
+    let Some(text_input) = app_state.active_text_input() else {
+        return;
+    };
+    let previous_text = text_input.text().to_string();
+    match action {
+        input::Action::TextInput(c) => text_input.insert_char(c),
+        input::Action::Paste(text) => text_input.insert_str(&text),
+        input::Action::NewLine => text_input.new_line(),
+        input::Action::Backspace => text_input.backspace(),
+        input::Action::Delete => text_input.delete(),
+        input::Action::DeleteWordBackward => text_input.delete_word_backward(),
+        input::Action::DeleteToLineStart => text_input.delete_to_line_start(),
+        input::Action::CursorLeft => text_input.move_left(),
+        input::Action::CursorRight => text_input.move_right(),
+        input::Action::CursorWordLeft => text_input.move_word_left(),
+        input::Action::CursorWordRight => text_input.move_word_right(),
+        input::Action::CursorHome => text_input.move_home(),
+        input::Action::CursorEnd => text_input.move_end(),
+        input::Action::Undo => text_input.undo(),
+        _ => return,
+    }
+
+    // Re-rank the file filter suggestions only when the typed path changed
+    if app_state.ui_state == UIState::FilterByFile && app_state.filter_input.text() != previous_text
+    {
+        app_state.update_filter_matches();
+    }
+}
+
+#[allow(clippy::collapsible_match)] // Arms check the UI state inside, so other states ignore them
+fn handle_command_mode(app_state: &mut AppState, action: input::Action) -> Vec<Effect> {
+    match action {
+        input::Action::Quit => {
             match app_state.ui_state {
                 UIState::Normal => {
                     app_state.ui_state = UIState::ConfirmQuit; // Transition to ConfirmQuit state
@@ -134,13 +673,42 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
                 _ => debug!("Quit action ignored in current UIState"),
             }
         }
-        Some(input::Action::NavigateUp) => {
+        input::Action::NavigateUp => {
             match app_state.ui_state {
+                UIState::CommitDetails if app_state.diff_stat_only => {
+                    app_state.select_previous_diff_file();
+                }
+                UIState::Blame => {
+                    if let Some(blame) = &mut app_state.blame {
+                        blame.cursor.select_previous(1, blame.lines.len());
+                    }
+                }
+                UIState::FileTree => {
+                    if let Some(tree) = &mut app_state.file_tree {
+                        tree.cursor.select_previous(1, tree.entries.len());
+                    }
+                }
+                UIState::FileContents => {
+                    if let Some(contents) = &mut app_state.file_contents {
+                        contents.cursor.select_previous(1, contents.lines.len());
+                    }
+                }
+                UIState::HookOutput => {
+                    if let Some(output) = &mut app_state.hook_output {
+                        output.cursor.select_previous(1, output.lines.len());
+                    }
+                }
                 UIState::CommitDetails => {
                     // Scroll up in commit details
                     app_state.scroll_commit_details_up(1);
                     debug!("Scrolled up in commit details");
                 }
+                UIState::Messages => {
+                    app_state.messages_scroll = app_state.messages_scroll.saturating_sub(1);
+                }
+                UIState::KeyGuide => {
+                    app_state.key_guide_scroll = app_state.key_guide_scroll.saturating_sub(1);
+                }
                 UIState::Normal => match app_state.focused_panel {
                     Panel::CommitLog => app_state.select_previous(),
                     Panel::Branches => app_state.select_previous_branch(),
@@ -148,13 +716,38 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
                 _ => {}
             }
         }
-        Some(input::Action::NavigateDown) => {
+        input::Action::NavigateDown => {
             match app_state.ui_state {
+                UIState::CommitDetails if app_state.diff_stat_only => {
+                    app_state.select_next_diff_file();
+                }
+                UIState::Blame => {
+                    if let Some(blame) = &mut app_state.blame {
+                        blame.cursor.select_next(1, blame.lines.len());
+                    }
+                }
+                UIState::FileTree => {
+                    if let Some(tree) = &mut app_state.file_tree {
+                        tree.cursor.select_next(1, tree.entries.len());
+                    }
+                }
+                UIState::FileContents => {
+                    if let Some(contents) = &mut app_state.file_contents {
+                        contents.cursor.select_next(1, contents.lines.len());
+                    }
+                }
+                UIState::HookOutput => {
+                    if let Some(output) = &mut app_state.hook_output {
+                        output.cursor.select_next(1, output.lines.len());
+                    }
+                }
                 UIState::CommitDetails => {
                     // Scroll down in commit details
                     app_state.scroll_commit_details_down(1);
                     debug!("Scrolled down in commit details");
                 }
+                UIState::KeyGuide => app_state.key_guide_scroll += 1, // Clamped by apply_layout
+                UIState::Messages => app_state.messages_scroll += 1,
                 UIState::Normal => match app_state.focused_panel {
                     Panel::CommitLog => app_state.select_next(),
                     Panel::Branches => app_state.select_next_branch(),
@@ -162,23 +755,60 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
                 _ => {}
             }
         }
-        Some(input::Action::NavigateLeft) => {
+        input::Action::NavigateTop => app_state.jump_to_top(),
+        input::Action::NavigateBottom => app_state.jump_to_bottom(),
+        input::Action::NavigateLeft if app_state.ui_state == UIState::FileTree => {
+            if let Some(tree) = &app_state.file_tree {
+                if let Some(parent) = tree.parent_directory() {
+                    let revision = tree.revision.clone();
+                    browse_directory(app_state, &revision, &parent);
+                }
+            }
+        }
+        input::Action::NavigateRight if app_state.ui_state == UIState::FileTree => {
+            open_selected_tree_entry(app_state, false);
+        }
+        input::Action::NavigateLeft => {
             if matches!(app_state.focused_panel, Panel::CommitLog)
                 && app_state.horizontal_offset > 0
             {
                 app_state.horizontal_offset -= 1;
             }
         }
-        Some(input::Action::NavigateRight) => {
+        input::Action::NavigateRight => {
             if matches!(app_state.focused_panel, Panel::CommitLog) {
                 app_state.horizontal_offset += 1; // Increase the offset to scroll right
             }
         }
-        Some(input::Action::NavigatePageUp) => {
+        input::Action::NavigatePageUp => {
             match app_state.ui_state {
                 UIState::CommitDetails => {
                     app_state.page_up_commit_details(app_state.commit_details_visible_count);
                 }
+                UIState::Blame => {
+                    if let Some(blame) = &mut app_state.blame {
+                        let page = blame.cursor.visible_count;
+                        blame.cursor.select_previous(page, blame.lines.len());
+                    }
+                }
+                UIState::FileTree => {
+                    if let Some(tree) = &mut app_state.file_tree {
+                        let page = tree.cursor.visible_count;
+                        tree.cursor.select_previous(page, tree.entries.len());
+                    }
+                }
+                UIState::FileContents => {
+                    if let Some(contents) = &mut app_state.file_contents {
+                        let page = contents.cursor.visible_count;
+                        contents.cursor.select_previous(page, contents.lines.len());
+                    }
+                }
+                UIState::HookOutput => {
+                    if let Some(output) = &mut app_state.hook_output {
+                        let page = output.cursor.visible_count;
+                        output.cursor.select_previous(page, output.lines.len());
+                    }
+                }
                 UIState::Normal => match app_state.focused_panel {
                     Panel::CommitLog => app_state.jump_commit_log_up(),
                     Panel::Branches => app_state.jump_branches_up(),
@@ -186,11 +816,35 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
                 _ => {} // Ignore for other UI states
             }
         }
-        Some(input::Action::NavigatePageDown) => {
+        input::Action::NavigatePageDown => {
             match app_state.ui_state {
                 UIState::CommitDetails => {
                     app_state.page_down_commit_details(app_state.commit_details_visible_count);
                 }
+                UIState::Blame => {
+                    if let Some(blame) = &mut app_state.blame {
+                        let page = blame.cursor.visible_count;
+                        blame.cursor.select_next(page, blame.lines.len());
+                    }
+                }
+                UIState::FileTree => {
+                    if let Some(tree) = &mut app_state.file_tree {
+                        let page = tree.cursor.visible_count;
+                        tree.cursor.select_next(page, tree.entries.len());
+                    }
+                }
+                UIState::FileContents => {
+                    if let Some(contents) = &mut app_state.file_contents {
+                        let page = contents.cursor.visible_count;
+                        contents.cursor.select_next(page, contents.lines.len());
+                    }
+                }
+                UIState::HookOutput => {
+                    if let Some(output) = &mut app_state.hook_output {
+                        let page = output.cursor.visible_count;
+                        output.cursor.select_next(page, output.lines.len());
+                    }
+                }
                 UIState::Normal => match app_state.focused_panel {
                     Panel::CommitLog => app_state.jump_commit_log_down(),
                     Panel::Branches => app_state.jump_branches_down(),
@@ -198,103 +852,112 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
                 _ => {} // Ignore for other UI states
             }
         }
-        Some(input::Action::Select) => match app_state.ui_state {
+        input::Action::Select => match app_state.ui_state {
             UIState::ConfirmQuit => {
-                return Ok(true); // Exit the program
+                return vec![Effect::Quit];
             }
             UIState::CommitMessage => {
                 app_state.ui_state = UIState::ConfirmCommit;
             }
             UIState::ConfirmCommit => {
-                if let Some(commit_state) = &app_state.commit_state {
-                    match git_commands::commit_and_push(".", &commit_state.message) {
-                        Ok(_) => {
-                            app_state.ui_state = UIState::Normal;
-                            app_state.commit_log = git_commands::get_commit_log("."); // Refresh commit log
-                            debug!("Changes committed and pushed successfully.");
-                        }
-                        Err(err) => {
-                            debug!("Error during commit and push: {}", err);
-                            app_state.ui_state = UIState::Normal;
-                        }
-                    }
-                }
+                finish_commit(app_state);
+            }
+            UIState::ConfirmFixup => {
+                create_fixup(app_state, false);
             }
             UIState::Normal => {
                 match app_state.focused_panel {
                     Panel::CommitLog => {
-                        let selected_commit = &app_state.commit_log[app_state.selected_index];
-                        let commit_hash = selected_commit.split(" | ").next().unwrap_or("");
+                        let commit_hash = app_state.selected_commit_hash().unwrap_or_default();
 
-                        match git_commands::get_commit_details(".", commit_hash) {
+                        // Read afresh rather than from the preview cache, to check the signature
+                        match git_commands::get_commit_details(&app_state.repo_path, &commit_hash) {
                             Ok(details) => {
                                 app_state.set_selected_commit_details(details);
-                                app_state.ui_state = UIState::CommitDetails; // Transition to CommitDetails state
+                                app_state.details_from_log = true;
+                                app_state.open_view(UIState::CommitDetails); // Transition to CommitDetails state
                                 debug!("Showing commit details");
                             }
-                            Err(err) => {
-                                debug!("Error fetching commit details: {}", err);
-                            }
+                            Err(err) => app_state.notify(
+                                NotificationKind::Error,
+                                format!("Cannot show the commit: {}", err),
+                            ),
                         }
                     }
                     Panel::Branches => {
                         let selected_branch = app_state.branches[app_state.selected_branch].clone();
-                        match crate::git_commands::checkout_branch(".", &selected_branch) {
+                        match crate::git_commands::checkout_branch(
+                            &app_state.repo_path,
+                            &selected_branch,
+                        ) {
                             Ok(_) => {
-                                app_state.commit_log = crate::git_commands::get_commit_log("."); // Refresh commit log
-                                app_state.branches = crate::git_commands::get_branches("."); // Refresh branch list
-                                app_state.branch_name = selected_branch.clone(); // Update the current branch
-                                debug!("Switched to branch: {}", selected_branch);
-                            }
-                            Err(err) => {
-                                debug!("Error checking out branch: {}", err);
-                                app_state.ui_state = UIState::Error; // Transition to error state
-                                app_state.error_message = Some(err); // Store error message for display
+                                app_state.commit_log =
+                                    crate::git_commands::get_commit_log(&app_state.repo_path); // Refresh commit log
+                                app_state.branches =
+                                    crate::git_commands::get_branches(&app_state.repo_path); // Refresh branch list
+                                app_state.refresh_status();
+                                app_state.notify(
+                                    NotificationKind::Success,
+                                    format!("Switched to branch '{}'", selected_branch),
+                                );
+                                app_state.branch_name = selected_branch; // Update the current branch
                             }
+                            Err(err) => show_error(app_state, err),
                         }
                     }
                 }
             }
+            UIState::CommitDetails => {
+                app_state.open_selected_diff_file();
+                debug!(
+                    "Jumped to file {} of the diff",
+                    app_state.selected_diff_file
+                );
+            }
+            UIState::FileTree => {
+                open_selected_tree_entry(app_state, true);
+            }
+            UIState::Blame => {
+                let commit_id = app_state
+                    .blame
+                    .as_ref()
+                    .and_then(|blame| blame.selected_line())
+                    .map(|line| line.commit_id.clone())
+                    .unwrap_or_default();
+                if !commit_id.is_empty() {
+                    show_diff(
+                        app_state,
+                        git_commands::get_commit_details(&app_state.repo_path, &commit_id),
+                    );
+                }
+            }
             UIState::ConfirmMerge => {
-                // Determine the target branch (main or master)
-                let target_branch = if app_state.branches.contains(&"main".to_string()) {
-                    "main"
-                } else {
-                    "master"
-                };
+                // Determine the target branch (configured, or main or master)
+                let target_branch = app_state.config.merge_target(&app_state.branches);
 
                 // Attempt to merge into the target branch
-                match git_commands::merge_into_branch(".", target_branch) {
-                    Ok(_) => {
-                        debug!(
-                            "Successfully merged the current branch into '{}'",
-                            target_branch
-                        );
-                        app_state.commit_log = git_commands::get_commit_log("."); // Refresh commit log
-                        app_state.ui_state = UIState::Normal; // Return to normal state after merging
-                        app_state.error_message = None; // Clear any previous error messages
-                    }
-                    Err(err) => {
-                        debug!("Error merging into '{}': {}", target_branch, err);
-                        app_state.ui_state = UIState::Error; // Transition to error state
-                        app_state.error_message = Some(err); // Store the error message
-                    }
-                }
+                start_operation(
+                    app_state,
+                    HookRetry::Merge {
+                        target: target_branch,
+                    },
+                    true,
+                );
             }
 
             _ => {}
         },
-        Some(input::Action::SwitchPanel) => {
+        input::Action::SwitchPanel => {
             app_state.focus_next_panel();
         }
-        Some(input::Action::Refresh) => {
+        input::Action::Refresh => {
             // Refresh commit log
-            app_state.commit_log = crate::git_commands::get_commit_log(".");
+            app_state.commit_log = crate::git_commands::get_commit_log(&app_state.repo_path);
             app_state.selected_index = 0; // Reset commit selection
             app_state.visible_range = (0, app_state.visible_count); // Reset visible range for commits
 
             // Refresh branches
-            app_state.branches = crate::git_commands::get_branches(".");
+            app_state.branches = crate::git_commands::get_branches(&app_state.repo_path);
             app_state.selected_branch = app_state
                 .branches
                 .iter()
@@ -303,12 +966,12 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
             app_state.branch_visible_range = (0, app_state.branch_visible_count); // Reset visible range for branches
 
             // Reset commit details
-            app_state.selected_commit_details = None; // Clear selected commit details
-            app_state.commit_details_total_lines = 0; // Reset commit details total lines
+            app_state.clear_selected_commit_details(); // Clear selected commit details and lines
             app_state.commit_details_visible_range = (0, 0); // Reset commit details visible range
+            app_state.refresh_status();
         }
         // Handle Deselect (Esc key) for canceling actions
-        Some(input::Action::Deselect) => match app_state.ui_state {
+        input::Action::Deselect => match app_state.ui_state {
             UIState::ConfirmQuit => {
                 app_state.ui_state = UIState::Normal; // Cancel quit and return to normal state
                 debug!("Quit cancelled");
@@ -319,55 +982,456 @@ pub fn handle_command_mode(app_state: &mut AppState) -> Result<bool, std::io::Er
                 debug!("Workflow cancelled");
             }
             UIState::CommitDetails => {
-                app_state.ui_state = UIState::Normal; // Return to normal state
+                app_state.close_view(); // Return to the previous view
                 debug!("Exited commit details view");
             }
+            UIState::Blame | UIState::FileTree | UIState::FileContents => {
+                app_state.close_view();
+                debug!("Returned to the previous view");
+            }
             UIState::KeyGuide => {
                 app_state.ui_state = UIState::Normal; // Return to normal state
+                app_state.key_guide_scroll = 0;
                 debug!("Exited key guide view");
             }
+            UIState::Messages => {
+                app_state.ui_state = UIState::Normal;
+                app_state.messages_scroll = 0;
+            }
             UIState::ConfirmMerge => {
                 app_state.ui_state = UIState::Normal;
             }
+            UIState::ConfirmFixup => {
+                app_state.ui_state = UIState::Normal;
+                app_state.fixup_target = None;
+            }
+            UIState::HookOutput => {
+                app_state.ui_state = UIState::Normal;
+                app_state.hook_output = None;
+            }
+            UIState::Error if app_state.identity_prompt.is_some() => {
+                // The error was shown over the identity prompt, so go back to it
+                app_state.ui_state = UIState::SetIdentity;
+                app_state.input_mode = InputMode::Text;
+                app_state.error_message = None;
+            }
             UIState::Error => {
                 app_state.ui_state = UIState::Normal; // Return to Normal state
                 app_state.error_message = None; // Clear the error message
             }
+            UIState::Normal if app_state.compare_base.is_some() => {
+                app_state.compare_base = None;
+                debug!("Comparison cancelled");
+            }
             _ => {}
         },
 
-        Some(input::Action::CommitWork) => {
+        input::Action::CommitWork => {
             if app_state.ui_state == UIState::Normal {
                 app_state.ui_state = UIState::CommitMessage;
-                app_state.commit_state = Some(CommitState {
-                    message: String::new(),
-                });
+                let template =
+                    commit_message::load_template(&app_state.repo_path).unwrap_or_default();
+                let mut commit_state = CommitState::new(
+                    CommitKind::New,
+                    &template,
+                    commit_message::load_history(&app_state.repo_path),
+                );
+                commit_state.message.move_to_first_line_end(); // Templates start with the subject
+                app_state.commit_state = Some(commit_state);
                 app_state.input_mode = InputMode::Text;
             }
         }
 
-        Some(input::Action::CreateBranch) => {
+        input::Action::AmendCommit if app_state.ui_state == UIState::Normal => {
+            edit_existing_commit(app_state, CommitKind::Amend);
+        }
+        input::Action::RewordCommit if app_state.ui_state == UIState::Normal => {
+            if let Some(commit_hash) = app_state.selected_commit_hash() {
+                edit_existing_commit(app_state, CommitKind::Reword(commit_hash));
+            }
+        }
+        input::Action::FixupCommit if app_state.ui_state == UIState::Normal => {
+            app_state.fixup_target = app_state.selected_commit_hash();
+            if let Some(target) = app_state.fixup_target.clone() {
+                check_rewrite(app_state, &target);
+                app_state.ui_state = UIState::ConfirmFixup;
+            }
+        }
+        input::Action::BypassHooks if app_state.ui_state == UIState::HookOutput => {
+            retry_without_hooks(app_state);
+        }
+        input::Action::Autosquash if app_state.ui_state == UIState::ConfirmFixup => {
+            create_fixup(app_state, true);
+        }
+
+        input::Action::SetIdentity if app_state.ui_state == UIState::Normal => {
+            open_identity_prompt(app_state, IdentityScope::Repository);
+        }
+        input::Action::CreateBranch => {
             if app_state.ui_state == UIState::Normal {
                 app_state.ui_state = UIState::CreateBranch;
                 app_state.input_mode = InputMode::Text;
-                app_state.branch_name = String::new();
+                app_state.branch_input.clear();
             }
         }
-        Some(input::Action::ShowKeyGuide) => {
+        input::Action::ShowKeyGuide if app_state.ui_state == UIState::Normal => {
             app_state.ui_state = UIState::KeyGuide;
         }
-        Some(input::Action::MergeBranch) => {
+        input::Action::ShowMessages if app_state.ui_state == UIState::Normal => {
+            app_state.ui_state = UIState::Messages;
+            app_state.messages_scroll = app_state.notifications.len() as u16; // Newest last; clamped by apply_layout
+        }
+        input::Action::MergeBranch if app_state.ui_state == UIState::Normal => {
             app_state.ui_state = UIState::ConfirmMerge;
         }
-        Some(input::Action::FilterCommitsByFile) => {
+        input::Action::FilterCommitsByFile => {
             if app_state.ui_state == UIState::Normal {
                 app_state.ui_state = UIState::FilterByFile;
                 app_state.input_mode = InputMode::Text;
-                app_state.branch_name = String::new();
+                app_state.filter_input.clear();
+                app_state.tracked_paths = git_commands::get_tracked_paths(&app_state.repo_path);
+                app_state.update_filter_matches();
+            }
+        }
+        input::Action::NextFile if app_state.ui_state == UIState::CommitDetails => {
+            app_state.jump_to_next_file();
+        }
+        input::Action::PreviousFile if app_state.ui_state == UIState::CommitDetails => {
+            app_state.jump_to_previous_file();
+        }
+        input::Action::NextHunk if app_state.ui_state == UIState::CommitDetails => {
+            app_state.jump_to_next_hunk();
+        }
+        input::Action::PreviousHunk if app_state.ui_state == UIState::CommitDetails => {
+            app_state.jump_to_previous_hunk();
+        }
+        input::Action::ToggleSyntaxHighlight if app_state.ui_state == UIState::CommitDetails => {
+            app_state.toggle_syntax_highlight();
+            debug!("Syntax highlighting set to {}", app_state.syntax_highlight);
+        }
+        input::Action::ShowUnstagedChanges if app_state.ui_state == UIState::Normal => {
+            show_diff(
+                app_state,
+                git_commands::get_unstaged_changes(&app_state.repo_path),
+            );
+        }
+        input::Action::ShowStagedChanges if app_state.ui_state == UIState::Normal => {
+            show_diff(
+                app_state,
+                git_commands::get_staged_changes(&app_state.repo_path),
+            );
+        }
+        input::Action::MarkForCompare if app_state.ui_state == UIState::Normal => {
+            if let Some(revision) = app_state.selected_revision() {
+                match app_state.compare_base.take() {
+                    None => {
+                        app_state.notify(
+                            NotificationKind::Info,
+                            format!(
+                                "Marked {}; mark another commit or branch to compare with it",
+                                revision
+                            ),
+                        );
+                        app_state.compare_base = Some(revision);
+                    }
+                    Some(base) => {
+                        show_diff(
+                            app_state,
+                            git_commands::get_diff_between(&app_state.repo_path, &base, &revision),
+                        );
+                    }
+                }
+            }
+        }
+        input::Action::BlameFile if app_state.ui_state == UIState::CommitDetails => {
+            match app_state.selected_diff_file_revision() {
+                Some(Ok((revision, path))) => show_blame(app_state, &revision, &path, 0),
+                Some(Err(err)) => show_error(app_state, err),
+                None => {}
+            }
+        }
+        input::Action::BlameParent if app_state.ui_state == UIState::Blame => {
+            if let Some(line) = app_state
+                .blame
+                .as_ref()
+                .and_then(|blame| blame.selected_line())
+                .filter(|line| !line.commit_id.is_empty())
+                .cloned()
+            {
+                show_blame(
+                    app_state,
+                    &format!("{}^", line.commit_id),
+                    &line.original_path,
+                    line.original_line.saturating_sub(1),
+                );
+            }
+        }
+        input::Action::BrowseFiles if app_state.ui_state == UIState::Normal => {
+            if let Some(revision) = app_state.selected_revision() {
+                match git_commands::get_tree_entries(&app_state.repo_path, &revision, "") {
+                    Ok(entries) => {
+                        app_state.open_view(UIState::FileTree);
+                        app_state.file_tree =
+                            Some(FileTreeState::new(revision, String::new(), entries));
+                    }
+                    Err(err) => show_error(app_state, err),
+                }
+            }
+        }
+        input::Action::BlameFile
+            if matches!(
+                app_state.ui_state,
+                UIState::FileTree | UIState::FileContents
+            ) =>
+        {
+            if let Some((revision, path, line)) = selected_browsed_file(app_state) {
+                show_blame(app_state, &revision, &path, line);
+            }
+        }
+        input::Action::FileHistory
+            if matches!(
+                app_state.ui_state,
+                UIState::FileTree | UIState::FileContents
+            ) =>
+        {
+            if let Some((revision, path, _)) = selected_browsed_file(app_state) {
+                match git_commands::get_commits_for_file(&app_state.repo_path, &revision, &path) {
+                    Ok(filtered_commits) if filtered_commits.is_empty() => {
+                        app_state.notify(
+                            NotificationKind::Info,
+                            format!("No commits found for file: {}", path),
+                        );
+                    }
+                    Ok(filtered_commits) => {
+                        app_state.commit_log = filtered_commits;
+                        app_state.selected_index = 0;
+                        app_state.visible_range = (0, 0);
+                        app_state.update_visible_range();
+                        app_state.focused_panel = Panel::CommitLog;
+                        app_state.ui_state = UIState::Normal;
+                        debug!("Filtered commits for file: {} at {}", path, revision);
+                    }
+                    Err(e) => show_error(app_state, e),
+                }
+            }
+        }
+        input::Action::ToggleDiffStat if app_state.ui_state == UIState::CommitDetails => {
+            app_state.toggle_diff_stat_only();
+            debug!("Diffstat only set to {}", app_state.diff_stat_only);
+        }
+        input::Action::ToggleDiffLayout if app_state.ui_state == UIState::CommitDetails => {
+            app_state.toggle_diff_layout();
+            debug!("Diff layout set to {:?}", app_state.diff_layout);
+        }
+        input::Action::TogglePreview if app_state.ui_state == UIState::Normal => {
+            let preview = !app_state.config.layout.preview;
+            app_state.config.layout.preview = preview;
+            debug!("Preview set to {}", preview);
+            refresh_preview(app_state);
+        }
+        input::Action::Click(column, row) if app_state.ui_state == UIState::Normal => {
+            app_state.dragging = app_state.divider_at(column, row); // The borders at a divider move it
+            app_state.click_panel(column, row);
+        }
+        input::Action::Click(column, row) if app_state.ui_state == UIState::CommitDetails => {
+            app_state.dragging = app_state.divider_at(column, row);
+            // Show the clicked commit in place of the one being shown
+            let on_commit = app_state.click_panel(column, row);
+            if on_commit {
+                let commit_hash = app_state.selected_commit_hash().unwrap_or_default();
+                match git_commands::get_commit_details(&app_state.repo_path, &commit_hash) {
+                    Ok(details) => app_state.set_selected_commit_details(details),
+                    Err(err) => show_error(app_state, err),
+                }
+            }
+        }
+        input::Action::Drag(column, row) if app_state.dragging.is_some() => {
+            app_state.drag_divider(column, row);
+        }
+        input::Action::Release(column, row) if app_state.dragging.is_some() => {
+            app_state.drag_divider(column, row);
+            app_state.dragging = None;
+            debug!("Layout set to {:?}", app_state.config.layout);
+        }
+        input::Action::DoubleClick(column, row) if app_state.ui_state == UIState::Normal => {
+            // Open the commit, like Enter
+            let on_commit = app_state.click_panel(column, row)
+                && matches!(app_state.focused_panel, Panel::CommitLog);
+            if on_commit {
+                return handle_command_mode(app_state, input::Action::Select);
+            }
+        }
+        input::Action::ScrollUp(column, row) => {
+            scroll_at(app_state, column, row, input::Action::NavigateUp);
+        }
+        input::Action::ScrollDown(column, row) => {
+            scroll_at(app_state, column, row, input::Action::NavigateDown);
+        }
+
+        _ => {}
+    }
+    Vec::new()
+}
+
+// The scroll wheel scrolls the view under the pointer a few lines at a time, taking the
+// selection along only when it would leave the view. In the main view that focuses the panel
+// it is over, or scrolls the preview.
+fn scroll_at(app_state: &mut AppState, column: u16, row: u16, direction: input::Action) {
+    let down = direction == input::Action::NavigateDown;
+    if app_state.is_over_preview(column, row) {
+        if down {
+            app_state.scroll_commit_details_down(SCROLL_LINES);
+        } else {
+            app_state.scroll_commit_details_up(SCROLL_LINES);
+        }
+        return;
+    }
+    let cursor = match app_state.ui_state {
+        UIState::Normal => {
+            match app_state.panel_at(column, row) {
+                Some(panel) => app_state.focused_panel = panel,
+                None => return,
+            }
+            app_state.scroll_panel(SCROLL_LINES, down);
+            return;
+        }
+        UIState::Blame => app_state
+            .blame
+            .as_mut()
+            .map(|blame| (&mut blame.cursor, blame.lines.len())),
+        UIState::FileTree => app_state
+            .file_tree
+            .as_mut()
+            .map(|tree| (&mut tree.cursor, tree.entries.len())),
+        UIState::FileContents => app_state
+            .file_contents
+            .as_mut()
+            .map(|contents| (&mut contents.cursor, contents.lines.len())),
+        UIState::HookOutput => app_state
+            .hook_output
+            .as_mut()
+            .map(|output| (&mut output.cursor, output.lines.len())),
+        // Commit details, the diffstat, the key guide and messages move a line per step
+        _ => {
+            for _ in 0..SCROLL_LINES {
+                handle_command_mode(app_state, direction.clone());
+            }
+            return;
+        }
+    };
+    if let Some((cursor, len)) = cursor {
+        cursor.scroll(SCROLL_LINES, down, len);
+    }
+}
+
+// Open the details view for a diff, or report why it could not be computed
+fn show_diff(app_state: &mut AppState, details: Result<CommitDetails, String>) {
+    match details {
+        Ok(details) => {
+            debug!("Showing {}", details.title);
+            app_state.set_selected_commit_details(details);
+            app_state.details_from_log = false; // Not a commit the log could show
+            app_state.open_view(UIState::CommitDetails);
+        }
+        Err(err) => show_error(app_state, err),
+    }
+}
+
+// Open the blame view for `path` at `revision` with line `selected` highlighted
+fn show_blame(app_state: &mut AppState, revision: &str, path: &str, selected: usize) {
+    match git_commands::get_blame(&app_state.repo_path, revision, path) {
+        Ok(lines) => {
+            debug!("Showing blame of {} at {}", path, revision);
+            app_state.open_view(UIState::Blame);
+            app_state.blame = Some(BlameState::new(
+                path.to_string(),
+                revision.to_string(),
+                lines,
+                selected,
+            ));
+        }
+        Err(err) => show_error(app_state, err),
+    }
+}
+
+/// Shows an error until it is closed, and keeps it in the message history.
+pub fn show_error(app_state: &mut AppState, err: String) {
+    app_state.notify(NotificationKind::Error, err.clone());
+    app_state.ui_state = UIState::Error;
+    app_state.input_mode = InputMode::Command; // Closed with the command keys
+    app_state.error_message = Some(err);
+}
+
+// Replace the browsed directory of the file tree without adding a view to return to
+fn browse_directory(app_state: &mut AppState, revision: &str, directory: &str) {
+    match git_commands::get_tree_entries(&app_state.repo_path, revision, directory) {
+        Ok(entries) => {
+            let previous = app_state.file_tree.replace(FileTreeState::new(
+                revision.to_string(),
+                directory.to_string(),
+                entries,
+            ));
+            // Keep the directory we came from highlighted when going up
+            if let (Some(previous), Some(tree)) = (previous, &mut app_state.file_tree) {
+                if let Some(index) = tree
+                    .entries
+                    .iter()
+                    .position(|entry| entry.path == previous.directory)
+                {
+                    tree.cursor.selected = index;
+                }
             }
         }
+        Err(err) => show_error(app_state, err),
+    }
+}
+
+// Descend into the highlighted directory, or show the highlighted file if `open_files` is set
+fn open_selected_tree_entry(app_state: &mut AppState, open_files: bool) {
+    let Some(tree) = &app_state.file_tree else {
+        return;
+    };
+    let Some(entry) = tree.selected_entry().cloned() else {
+        return;
+    };
+    let revision = tree.revision.clone();
 
+    match entry.kind {
+        TreeEntryKind::Directory => browse_directory(app_state, &revision, &entry.path),
+        TreeEntryKind::File | TreeEntryKind::Symlink if open_files => {
+            match git_commands::get_file_contents(&app_state.repo_path, &revision, &entry.path) {
+                Ok(contents) => {
+                    let lines: Vec<String> = contents.lines().map(String::from).collect();
+                    app_state.open_view(UIState::FileContents);
+                    app_state.file_contents = Some(FileContentsState {
+                        path: entry.path,
+                        revision,
+                        cursor: ListCursor::new(0, lines.len()),
+                        lines,
+                    });
+                }
+                Err(err) => show_error(app_state, err),
+            }
+        }
         _ => {}
     }
-    Ok(false)
+}
+
+// Revision, path and highlighted line of the file selected in the tree or shown in the contents view
+fn selected_browsed_file(app_state: &AppState) -> Option<(String, String, usize)> {
+    match app_state.ui_state {
+        UIState::FileContents => app_state.file_contents.as_ref().map(|contents| {
+            (
+                contents.revision.clone(),
+                contents.path.clone(),
+                contents.cursor.selected,
+            )
+        }),
+        _ => {
+            let tree = app_state.file_tree.as_ref()?;
+            tree.selected_entry()
+                .filter(|entry| entry.kind == TreeEntryKind::File)
+                .map(|entry| (tree.revision.clone(), entry.path.clone(), 0))
+        }
+    }
 }
@@ -0,0 +1,124 @@
+/// A candidate that matched a fuzzy query.
+pub struct FuzzyMatch {
+    pub index: usize,          // Index of the candidate in the searched list
+    pub score: i64,            // Higher is better
+    pub positions: Vec<usize>, // Char positions of the candidate matched by the query
+}
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 24;
+const SEGMENT_START_BONUS: i64 = 32; // Match right after '/', '_', '-', '.' or at the start
+const FILE_NAME_BONUS: i64 = 8; // Match inside the last path component
+const CASE_BONUS: i64 = 2;
+const GAP_PENALTY: i64 = 1;
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '/' | '_' | '-' | '.' | ' ')
+}
+
+/// Scores `candidate` against `query`, requiring every query char to appear in order
+/// (case-insensitively). Returns `None` if the candidate does not match.
+pub fn score(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let candidate_chars: Vec<char> = candidate.chars().collect();
+    let file_name_start = candidate_chars
+        .iter()
+        .rposition(|&c| c == '/')
+        .map_or(0, |i| i + 1);
+
+    let mut positions = Vec::new();
+    let mut total = 0;
+    let mut next = 0;
+    for query_char in query.chars() {
+        let lower = query_char.to_lowercase().next().unwrap_or(query_char);
+
+        // Prefer the next occurrence that starts a segment, else take the first one
+        let occurrences: Vec<usize> = (next..candidate_chars.len())
+            .filter(|&i| candidate_chars[i].to_lowercase().next() == Some(lower))
+            .collect();
+        let position = *occurrences
+            .iter()
+            .find(|&&i| i == next || i == 0 || is_separator(candidate_chars[i - 1]))
+            .or(occurrences.first())?;
+
+        total += MATCH_SCORE;
+        if position == 0 || is_separator(candidate_chars[position - 1]) {
+            total += SEGMENT_START_BONUS;
+        }
+        if positions.last().is_some_and(|&last| last + 1 == position) {
+            total += CONSECUTIVE_BONUS;
+        }
+        if position >= file_name_start {
+            total += FILE_NAME_BONUS;
+        }
+        if candidate_chars[position] == query_char {
+            total += CASE_BONUS;
+        }
+        total -= (position - next) as i64 * GAP_PENALTY;
+
+        positions.push(position);
+        next = position + 1;
+    }
+
+    // Prefer shorter candidates among equally good matches
+    total -= candidate_chars.len() as i64 / 8;
+    Some((total, positions))
+}
+
+/// Ranks the candidates matching `query`, best first. An empty query keeps the original order.
+pub fn rank(query: &str, candidates: &[String], limit: usize) -> Vec<FuzzyMatch> {
+    let mut matches: Vec<FuzzyMatch> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            score(query, candidate).map(|(score, positions)| FuzzyMatch {
+                index,
+                score,
+                positions,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_chars_must_appear_in_order() {
+        assert!(score("abc", "a_b_c").is_some());
+        assert!(score("ABC", "a_b_c").is_some()); // Case-insensitive
+        assert!(score("cba", "a_b_c").is_none());
+        assert_eq!(score("", "anything").unwrap().1, Vec::<usize>::new());
+    }
+
+    #[test]
+    fn segment_starts_are_preferred() {
+        let (_, positions) = score("ma", "src/main.rs").unwrap();
+        assert_eq!(positions, vec![4, 5]);
+        let (_, positions) = score("tm", "test_main").unwrap();
+        assert_eq!(positions, vec![0, 5]);
+    }
+
+    #[test]
+    fn file_names_rank_above_directories() {
+        let candidates = vec![
+            "app/src/lib.rs".to_string(),
+            "src/app.rs".to_string(),
+            "docs/readme.md".to_string(),
+        ];
+        let ranked = rank("app", &candidates, 10);
+        let indices: Vec<usize> = ranked.iter().map(|m| m.index).collect();
+        assert_eq!(indices, vec![1, 0]);
+    }
+
+    #[test]
+    fn empty_query_keeps_the_order_up_to_the_limit() {
+        let candidates: Vec<String> = ["b", "a", "c"].iter().map(|s| s.to_string()).collect();
+        let indices: Vec<usize> = rank("", &candidates, 2).iter().map(|m| m.index).collect();
+        assert_eq!(indices, vec![0, 1]);
+    }
+}
@@ -1,10 +1,16 @@
+use crate::diff::{self, CommitDetails};
+use crate::hooks::Hooks;
+use crate::identity::{self, Role};
+use crate::signing::{self, SignatureStatus};
 use chrono::{DateTime, Local, Utc};
+use git2::build::CheckoutBuilder;
 use git2::{
-    BranchType, Cred, DiffOptions, MergeOptions, PushOptions, RemoteCallbacks, Repository,
-    Signature,
+    BlameOptions, BranchType, Commit, Cred, DiffOptions, MergeOptions, Oid, PushOptions,
+    RemoteCallbacks, Repository, RepositoryState, ResetType, Signature, Status, StatusOptions,
+    Tree,
 };
 use std::{
-    collections::HashMap,
+    fs,
     path::Path,
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -14,6 +20,12 @@ use log::debug;
 use dotenv::dotenv;
 use std::env;
 
+/// The root of the working directory of the repository that `path` is in.
+pub fn find_workdir(path: &str) -> Option<String> {
+    let repo = Repository::discover(path).ok()?;
+    repo.workdir().and_then(Path::to_str).map(String::from)
+}
+
 pub fn get_commit_log(repo_path: &str) -> Vec<String> {
     let repo = Repository::open(repo_path).expect("Failed to open repository");
     let mut revwalk = repo.revwalk().expect("Failed to create revwalk");
@@ -36,8 +48,12 @@ pub fn get_commit_log(repo_path: &str) -> Vec<String> {
             let binding = commit.author();
             let author = binding.name().unwrap_or("Unknown");
 
-            // Summary
-            let summary = commit.summary().unwrap_or("No message");
+            // Summary, marked when the commit is signed
+            let summary = format!(
+                "{}{}",
+                signing::log_badge(&repo, commit.id()),
+                commit.summary().unwrap_or("No message")
+            );
 
             // Combine all fields
             format!(
@@ -48,6 +64,26 @@ pub fn get_commit_log(repo_path: &str) -> Vec<String> {
         .collect()
 }
 
+/// Summaries of the latest `limit` commits reachable from HEAD, newest first.
+pub fn get_recent_summaries(repo_path: &str, limit: usize) -> Vec<String> {
+    let Ok(repo) = Repository::open(repo_path) else {
+        return Vec::new();
+    };
+    let Ok(mut revwalk) = repo.revwalk() else {
+        return Vec::new();
+    };
+    if revwalk.push_head().is_err() {
+        return Vec::new(); // Unborn branch
+    }
+
+    revwalk
+        .filter_map(|oid| oid.ok())
+        .filter_map(|oid| repo.find_commit(oid).ok())
+        .take(limit)
+        .filter_map(|commit| commit.summary().map(String::from))
+        .collect()
+}
+
 pub fn get_branches(repo_path: &str) -> Vec<String> {
     let repo = Repository::open(repo_path).expect("Failed to open repository");
 
@@ -58,6 +94,90 @@ pub fn get_branches(repo_path: &str) -> Vec<String> {
         .collect()
 }
 
+/// What the status bar shows about the repository.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RepoStatus {
+    pub branch: Option<String>,               // `None` when HEAD is detached
+    pub head: Option<String>,                 // Short ID of HEAD, `None` before the first commit
+    pub ahead_behind: Option<(usize, usize)>, // Commits ahead of and behind the upstream, if any
+    pub operation: Option<&'static str>,      // Merge, rebase and the like left in progress
+    pub changed: usize,                       // Changed, staged and untracked files
+}
+
+pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let mut status = RepoStatus::default();
+
+    if let Ok(head) = repo.head() {
+        if head.is_branch() {
+            status.branch = head.shorthand().map(String::from);
+            status.ahead_behind = head
+                .shorthand()
+                .and_then(|name| repo.find_branch(name, BranchType::Local).ok())
+                .and_then(|branch| branch.upstream().ok())
+                .and_then(|upstream| {
+                    let local = head.target()?;
+                    let remote = upstream.get().target()?;
+                    repo.graph_ahead_behind(local, remote).ok()
+                });
+        }
+        status.head = head.peel_to_commit().ok().map(|commit| short_id(&commit));
+    } else if let Ok(head) = repo.find_reference("HEAD") {
+        // Unborn branch: HEAD names a branch that has no commits yet
+        status.branch = head
+            .symbolic_target()
+            .map(|target| target.trim_start_matches("refs/heads/").to_string());
+    }
+
+    status.operation = match repo.state() {
+        RepositoryState::Clean => None,
+        RepositoryState::Merge => Some("MERGING"),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some("REVERTING"),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some("CHERRY-PICKING"),
+        RepositoryState::Bisect => Some("BISECTING"),
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge => Some("REBASING"),
+        RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => Some("APPLYING"),
+    };
+
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .exclude_submodules(true);
+    status.changed = repo
+        .statuses(Some(&mut status_options))
+        .map_err(|e| format!("Failed to get status: {}", e))?
+        .len();
+    Ok(status)
+}
+
+/// Paths of all files tracked in the HEAD tree, sorted.
+pub fn get_tracked_paths(repo_path: &str) -> Vec<String> {
+    let Ok(repo) = Repository::open(repo_path) else {
+        return Vec::new();
+    };
+    let Ok(tree) = repo.head().and_then(|head| head.peel_to_tree()) else {
+        return Vec::new(); // Unborn branch
+    };
+
+    let mut paths = Vec::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |directory, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) {
+            if let Some(name) = entry.name() {
+                paths.push(format!("{}{}", directory, name));
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })
+    .ok();
+
+    paths.sort();
+    paths
+}
+
 pub fn checkout_branch(repo_path: &str, branch_name: &str) -> Result<(), String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
@@ -76,7 +196,23 @@ pub fn checkout_branch(repo_path: &str, branch_name: &str) -> Result<(), String>
     Ok(())
 }
 
-pub fn get_commit_details(repo_path: &str, commit_hash: &str) -> Result<String, String> {
+/// Everything about a commit for the details view, including whether its signature is good.
+pub fn get_commit_details(repo_path: &str, commit_hash: &str) -> Result<CommitDetails, String> {
+    commit_details(repo_path, commit_hash, true)
+}
+
+/// The details of a commit for the preview. Checking a signature runs gpg or ssh-keygen,
+/// which is too slow while moving through the log, so the header only says whether the
+/// commit is signed.
+pub fn get_commit_preview(repo_path: &str, commit_hash: &str) -> Result<CommitDetails, String> {
+    commit_details(repo_path, commit_hash, false)
+}
+
+fn commit_details(
+    repo_path: &str,
+    commit_hash: &str,
+    verify_signature: bool,
+) -> Result<CommitDetails, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
     let oid = repo
         .revparse_single(commit_hash)
@@ -128,7 +264,7 @@ pub fn get_commit_details(repo_path: &str, commit_hash: &str) -> Result<String,
         None
     };
 
-    let diff = repo
+    let mut diff = repo
         .diff_tree_to_tree(
             parent_tree.as_ref(),
             Some(&tree),
@@ -136,65 +272,349 @@ pub fn get_commit_details(repo_path: &str, commit_hash: &str) -> Result<String,
         )
         .map_err(|e| e.to_string())?;
 
-    let mut added = 0;
-    let mut deleted = 0;
-    let mut changes_by_file = HashMap::new();
-
-    diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
-        let file_path = delta
-            .new_file()
-            .path()
-            .unwrap_or_else(|| delta.old_file().path().unwrap_or(Path::new("unknown")))
-            .display()
-            .to_string();
-        let entry = changes_by_file.entry(file_path).or_insert_with(Vec::new);
-
-        let content = String::from_utf8_lossy(line.content()).trim().to_string(); // Trim whitespace
-
-        match line.origin() {
-            '+' => {
-                added += 1;
-                entry.push(format!("+{:4} {}", line.new_lineno().unwrap_or(0), content));
-            }
-            '-' => {
-                deleted += 1;
-                entry.push(format!("-{:4} {}", line.old_lineno().unwrap_or(0), content));
-            }
-            _ => {}
-        }
-        true
-    })
-    .map_err(|e| e.to_string())?;
-
-    // Prepare the output details
-    let mut changes_output = String::new();
-    for (file, changes) in changes_by_file {
-        changes_output.push_str(&format!("\nFile: {}\n", file));
-        changes_output.push_str(&changes.join("\n"));
-        changes_output.push_str("\n\n-----------------------------\n");
-    }
+    let files = diff::collect_file_diffs(&mut diff)?;
+    let added: usize = files.iter().map(|file| file.added()).sum();
+    let deleted: usize = files.iter().map(|file| file.deleted()).sum();
 
-    let details = format!(
-        "Commit Hash: {}\nAuthor: {} <{}>\nDate: {}\nElapsed Time: {}\n\nMessage:\n{}\n\nParent(s):\n{}\n\nChanges:\n- Lines Added: {}\n- Lines Deleted: {}\n\nDiff:\n{}",
+    let signature = if verify_signature {
+        signing::verify_commit(&repo, commit.id()).describe()
+    } else if signing::is_signed(&repo, commit.id()) {
+        "Signed; open the commit to verify it".to_string()
+    } else {
+        SignatureStatus::Unsigned.describe()
+    };
+
+    let header = format!(
+        "Commit Hash: {}\nAuthor: {} <{}>\nDate: {}\nElapsed Time: {}\nSignature: {}\n\nMessage:\n{}\n\nParent(s):\n{}\n\nChanges:\n- Lines Added: {}\n- Lines Deleted: {}",
         commit.id(),
         commit.author().name().unwrap_or("Unknown"),
         commit.author().email().unwrap_or("Unknown"),
         formatted_date,
         elapsed_time,
+        signature,
         commit.message().unwrap_or("No message"),
         parents.join("\n"),
         added,
         deleted,
-        changes_output
     );
 
+    let details = CommitDetails {
+        title: format!("Commit {}", &commit.id().to_string()[..7]),
+        revision: Some(commit.id().to_string()),
+        old_revision: commit.parent_id(0).ok().map(|parent| parent.to_string()),
+        header,
+        files,
+    };
+
     Ok(details)
 }
 
-pub fn commit_and_push(repo_path: &str, commit_message: &str) -> Result<(), String> {
+pub fn get_unstaged_changes(repo_path: &str) -> Result<CommitDetails, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let mut diff = repo
+        .diff_index_to_workdir(None, Some(&mut options))
+        .map_err(|e| format!("Failed to diff working tree: {}", e))?;
+
+    diff_details(
+        &mut diff,
+        "Unstaged Changes".to_string(),
+        None,
+        None,
+        "Changes in the working tree that are not staged for commit.",
+    )
+}
+
+pub fn get_staged_changes(repo_path: &str) -> Result<CommitDetails, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+
+    // An unborn branch has no HEAD tree, so everything in the index is new
+    let head_tree = match repo.head() {
+        Ok(head) => Some(
+            head.peel_to_tree()
+                .map_err(|e| format!("Failed to get HEAD tree: {}", e))?,
+        ),
+        Err(_) => None,
+    };
+    let mut diff = repo
+        .diff_tree_to_index(head_tree.as_ref(), None, Some(&mut DiffOptions::new()))
+        .map_err(|e| format!("Failed to diff index: {}", e))?;
+
+    let head_revision = head_tree.as_ref().map(|_| "HEAD".to_string());
+    diff_details(
+        &mut diff,
+        "Staged Changes".to_string(),
+        None,
+        head_revision,
+        "Changes in the index that will be part of the next commit.",
+    )
+}
+
+/// Diffs the trees of two revisions (commit hashes, branch names or any revspec).
+pub fn get_diff_between(repo_path: &str, from: &str, to: &str) -> Result<CommitDetails, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+
+    let find_tree = |revision: &str| {
+        repo.revparse_single(revision)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| format!("Failed to resolve '{}': {}", revision, e))
+    };
+    let from_tree = find_tree(from)?;
+    let to_tree = find_tree(to)?;
+
+    let mut diff = repo
+        .diff_tree_to_tree(
+            Some(&from_tree),
+            Some(&to_tree),
+            Some(&mut DiffOptions::new()),
+        )
+        .map_err(|e| e.to_string())?;
+
+    diff_details(
+        &mut diff,
+        format!("Compare {}..{}", from, to),
+        Some(to.to_string()),
+        Some(from.to_string()),
+        &format!("Changes from '{}' to '{}'.", from, to),
+    )
+}
+
+// Build the details view content for a diff that is not a single commit
+fn diff_details(
+    diff: &mut git2::Diff,
+    title: String,
+    revision: Option<String>,
+    old_revision: Option<String>,
+    description: &str,
+) -> Result<CommitDetails, String> {
+    let files = diff::collect_file_diffs(diff)?;
+    let added: usize = files.iter().map(|file| file.added()).sum();
+    let deleted: usize = files.iter().map(|file| file.deleted()).sum();
+
+    let header = format!(
+        "{}\n\nChanges:\n- Files Changed: {}\n- Lines Added: {}\n- Lines Deleted: {}",
+        description,
+        files.len(),
+        added,
+        deleted
+    );
+
+    Ok(CommitDetails {
+        title,
+        revision,
+        old_revision,
+        header,
+        files,
+    })
+}
+
+#[derive(Clone)]
+pub struct BlameLine {
+    pub commit_id: String,
+    pub author: String,
+    pub date: String,
+    pub line_number: usize,
+    pub original_path: String, // Path of the file in the commit that last changed the line
+    pub original_line: usize,  // Line number in that commit
+    pub content: String,
+}
+
+/// Blames every line of `file_path` as of `revision`.
+pub fn get_blame(
+    repo_path: &str,
+    revision: &str,
+    file_path: &str,
+) -> Result<Vec<BlameLine>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let commit = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve '{}': {}", revision, e))?;
+
+    let blob = commit
+        .tree()
+        .and_then(|tree| tree.get_path(Path::new(file_path)))
+        .and_then(|entry| repo.find_blob(entry.id()))
+        .map_err(|e| format!("'{}' does not exist at {}: {}", file_path, revision, e))?;
+    if blob.is_binary() {
+        return Err(format!("Cannot blame binary file '{}'", file_path));
+    }
+
+    let mut options = BlameOptions::new();
+    options.newest_commit(commit.id());
+    let blame = repo
+        .blame_file(Path::new(file_path), Some(&mut options))
+        .map_err(|e| format!("Failed to blame '{}': {}", file_path, e))?;
+
+    let content = String::from_utf8_lossy(blob.content());
+    let lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line_number = i + 1;
+            let hunk = blame.get_line(line_number);
+
+            let (commit_id, author, date, original_path, original_line) = match hunk {
+                Some(hunk) => {
+                    let signature = hunk.final_signature();
+                    let timestamp = signature.when().seconds();
+                    let date = DateTime::<Utc>::from_timestamp(timestamp, 0)
+                        .map(|datetime| {
+                            datetime
+                                .with_timezone(&Local)
+                                .format("%Y-%m-%d")
+                                .to_string()
+                        })
+                        .unwrap_or_default();
+                    let original_path = hunk
+                        .path()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_else(|| file_path.to_string());
+                    let original_line =
+                        hunk.orig_start_line() + (line_number - hunk.final_start_line());
+                    (
+                        hunk.final_commit_id().to_string(),
+                        signature.name().unwrap_or("Unknown").to_string(),
+                        date,
+                        original_path,
+                        original_line,
+                    )
+                }
+                None => (
+                    String::new(),
+                    "Unknown".to_string(),
+                    String::new(),
+                    file_path.to_string(),
+                    line_number,
+                ),
+            };
+
+            BlameLine {
+                commit_id,
+                author,
+                date,
+                line_number,
+                original_path,
+                original_line,
+                content: line.to_string(),
+            }
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+#[derive(Clone)]
+pub struct TreeEntry {
+    pub name: String,
+    pub path: String, // Path from the repository root
+    pub kind: TreeEntryKind,
+    pub size: Option<usize>, // Size in bytes of files
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TreeEntryKind {
+    Directory,
+    File,
+    Symlink,
+    Submodule,
+}
+
+/// Lists `directory` (empty for the root) as of `revision`, directories first.
+pub fn get_tree_entries(
+    repo_path: &str,
+    revision: &str,
+    directory: &str,
+) -> Result<Vec<TreeEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let root = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|e| format!("Failed to resolve '{}': {}", revision, e))?;
+
+    let tree = if directory.is_empty() {
+        root
+    } else {
+        root.get_path(Path::new(directory))
+            .and_then(|entry| entry.to_object(&repo))
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| format!("'{}' is not a directory at {}: {}", directory, revision, e))?
+    };
+
+    let mut entries: Vec<TreeEntry> = tree
+        .iter()
+        .map(|entry| {
+            let name = entry.name().unwrap_or("unknown").to_string();
+            let path = if directory.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", directory, name)
+            };
+            let kind = match entry.filemode() {
+                0o040000 => TreeEntryKind::Directory,
+                0o120000 => TreeEntryKind::Symlink,
+                0o160000 => TreeEntryKind::Submodule,
+                _ => TreeEntryKind::File,
+            };
+            let size = match kind {
+                TreeEntryKind::File => repo.find_blob(entry.id()).ok().map(|blob| blob.size()),
+                _ => None,
+            };
+            TreeEntry {
+                name,
+                path,
+                kind,
+                size,
+            }
+        })
+        .collect();
+
+    entries.sort_by(|a, b| {
+        (b.kind == TreeEntryKind::Directory)
+            .cmp(&(a.kind == TreeEntryKind::Directory))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(entries)
+}
+
+/// Reads `file_path` as of `revision`. Binary files are described instead of decoded.
+pub fn get_file_contents(
+    repo_path: &str,
+    revision: &str,
+    file_path: &str,
+) -> Result<String, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let blob = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_tree())
+        .and_then(|tree| tree.get_path(Path::new(file_path)))
+        .and_then(|entry| repo.find_blob(entry.id()))
+        .map_err(|e| format!("'{}' is not a file at {}: {}", file_path, revision, e))?;
+
+    if blob.is_binary() {
+        return Ok(format!("Binary file ({} bytes)", blob.size()));
+    }
+    Ok(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+/// Commits all changes and pushes them to `remote_name`, running the commit and push hooks
+/// on the way.
+pub fn commit_and_push(
+    repo_path: &str,
+    remote_name: &str,
+    commit_message: &str,
+    hooks: &mut Hooks,
+) -> Result<(), String> {
+    // Check the push credentials before committing anything
     dotenv().ok();
-    let username = env::var("GIT_USERNAME").map_err(|_| "GIT_USERNAME not set".to_string())?;
-    let token = env::var("GIT_PASSWORD").map_err(|_| "GIT_PASSWORD not set".to_string())?; // Use the token here
+    env::var("GIT_USERNAME").map_err(|_| "GIT_USERNAME not set".to_string())?;
+    env::var("GIT_PASSWORD").map_err(|_| "GIT_PASSWORD not set".to_string())?;
 
     let repo =
         Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
@@ -209,56 +629,506 @@ pub fn commit_and_push(repo_path: &str, commit_message: &str) -> Result<(), Stri
         .write()
         .map_err(|e| format!("Failed to write index: {}", e))?;
 
-    let oid = index
-        .write_tree()
-        .map_err(|e| format!("Failed to write tree: {}", e))?;
-    let tree = repo
-        .find_tree(oid)
-        .map_err(|e| format!("Failed to find tree: {}", e))?;
+    hooks.run(&repo, "pre-commit", &[], None)?;
+    let commit_message = run_message_hooks(&repo, hooks, commit_message, &["message"])?;
+
+    let tree = index_tree(&repo)?; // Pre-commit hooks may have staged more changes
     let head = repo
         .head()
         .map_err(|e| format!("Failed to get HEAD: {}", e))?;
     let parent_commit = head
         .peel_to_commit()
         .map_err(|e| format!("Failed to get parent commit: {}", e))?;
-    let signature = repo
-        .signature()
-        .map_err(|e| format!("Failed to create signature: {}", e))?;
-    repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        commit_message,
+    let author = identity::signature(&repo, Role::Author)?;
+    let committer = identity::signature(&repo, Role::Committer)?;
+    let oid = write_commit(
+        &repo,
+        &author,
+        &committer,
+        &commit_message,
         &tree,
         &[&parent_commit],
     )
     .map_err(|e| format!("Failed to commit changes: {}", e))?;
+    move_head(&repo, oid, "commit")?;
+    hooks.notify(&repo, "post-commit", &[]);
+
+    push_to_remote(repo_path, remote_name, hooks)
+}
+
+/// Pushes to the remote, running the pre-push hook first.
+pub fn push_to_remote(repo_path: &str, remote_name: &str, hooks: &mut Hooks) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
 
+    // Push the branch that is checked out, under the same name
+    let head = repo
+        .head()
+        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
+    if !head.is_branch() {
+        return Err("HEAD is detached; check out a branch to push".to_string());
+    }
+    let branch_ref = head
+        .name()
+        .ok_or("Current branch name is not valid UTF-8".to_string())?;
+    let refspec = format!("{}:{}", branch_ref, branch_ref);
+
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(credential_callbacks());
+
+    let mut remote = repo
+        .find_remote(remote_name)
+        .map_err(|e| format!("Failed to find remote '{}': {}", remote_name, e))?;
+    run_pre_push_hook(&repo, hooks, &remote, &refspec)?;
+    remote
+        .push(&[&refspec], Some(&mut push_options))
+        .map_err(|e| format!("Failed to push changes: {}", e))?;
+
+    Ok(())
+}
+
+// Authenticate with GIT_USERNAME and GIT_PASSWORD, read only when the remote asks for them
+fn credential_callbacks<'a>() -> RemoteCallbacks<'a> {
     let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(move |_url, username_from_url, _allowed_types| {
+    callbacks.credentials(|_url, username_from_url, _allowed_types| {
+        dotenv().ok();
+        let username =
+            env::var("GIT_USERNAME").map_err(|_| git2::Error::from_str("GIT_USERNAME not set"))?;
+        let token = env::var("GIT_PASSWORD") // Use the token here
+            .map_err(|_| git2::Error::from_str("GIT_PASSWORD not set"))?;
         Cred::userpass_plaintext(
             username_from_url.unwrap_or(&username), // Use username from URL or fallback
             &token,                                 // Use the PAT as the password
         )
     });
+    callbacks
+}
 
-    let mut push_options = PushOptions::new();
-    push_options.remote_callbacks(callbacks);
+// Give the prepare-commit-msg and commit-msg hooks the message through COMMIT_EDITMSG,
+// and return the message as they left it
+fn run_message_hooks(
+    repo: &Repository,
+    hooks: &mut Hooks,
+    commit_message: &str,
+    source: &[&str],
+) -> Result<String, String> {
+    let path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&path, format!("{}\n", commit_message.trim_end()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let path_arg = path.to_string_lossy();
+    let mut args = vec![path_arg.as_ref()];
+    args.extend(source);
+    hooks.run(repo, "prepare-commit-msg", &args, None)?;
+    hooks.run(repo, "commit-msg", &[path_arg.as_ref()], None)?;
+
+    let edited = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(git2::message_prettify(&edited, None).unwrap_or(edited))
+}
 
-    let mut remote = repo
-        .find_remote("origin")
-        .map_err(|e| format!("Failed to find remote: {}", e))?;
-    remote
-        .push(
-            &["refs/heads/main:refs/heads/main"],
-            Some(&mut push_options),
-        )
-        .map_err(|e| format!("Failed to push changes: {}", e))?;
+// The pre-push hook gets the remote as arguments and the refs to push on stdin
+fn run_pre_push_hook(
+    repo: &Repository,
+    hooks: &mut Hooks,
+    remote: &git2::Remote,
+    refspec: &str,
+) -> Result<(), String> {
+    let name = remote.name().unwrap_or("origin");
+    let (local_ref, remote_ref) = refspec.split_once(':').unwrap_or((refspec, refspec));
+    let zero = Oid::zero().to_string();
+    let local_id = repo
+        .refname_to_id(local_ref)
+        .map_or(zero.clone(), |id| id.to_string());
+    let tracking_ref = remote_ref.replacen("refs/heads/", &format!("refs/remotes/{}/", name), 1);
+    let remote_id = repo
+        .refname_to_id(&tracking_ref)
+        .map_or(zero, |id| id.to_string());
+
+    let stdin = format!("{} {} {} {}\n", local_ref, local_id, remote_ref, remote_id);
+    hooks.run(
+        repo,
+        "pre-push",
+        &[name, remote.url().unwrap_or(name)],
+        Some(&stdin),
+    )
+}
+
+/// The ID of the commit a revision names.
+pub fn resolve_commit(repo_path: &str, revision: &str) -> Result<Oid, String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let oid = find_commit(&repo, revision)?.id();
+    Ok(oid)
+}
+
+/// Full message of a commit, for editing it.
+pub fn get_commit_message(repo_path: &str, revision: &str) -> Result<String, String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let commit = find_commit(&repo, revision)?;
+    Ok(commit.message().unwrap_or_default().trim_end().to_string())
+}
+
+/// Replaces HEAD with a commit of the staged changes and the new message. Nothing is pushed.
+pub fn amend_head(repo_path: &str, commit_message: &str, hooks: &mut Hooks) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let head = find_commit(&repo, "HEAD")?;
+    hooks.run(&repo, "pre-commit", &[], None)?;
+    let commit_message = run_message_hooks(&repo, hooks, commit_message, &["commit", "HEAD"])?;
+    let tree = index_tree(&repo)?;
+    let committer = identity::signature(&repo, Role::Committer)?;
+
+    let parents: Vec<Commit> = head.parents().collect();
+    let parents: Vec<&Commit> = parents.iter().collect();
+    let oid = write_commit(
+        &repo,
+        &head.author(),
+        &committer,
+        &commit_message,
+        &tree,
+        &parents,
+    )
+    .map_err(|e| format!("Failed to amend commit: {}", e))?;
+    move_head(&repo, oid, "amend")?;
+    hooks.notify(&repo, "post-commit", &[]);
+    Ok(())
+}
+
+/// The upstream of the current branch, if it already contains the commit `revision` names.
+/// Rewriting such a commit means the branch has to be force pushed.
+pub fn upstream_containing(repo_path: &str, revision: &str) -> Result<Option<String>, String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let target = find_commit(&repo, revision)?.id();
+    let upstream = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(String::from))
+        .and_then(|name| repo.find_branch(&name, BranchType::Local).ok())
+        .and_then(|branch| branch.upstream().ok());
+    let Some(upstream) = upstream else {
+        return Ok(None);
+    };
+    let Some(upstream_tip) = upstream.get().target() else {
+        return Ok(None);
+    };
+
+    let contains = upstream_tip == target
+        || repo
+            .graph_descendant_of(upstream_tip, target)
+            .map_err(|e| format!("Failed to compare with the upstream: {}", e))?;
+    Ok(contains.then(|| {
+        upstream
+            .name()
+            .ok()
+            .flatten()
+            .unwrap_or("upstream")
+            .to_string()
+    }))
+}
+
+/// Changes the message of a commit on the current branch, recreating the commits after it
+/// with unchanged contents. Nothing is pushed.
+pub fn reword_commit(
+    repo_path: &str,
+    revision: &str,
+    commit_message: &str,
+    hooks: &mut Hooks,
+) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let target = find_commit(&repo, revision)?;
+    let descendants = commits_since(&repo, &target)?;
+    let target_id = target.id().to_string();
+    let commit_message = run_message_hooks(&repo, hooks, commit_message, &["commit", &target_id])?;
+    let committer = identity::signature(&repo, Role::Committer)?;
+
+    let parents: Vec<Commit> = target.parents().collect();
+    let parents: Vec<&Commit> = parents.iter().collect();
+    let tree = target
+        .tree()
+        .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let mut tip = write_commit(
+        &repo,
+        &target.author(),
+        &committer,
+        &commit_message,
+        &tree,
+        &parents,
+    )
+    .map_err(|e| format!("Failed to reword commit: {}", e))?;
+
+    for commit in &descendants {
+        let tree = commit
+            .tree()
+            .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+        tip = recommit(&repo, commit, &committer, &tree, tip)?;
+    }
+    move_head(&repo, tip, "reword")
+}
+
+/// Commits the staged changes as `fixup! <summary>` of a commit on the current branch,
+/// ready to be squashed into it.
+pub fn create_fixup_commit(
+    repo_path: &str,
+    revision: &str,
+    hooks: &mut Hooks,
+) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    let target = find_commit(&repo, revision)?;
+    commits_since(&repo, &target)?; // The target must be on the current branch
+    let head = find_commit(&repo, "HEAD")?;
+
+    if index_tree(&repo)?.id() == head.tree_id() {
+        return Err("No staged changes to create a fixup commit from".to_string());
+    }
+    hooks.run(&repo, "pre-commit", &[], None)?;
+    let message = format!("fixup! {}", target.summary().unwrap_or_default());
+    let message = run_message_hooks(&repo, hooks, &message, &["message"])?;
+    let tree = index_tree(&repo)?;
+
+    let author = identity::signature(&repo, Role::Author)?;
+    let committer = identity::signature(&repo, Role::Committer)?;
+    let oid = write_commit(&repo, &author, &committer, &message, &tree, &[&head])
+        .map_err(|e| format!("Failed to create fixup commit: {}", e))?;
+    move_head(&repo, oid, "fixup")?;
+    hooks.notify(&repo, "post-commit", &[]);
+    Ok(())
+}
+
+/// Commits the staged changes as a fixup of a commit and squashes it in right away. Nothing
+/// is committed unless the squash can run, and the staged changes are put back if it fails.
+pub fn fixup_and_autosquash(
+    repo_path: &str,
+    revision: &str,
+    hooks: &mut Hooks,
+) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    // Only the staged changes, which go into the fixup commit, may be left
+    check_clean_tree(
+        &repo,
+        Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_RENAMED
+            | Status::INDEX_TYPECHANGE,
+    )?;
+    let head = repo
+        .revparse_single("HEAD")
+        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
+
+    create_fixup_commit(repo_path, revision, hooks)?;
+    autosquash_fixup(repo_path, revision).inspect_err(|_| {
+        // Undo the fixup commit, keeping its changes staged
+        if let Err(e) = repo.reset(&head, ResetType::Soft, None) {
+            debug!("Failed to undo the fixup commit: {}", e);
+        }
+    })
+}
+
+/// Squashes the fixup commit at HEAD into the commit it fixes, replaying the commits in
+/// between, like `git rebase --autosquash`. The working tree must be clean.
+pub fn autosquash_fixup(repo_path: &str, revision: &str) -> Result<(), String> {
+    let repo =
+        Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    check_clean_tree(&repo, Status::empty())?;
+
+    let target = find_commit(&repo, revision)?;
+    let replayed = commits_since(&repo, &target)?;
+    let Some((fixup, between)) = replayed.split_last() else {
+        return Err("There is no fixup commit to squash".to_string());
+    };
+    if !fixup.summary().unwrap_or_default().starts_with("fixup! ") {
+        return Err("HEAD is not a fixup commit".to_string());
+    }
+    let committer = identity::signature(&repo, Role::Committer)?;
+
+    // Squash the fixup into the target, then replay the commits in between on top
+    let squashed_tree = cherry_pick_tree(&repo, fixup, &target)?;
+    let parents: Vec<Commit> = target.parents().collect();
+    let parents: Vec<&Commit> = parents.iter().collect();
+    let mut tip = write_commit(
+        &repo,
+        &target.author(),
+        &committer,
+        target.message().unwrap_or_default(),
+        &squashed_tree,
+        &parents,
+    )
+    .map_err(|e| format!("Failed to squash fixup commit: {}", e))?;
+    for commit in between {
+        let onto = repo
+            .find_commit(tip)
+            .map_err(|e| format!("Failed to find commit: {}", e))?;
+        let tree = cherry_pick_tree(&repo, commit, &onto)?;
+        tip = recommit(&repo, commit, &committer, &tree, tip)?;
+    }
+
+    move_head(&repo, tip, "autosquash")?;
+    let tip = repo
+        .find_object(tip, None)
+        .map_err(|e| format!("Failed to find commit: {}", e))?;
+    repo.reset(&tip, ResetType::Hard, None)
+        .map_err(|e| format!("Failed to check out squashed commits: {}", e))
+}
+
+// Fail if tracked files have changes other than the `allowed` ones, which autosquash would
+// otherwise overwrite when it checks out the result
+fn check_clean_tree(repo: &Repository, allowed: Status) -> Result<(), String> {
+    let mut status_options = StatusOptions::new();
+    status_options.include_untracked(false);
+    let statuses = repo
+        .statuses(Some(&mut status_options))
+        .map_err(|e| format!("Failed to get status: {}", e))?;
+    if statuses
+        .iter()
+        .any(|entry| !allowed.contains(entry.status()))
+    {
+        return Err(
+            "Autosquash needs a clean working tree; commit or stash your changes first".to_string(),
+        );
+    }
+    Ok(())
+}
 
+fn find_commit<'r>(repo: &'r Repository, revision: &str) -> Result<Commit<'r>, String> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to find commit '{}': {}", revision, e))
+}
+
+fn index_tree(repo: &Repository) -> Result<Tree<'_>, String> {
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to get repository index: {}", e))?;
+    let oid = index
+        .write_tree()
+        .map_err(|e| format!("Failed to write tree: {}", e))?;
+    repo.find_tree(oid)
+        .map_err(|e| format!("Failed to find tree: {}", e))
+}
+
+// Commits after `target` up to HEAD, oldest first. Rewriting them is only supported when
+// `target` is a first-parent ancestor of HEAD with no merges in between.
+fn commits_since<'r>(repo: &'r Repository, target: &Commit) -> Result<Vec<Commit<'r>>, String> {
+    let mut commits = Vec::new();
+    let mut current = find_commit(repo, "HEAD")?;
+    while current.id() != target.id() {
+        if current.parent_count() > 1 {
+            return Err(format!(
+                "Cannot rewrite history across merge commit {}",
+                short_id(&current)
+            ));
+        }
+        let parent = current
+            .parent(0)
+            .map_err(|_| format!("Commit {} is not on the current branch", short_id(target)))?;
+        commits.push(current);
+        current = parent;
+    }
+    commits.reverse();
+    Ok(commits)
+}
+
+// Recreate `commit` with a new tree on top of `parent`, keeping its author and message
+fn recommit(
+    repo: &Repository,
+    commit: &Commit,
+    committer: &Signature,
+    tree: &Tree,
+    parent: Oid,
+) -> Result<Oid, String> {
+    let parent = repo
+        .find_commit(parent)
+        .map_err(|e| format!("Failed to find commit: {}", e))?;
+    write_commit(
+        repo,
+        &commit.author(),
+        committer,
+        commit.message().unwrap_or_default(),
+        tree,
+        &[&parent],
+    )
+    .map_err(|e| format!("Failed to rewrite commit {}: {}", short_id(commit), e))
+}
+
+// Create a commit without moving any branch, signing it when `commit.gpgsign` is set
+fn write_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+) -> Result<Oid, String> {
+    let Some(config) = signing::signing_config(repo) else {
+        return repo
+            .commit(None, author, committer, message, tree, parents)
+            .map_err(|e| e.to_string());
+    };
+
+    let buffer = repo
+        .commit_create_buffer(author, committer, message, tree, parents)
+        .map_err(|e| e.to_string())?;
+    let content = buffer
+        .as_str()
+        .ok_or("The commit is not valid UTF-8 and cannot be signed")?;
+    let signature = signing::sign(repo, &config, content)?;
+    repo.commit_signed(content, &signature, None)
+        .map_err(|e| e.to_string())
+}
+
+// Tree of `commit`'s changes applied on top of `onto`
+fn cherry_pick_tree<'r>(
+    repo: &'r Repository,
+    commit: &Commit,
+    onto: &Commit,
+) -> Result<Tree<'r>, String> {
+    let mut index = repo
+        .cherrypick_commit(commit, onto, 0, None)
+        .map_err(|e| format!("Failed to replay commit {}: {}", short_id(commit), e))?;
+    if index.has_conflicts() {
+        return Err(format!(
+            "Replaying commit {} conflicts; nothing was changed",
+            short_id(commit)
+        ));
+    }
+    let oid = index
+        .write_tree_to(repo)
+        .map_err(|e| format!("Failed to write tree: {}", e))?;
+    repo.find_tree(oid)
+        .map_err(|e| format!("Failed to find tree: {}", e))
+}
+
+// Point the current branch (or a detached HEAD) at a rewritten commit
+fn move_head(repo: &Repository, tip: Oid, operation: &str) -> Result<(), String> {
+    let mut head = repo
+        .head()
+        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
+    if head.is_branch() {
+        head.set_target(tip, &format!("ezgit: {}", operation))
+            .map_err(|e| format!("Failed to update branch: {}", e))?;
+    } else {
+        repo.set_head_detached(tip)
+            .map_err(|e| format!("Failed to update HEAD: {}", e))?;
+    }
     Ok(())
 }
 
-pub fn create_and_switch_branch(repo_path: &str, branch_name: &str) -> Result<(), String> {
+fn short_id(commit: &Commit) -> String {
+    commit.id().to_string()[..7].to_string()
+}
+
+pub fn create_and_switch_branch(
+    repo_path: &str,
+    remote_name: &str,
+    branch_name: &str,
+) -> Result<(), String> {
     let repo =
         Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
 
@@ -286,21 +1156,10 @@ pub fn create_and_switch_branch(repo_path: &str, branch_name: &str) -> Result<()
 
     // Push the branch to the remote and set upstream
     let mut remote = repo
-        .find_remote("origin")
-        .map_err(|e| format!("Failed to find remote: {}", e))?;
-    let mut callbacks = git2::RemoteCallbacks::new();
-
-    // Load credentials for authentication
-    dotenv::dotenv().ok();
-    let username = std::env::var("GIT_USERNAME").map_err(|_| "GIT_USERNAME not set".to_string())?;
-    let token = std::env::var("GIT_PASSWORD").map_err(|_| "GIT_PASSWORD not set".to_string())?;
-
-    callbacks.credentials(move |_url, username_from_url, _allowed_types| {
-        git2::Cred::userpass_plaintext(username_from_url.unwrap_or(&username), &token)
-    });
-
-    let mut push_options = git2::PushOptions::new();
-    push_options.remote_callbacks(callbacks);
+        .find_remote(remote_name)
+        .map_err(|e| format!("Failed to find remote '{}': {}", remote_name, e))?;
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(credential_callbacks());
 
     let refspec = format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name);
     remote
@@ -309,17 +1168,35 @@ pub fn create_and_switch_branch(repo_path: &str, branch_name: &str) -> Result<()
 
     // Set the upstream branch
     branch
-        .set_upstream(Some(&format!("origin/{}", branch_name)))
+        .set_upstream(Some(&format!("{}/{}", remote_name, branch_name)))
         .map_err(|e| format!("Failed to set upstream for branch '{}': {}", branch_name, e))?;
 
     Ok(())
 }
 
-pub fn merge_into_branch(repo_path: &str, target_branch: &str) -> Result<(), String> {
+pub fn merge_into_branch(
+    repo_path: &str,
+    target_branch: &str,
+    hooks: &mut Hooks,
+) -> Result<(), String> {
     let repo =
         Repository::open(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
 
-    // Step 1: Check for uncommitted changes before switching branches
+    // Step 1: Note the branch being merged, before HEAD moves to the target
+    let source_branch = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string))
+        .ok_or("HEAD is detached; check out the branch to merge".to_string())?;
+    if source_branch == target_branch {
+        return Err(format!(
+            "Cannot merge '{}' into itself; check out the branch to merge first",
+            target_branch
+        ));
+    }
+
+    // Step 2: Check for uncommitted changes before switching branches
     let statuses = repo
         .statuses(None)
         .map_err(|e| format!("Failed to get repository statuses: {}", e))?;
@@ -347,21 +1224,38 @@ pub fn merge_into_branch(repo_path: &str, target_branch: &str) -> Result<(), Str
         let parent_commit = head
             .peel_to_commit()
             .map_err(|e| format!("Failed to get parent commit: {}", e))?;
-        let signature = repo
-            .signature()
-            .map_err(|e| format!("Failed to create signature: {}", e))?;
-        repo.commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
+        let author = identity::signature(&repo, Role::Author)?;
+        let committer = identity::signature(&repo, Role::Committer)?;
+        let oid = write_commit(
+            &repo,
+            &author,
+            &committer,
             "Auto-commit changes before merge",
             &tree,
             &[&parent_commit],
         )
         .map_err(|e| format!("Failed to commit changes: {}", e))?;
+        move_head(&repo, oid, "commit")?;
     }
 
-    // Step 2: Switch to the main branch
+    let source_commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|e| format!("Failed to get commit for branch '{}': {}", source_branch, e))?;
+
+    // Step 3: Switch to the target branch, checking out its tree before moving HEAD so that
+    // the working tree is compared against the branch being left
+    let target_commit = repo
+        .find_branch(target_branch, BranchType::Local)
+        .and_then(|branch| branch.get().peel_to_commit())
+        .map_err(|e| format!("Failed to find target branch '{}': {}", target_branch, e))?;
+    repo.checkout_tree(target_commit.as_object(), None)
+        .map_err(|e| {
+            format!(
+                "Failed to checkout target branch '{}': {}",
+                target_branch, e
+            )
+        })?;
     repo.set_head(&format!("refs/heads/{}", target_branch))
         .map_err(|e| {
             format!(
@@ -369,25 +1263,21 @@ pub fn merge_into_branch(repo_path: &str, target_branch: &str) -> Result<(), Str
                 target_branch, e
             )
         })?;
-    repo.checkout_head(None).map_err(|e| {
-        format!(
-            "Failed to checkout target branch '{}': {}",
-            target_branch, e
-        )
-    })?;
-
-    // Step 3: Get the current branch's HEAD commit
-    let head_ref = repo
-        .head()
-        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
-    let current_branch_commit = head_ref
-        .peel_to_commit()
-        .map_err(|e| format!("Failed to get commit for current branch: {}", e))?;
 
-    // Step 4: Merge the current branch into the target branch
+    // Step 4: Merge the source branch into the target branch
     let annotated_commit = repo
-        .find_annotated_commit(current_branch_commit.id())
+        .find_annotated_commit(source_commit.id())
         .map_err(|e| format!("Failed to create annotated commit: {}", e))?;
+    let (analysis, _) = repo
+        .merge_analysis(&[&annotated_commit])
+        .map_err(|e| format!("Failed to analyze merge: {}", e))?;
+    if analysis.is_up_to_date() {
+        return_to_branch(&repo, &source_branch, &source_commit);
+        return Err(format!(
+            "'{}' is already merged into '{}'",
+            source_branch, target_branch
+        ));
+    }
     let mut merge_options = MergeOptions::new();
     repo.merge(&[&annotated_commit], Some(&mut merge_options), None)
         .map_err(|e| format!("Merge failed: {}", e))?;
@@ -397,9 +1287,20 @@ pub fn merge_into_branch(repo_path: &str, target_branch: &str) -> Result<(), Str
         return Err("Merge completed with conflicts. Please resolve them manually.".to_string());
     }
 
-    // Step 6: Commit the merge
-    let signature = Signature::now("Merge Bot", "merge@example.com")
-        .map_err(|e| format!("Failed to create signature: {}", e))?;
+    // Step 6: Commit the merge, unless a hook stops it
+    let merge_message = hooks
+        .run(&repo, "pre-merge-commit", &[], None)
+        .and_then(|_| {
+            run_message_hooks(
+                &repo,
+                hooks,
+                &format!("Merge branch '{}' into {}", source_branch, target_branch),
+                &["merge"],
+            )
+        })
+        .inspect_err(|_| return_to_branch(&repo, &source_branch, &source_commit))?;
+    let author = identity::signature(&repo, Role::Author)?;
+    let committer = identity::signature(&repo, Role::Committer)?;
     let tree_oid = repo
         .index()
         .map_err(|e| e.to_string())?
@@ -408,34 +1309,60 @@ pub fn merge_into_branch(repo_path: &str, target_branch: &str) -> Result<(), Str
     let tree = repo
         .find_tree(tree_oid)
         .map_err(|e| format!("Failed to find tree: {}", e))?;
-    repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        "Merge changes into main branch",
+    let oid = write_commit(
+        &repo,
+        &author,
+        &committer,
+        &merge_message,
         &tree,
-        &[&current_branch_commit],
+        &[&target_commit, &source_commit],
     )
     .map_err(|e| format!("Failed to commit merge: {}", e))?;
+    move_head(&repo, oid, "merge")?;
+    repo.cleanup_state().ok(); // Remove MERGE_HEAD and friends
+    hooks.notify(&repo, "post-merge", &["0"]); // Not a squash merge
 
     debug!(
         "Merge completed successfully. You are now on the '{}' branch.",
         target_branch
     );
 
-    debug!("Is this in?");
-
-    debug!("Is this also in?");
-
     Ok(())
 }
 
-pub fn get_commits_for_file(repo_path: &str, file_path: &str) -> Vec<String> {
-    let repo = Repository::open(repo_path).expect("Failed to open repository");
-    let mut revwalk = repo.revwalk().expect("Failed to create revwalk");
-    revwalk.push_head().expect("Failed to push head");
+// Abandon the merge, if one is in progress, and check the merged branch out again, so that
+// the merge can be retried as it was started
+fn return_to_branch(repo: &Repository, branch: &str, commit: &Commit) {
+    repo.cleanup_state().ok();
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force(); // Drop the merge result from the working tree
+    let result = repo
+        .checkout_tree(commit.as_object(), Some(&mut checkout))
+        .and_then(|_| repo.set_head(&format!("refs/heads/{}", branch)));
+    if let Err(e) = result {
+        debug!("Failed to return to branch '{}': {}", branch, e);
+    }
+}
 
+/// Log lines of the commits reachable from `revision` that changed `file_path`.
+pub fn get_commits_for_file(
+    repo_path: &str,
+    revision: &str,
+    file_path: &str,
+) -> Result<Vec<String>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let commit = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve '{}': {}", revision, e))?;
+    let mut revwalk = repo
+        .revwalk()
+        .map_err(|e| format!("Failed to create revwalk: {}", e))?;
     revwalk
+        .push(commit.id())
+        .map_err(|e| format!("Failed to walk from '{}': {}", revision, e))?;
+
+    let commits = revwalk
         .filter_map(|oid| oid.ok())
         .filter_map(|oid| {
             let commit = repo.find_commit(oid).ok()?;
@@ -479,8 +1406,12 @@ pub fn get_commits_for_file(repo_path: &str, file_path: &str) -> Vec<String> {
                 let binding = commit.author();
                 let author = binding.name().unwrap_or("Unknown");
 
-                // Summary
-                let summary = commit.summary().unwrap_or("No message");
+                // Summary, marked when the commit is signed
+                let summary = format!(
+                    "{}{}",
+                    signing::log_badge(&repo, commit.id()),
+                    commit.summary().unwrap_or("No message")
+                );
 
                 // Combine all fields
                 Some(format!(
@@ -491,5 +1422,6 @@ pub fn get_commits_for_file(repo_path: &str, file_path: &str) -> Vec<String> {
                 None
             }
         })
-        .collect()
+        .collect();
+    Ok(commits)
 }
@@ -0,0 +1,147 @@
+use crate::diff::FileDiff;
+
+#[cfg(feature = "syntax-highlighting")]
+mod syntax {
+    use crate::diff::{DiffLine, FileDiff, LineKind, SyntaxSpan};
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::{Theme, ThemeSet};
+    use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+    const FALLBACK_THEME: &str = "base16-ocean.dark";
+
+    fn syntax_set() -> &'static SyntaxSet {
+        static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+        SYNTAXES.get_or_init(SyntaxSet::load_defaults_nonewlines)
+    }
+
+    fn theme_set() -> &'static ThemeSet {
+        static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+        THEMES.get_or_init(ThemeSet::load_defaults)
+    }
+
+    pub fn theme_names() -> Vec<&'static str> {
+        theme_set().themes.keys().map(String::as_str).collect()
+    }
+
+    // One of syntect's bundled themes, or the default one if there is no such theme
+    fn theme(name: &str) -> &'static Theme {
+        let themes = &theme_set().themes;
+        themes
+            .get(name)
+            .or_else(|| themes.get(FALLBACK_THEME))
+            .expect("syntect bundles its default theme")
+    }
+
+    fn find_syntax(path: &str) -> Option<&'static SyntaxReference> {
+        let path = Path::new(path);
+        let syntaxes = syntax_set();
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| syntaxes.find_syntax_by_extension(ext))
+            .or_else(|| {
+                let name = path.file_name()?.to_str()?;
+                syntaxes.find_syntax_by_extension(name)
+            })
+    }
+
+    fn highlight_side(syntax: &SyntaxReference, theme: &Theme, lines: Vec<&mut DiffLine>) {
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        for line in lines {
+            let Ok(ranges) = highlighter.highlight_line(&line.content, syntax_set()) else {
+                return;
+            };
+
+            let mut offset = 0;
+            line.syntax = ranges
+                .into_iter()
+                .map(|(style, text)| {
+                    let start = offset;
+                    offset += text.len();
+                    SyntaxSpan {
+                        start,
+                        end: offset,
+                        color: (style.foreground.r, style.foreground.g, style.foreground.b),
+                    }
+                })
+                .collect();
+        }
+    }
+
+    pub fn highlight_file(file: &mut FileDiff, theme_name: &str) {
+        let Some(syntax) = find_syntax(&file.path) else {
+            return;
+        };
+        let theme = theme(theme_name);
+
+        // Highlight the old and new side of each hunk separately so that each pass sees
+        // a plausible sequence of source lines. Context lines end up with new-side colors.
+        for hunk in &mut file.hunks {
+            let old_side = hunk
+                .lines
+                .iter_mut()
+                .filter(|line| line.kind != LineKind::Added)
+                .collect();
+            highlight_side(syntax, theme, old_side);
+
+            let new_side = hunk
+                .lines
+                .iter_mut()
+                .filter(|line| line.kind != LineKind::Removed)
+                .collect();
+            highlight_side(syntax, theme, new_side);
+        }
+    }
+}
+
+/// Attaches syntax colors from the syntect theme `theme_name` to every diff line, based
+/// on each file's extension. Without the `syntax-highlighting` feature this only clears
+/// existing colors.
+pub fn highlight_files(files: &mut [FileDiff], theme_name: &str) {
+    clear_highlights(files);
+    #[cfg(feature = "syntax-highlighting")]
+    for file in files.iter_mut() {
+        syntax::highlight_file(file, theme_name);
+    }
+    #[cfg(not(feature = "syntax-highlighting"))]
+    let _ = theme_name;
+}
+
+/// Checks that `name` is one of syntect's bundled themes. Without the `syntax-highlighting`
+/// feature diffs aren't highlighted, so any name will do.
+pub fn check_syntax_theme(name: &str) -> Result<(), String> {
+    #[cfg(feature = "syntax-highlighting")]
+    {
+        let names = syntax::theme_names();
+        if !names.contains(&name) {
+            return Err(format!(
+                "syntax '{}' is not one of {} or \"none\"",
+                name,
+                names.join(", ")
+            ));
+        }
+    }
+    #[cfg(not(feature = "syntax-highlighting"))]
+    let _ = name;
+    Ok(())
+}
+
+/// Whether any line of the files has syntax highlighting to clear.
+pub fn is_highlighted(files: &[FileDiff]) -> bool {
+    files
+        .iter()
+        .flat_map(|file| file.hunks.iter())
+        .flat_map(|hunk| hunk.lines.iter())
+        .any(|line| !line.syntax.is_empty())
+}
+
+pub fn clear_highlights(files: &mut [FileDiff]) {
+    for line in files
+        .iter_mut()
+        .flat_map(|file| file.hunks.iter_mut())
+        .flat_map(|hunk| hunk.lines.iter_mut())
+    {
+        line.syntax.clear();
+    }
+}
@@ -0,0 +1,203 @@
+use git2::Repository;
+use log::debug;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+
+// Hooks that can stop an operation and are skipped when bypassing hooks, like `--no-verify`
+const VERIFY_HOOKS: &[&str] = &["pre-commit", "commit-msg", "pre-merge-commit", "pre-push"];
+
+/// Output of one hook run.
+#[derive(Clone)]
+pub struct HookRun {
+    pub name: String,
+    pub output: String, // Combined stdout and stderr
+    pub success: bool,
+    pub status: String, // Exit status description, e.g. "exit status: 1"
+}
+
+/// Progress of a hook, sent while it runs so its output can be shown as it comes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HookEvent {
+    Started(String), // Hook name
+    Output(String),  // One line of stdout or stderr
+    Finished { success: bool, status: String },
+}
+
+/// Runs the repository's hooks during one operation and collects their output.
+pub struct Hooks {
+    pub verify: bool, // When false, hooks that can stop the operation are skipped
+    pub runs: Vec<HookRun>,
+    events: Option<Sender<HookEvent>>, // Receives the output of each hook while it runs
+}
+
+impl Hooks {
+    pub fn new(verify: bool) -> Self {
+        Self {
+            verify,
+            runs: Vec::new(),
+            events: None,
+        }
+    }
+
+    /// Hooks that also send their progress to `events` while they run.
+    pub fn streaming(verify: bool, events: Sender<HookEvent>) -> Self {
+        Self {
+            events: Some(events),
+            ..Self::new(verify)
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.runs.iter().any(|run| !run.success)
+    }
+
+    /// Runs the hook `name` if the repository has one, failing if it exits with a
+    /// non-zero status.
+    pub fn run(
+        &mut self,
+        repo: &Repository,
+        name: &str,
+        args: &[&str],
+        stdin: Option<&str>,
+    ) -> Result<(), String> {
+        if !self.verify && VERIFY_HOOKS.contains(&name) {
+            debug!("Skipping the {} hook", name);
+            return Ok(());
+        }
+        let Some(run) = run_hook(repo, name, args, stdin, self.events.as_ref())? else {
+            return Ok(());
+        };
+        let result = if run.success {
+            Ok(())
+        } else {
+            Err(format!("The {} hook failed ({})", name, run.status))
+        };
+        self.runs.push(run);
+        result
+    }
+
+    /// Runs a hook whose exit status doesn't matter, like `post-commit`.
+    pub fn notify(&mut self, repo: &Repository, name: &str, args: &[&str]) {
+        match run_hook(repo, name, args, None, self.events.as_ref()) {
+            Ok(Some(run)) => self.runs.push(run),
+            Ok(None) => {}
+            Err(err) => debug!("{}", err),
+        }
+    }
+}
+
+/// Directory the repository's hooks live in: `core.hooksPath`, or `.git/hooks`.
+pub fn hooks_dir(repo: &Repository) -> PathBuf {
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok());
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => working_dir(repo).join(path), // Relative to where hooks run
+        None => repo.path().join("hooks"),
+    }
+}
+
+// Hooks run at the root of the working tree, or in the git directory of bare repositories
+fn working_dir(repo: &Repository) -> &Path {
+    repo.workdir().unwrap_or(repo.path())
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+// Run one hook, or return None if the repository doesn't have it
+fn run_hook(
+    repo: &Repository,
+    name: &str,
+    args: &[&str],
+    stdin: Option<&str>,
+    events: Option<&Sender<HookEvent>>,
+) -> Result<Option<HookRun>, String> {
+    let path = hooks_dir(repo).join(name);
+    if !is_executable(&path) {
+        return Ok(None);
+    }
+    debug!("Running the {} hook", name);
+    // Nobody may be listening any more, which doesn't stop the hook
+    let send = |event: HookEvent| {
+        if let Some(events) = events {
+            events.send(event).ok();
+        }
+    };
+    send(HookEvent::Started(name.to_string()));
+
+    // Send stdout and stderr through one pipe to keep their output in order
+    let (reader, writer) =
+        std::io::pipe().map_err(|e| format!("Failed to run the {} hook: {}", name, e))?;
+    let mut child = {
+        let stderr = writer
+            .try_clone()
+            .map_err(|e| format!("Failed to run the {} hook: {}", name, e))?;
+        let mut command = Command::new(&path);
+        command
+            .args(args)
+            .current_dir(working_dir(repo))
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(writer)
+            .stderr(stderr);
+        command
+            .spawn()
+            .map_err(|e| format!("Failed to run the {} hook: {}", name, e))?
+        // The command's copies of the pipe are dropped here, so reading ends with the hook
+    };
+
+    if let (Some(input), Some(mut child_stdin)) = (stdin, child.stdin.take()) {
+        // The hook may exit without reading its input
+        child_stdin.write_all(input.as_bytes()).ok();
+    }
+    // Pass on each line as the hook prints it
+    let mut reader = BufReader::new(reader);
+    let mut output = String::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("Failed to read the output of the {} hook: {}", name, e))?;
+        if read == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        output.push_str(&text);
+        send(HookEvent::Output(
+            text.trim_end_matches(['\n', '\r']).to_string(),
+        ));
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for the {} hook: {}", name, e))?;
+    send(HookEvent::Finished {
+        success: status.success(),
+        status: status.to_string(),
+    });
+
+    Ok(Some(HookRun {
+        name: name.to_string(),
+        output,
+        success: status.success(),
+        status: status.to_string(),
+    }))
+}
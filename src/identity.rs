@@ -0,0 +1,129 @@
+use git2::{Config, ConfigLevel, Repository, Signature};
+use std::env;
+use std::path::PathBuf;
+
+/// Whose identity to resolve. Git lets both be overridden separately.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Role {
+    Author,
+    Committer,
+}
+
+impl Role {
+    fn env_prefix(self) -> &'static str {
+        match self {
+            Role::Author => "GIT_AUTHOR",
+            Role::Committer => "GIT_COMMITTER",
+        }
+    }
+
+    fn config_section(self) -> &'static str {
+        match self {
+            Role::Author => "author",
+            Role::Committer => "committer",
+        }
+    }
+}
+
+/// Where to save an identity entered in the identity prompt.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IdentityScope {
+    Global,     // user.name and user.email in ~/.gitconfig
+    Repository, // user.name and user.email in .git/config, overriding the global identity
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+impl std::fmt::Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+/// Resolves an identity like git does: the `GIT_AUTHOR_*`/`GIT_COMMITTER_*` environment
+/// variables, then `author.*`/`committer.*` and finally `user.*` from the repository,
+/// global and system config.
+pub fn resolve(repo: &Repository, role: Role) -> Result<Identity, String> {
+    let config = repo
+        .config()
+        .map_err(|e| format!("Failed to read git config: {}", e))?;
+    let lookup = |field: &str| {
+        let non_empty = |value: String| (!value.trim().is_empty()).then_some(value);
+        env::var(format!("{}_{}", role.env_prefix(), field.to_uppercase()))
+            .ok()
+            .and_then(non_empty)
+            .or_else(|| {
+                config
+                    .get_string(&format!("{}.{}", role.config_section(), field))
+                    .ok()
+                    .and_then(non_empty)
+            })
+            .or_else(|| {
+                config
+                    .get_string(&format!("user.{}", field))
+                    .ok()
+                    .and_then(non_empty)
+            })
+    };
+
+    match (lookup("name"), lookup("email")) {
+        (Some(name), Some(email)) => Ok(Identity { name, email }),
+        _ => Err(format!(
+            "The {} identity is unknown; set user.name and user.email in your git config",
+            role.config_section()
+        )),
+    }
+}
+
+/// Signature with the current time for commits created as `role`.
+pub fn signature(repo: &Repository, role: Role) -> Result<Signature<'static>, String> {
+    let identity = resolve(repo, role)?;
+    Signature::now(&identity.name, &identity.email)
+        .map_err(|e| format!("Failed to create signature: {}", e))
+}
+
+/// Whether commits can be created without asking for a name and email first.
+pub fn is_configured(repo_path: &str) -> bool {
+    Repository::open(repo_path).is_ok_and(|repo| {
+        resolve(&repo, Role::Author).is_ok() && resolve(&repo, Role::Committer).is_ok()
+    })
+}
+
+/// The identity new commits are authored with, to prefill the identity prompt.
+pub fn current_author(repo_path: &str) -> Option<Identity> {
+    let repo = Repository::open(repo_path).ok()?;
+    resolve(&repo, Role::Author).ok()
+}
+
+/// Saves a name and email as the identity in the global config, or in this repository's config.
+pub fn save(repo_path: &str, scope: IdentityScope, name: &str, email: &str) -> Result<(), String> {
+    let mut config = match scope {
+        IdentityScope::Global => global_config()?,
+        IdentityScope::Repository => {
+            let repo = Repository::open(repo_path)
+                .map_err(|e| format!("Failed to open repository: {}", e))?;
+            repo.config()
+                .and_then(|config| config.open_level(ConfigLevel::Local))
+                .map_err(|e| format!("Failed to open repository config: {}", e))?
+        }
+    };
+    // Both the author and the committer of new commits, like `git config --local user.*`
+    config
+        .set_str("user.name", name.trim())
+        .and_then(|_| config.set_str("user.email", email.trim()))
+        .map_err(|e| format!("Failed to save identity: {}", e))
+}
+
+// ~/.gitconfig, created if this is the first thing ever configured
+fn global_config() -> Result<Config, String> {
+    let path = Config::find_global().or_else(|_| {
+        env::var("HOME")
+            .map(|home| PathBuf::from(home).join(".gitconfig"))
+            .map_err(|_| "Cannot find the global git config".to_string())
+    })?;
+    Config::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
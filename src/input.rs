@@ -1,5 +1,10 @@
-use crossterm::event::{Event, KeyCode};
+use crate::keymap::{KeyChord, KeyContext, Keymap};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+use std::time::{Duration, Instant};
 
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+#[derive(Clone, PartialEq, Debug)]
 pub enum Action {
     Quit,
     Refresh,
@@ -9,56 +14,143 @@ pub enum Action {
     NavigateRight,
     NavigatePageUp,
     NavigatePageDown,
+    NavigateTop,
+    NavigateBottom,
     Select,
     SwitchPanel,
     Deselect,
     CommitWork,
     CreateBranch,
     ShowKeyGuide,
+    ShowMessages, // History of operation results
     MergeBranch,
     FilterCommitsByFile,
+    NextFile,
+    PreviousFile,
+    NextHunk,
+    PreviousHunk,
+    ToggleSyntaxHighlight,
+    ToggleDiffLayout,
+    ToggleDiffStat,
+    TogglePreview, // Show the selected commit's details beside the Commit Log
+    ShowUnstagedChanges,
+    ShowStagedChanges,
+    MarkForCompare,
+    BlameFile,
+    BlameParent,
+    BrowseFiles,
+    FileHistory,
+    AmendCommit,
+    RewordCommit,
+    FixupCommit,
+    Autosquash,
+    BypassHooks, // Retry an operation stopped by a hook, like --no-verify
+    SetIdentity, // Set the name and email for commits in this repository
 
     // Text input actions
     TextInput(char), // Character input for text mode
     Backspace,       // Handle backspace during text input
     Confirm,         // Confirm (e.g., Enter key) during text input
     Cancel,          // Cancel (e.g., Escape key) during text input
+    Complete,        // Complete the input (e.g., Tab key) during text input
+    Delete,          // Delete the character under the cursor
+    DeleteWordBackward,
+    DeleteToLineStart,
+    CursorLeft,
+    CursorRight,
+    CursorWordLeft,
+    CursorWordRight,
+    CursorHome, // Start of the current line
+    CursorEnd,  // End of the current line
+    NewLine,    // Line break in multi-line inputs
+    Undo,
+    Paste(String),  // Text pasted into the terminal (bracketed paste)
+    Submit,         // Submit a multi-line input, where Enter inserts a line break
+    SignOff,        // Add a Signed-off-by trailer to the commit message
+    AddCoAuthor,    // Add a Co-authored-by trailer to the commit message
+    OpenEditor,     // Edit the commit message in $EDITOR
+    PickCommitType, // Pick a conventional commit type and scope for the subject
+
+    // Mouse actions, at a column and row of the terminal
+    Click(u16, u16),
+    DoubleClick(u16, u16),
+    ScrollUp(u16, u16),
+    ScrollDown(u16, u16),
+    Drag(u16, u16),    // Moved with the left button held
+    Release(u16, u16), // Left button let go
+
+    Resize(u16, u16), // The terminal's new width and height
+}
+
+/// Turns a second click on the same cell soon after the first into a double-click.
+#[derive(Default)]
+pub struct ClickTracker {
+    last_click: Option<(Instant, u16, u16)>,
+}
+
+impl ClickTracker {
+    pub fn click(&mut self, column: u16, row: u16) -> Action {
+        let now = Instant::now();
+        let double = self
+            .last_click
+            .is_some_and(|(time, last_column, last_row)| {
+                (last_column, last_row) == (column, row) && now - time <= DOUBLE_CLICK_TIME
+            });
+        if double {
+            self.last_click = None; // A third click starts over
+            Action::DoubleClick(column, row)
+        } else {
+            self.last_click = Some((now, column, row));
+            Action::Click(column, row)
+        }
+    }
 }
 
-pub fn handle_user_input(is_text_mode: bool) -> Result<Option<Action>, std::io::Error> {
-    if crossterm::event::poll(std::time::Duration::from_millis(200))? {
-        if let Event::Key(key) = crossterm::event::read()? {
-            if is_text_mode {
-                // Handle input in text mode
-                return Ok(match key.code {
-                    KeyCode::Char(c) => Some(Action::TextInput(c)), // Capture character input
-                    KeyCode::Backspace => Some(Action::Backspace),  // Handle backspace
-                    KeyCode::Enter => Some(Action::Confirm),        // Confirm input
-                    KeyCode::Esc => Some(Action::Cancel),           // Cancel text input
-                    _ => None,
-                });
-            } else {
-                return Ok(match key.code {
-                    KeyCode::Char('q') => Some(Action::Quit),
-                    KeyCode::Char('r') => Some(Action::Refresh),
-                    KeyCode::Up => Some(Action::NavigateUp),
-                    KeyCode::Down => Some(Action::NavigateDown),
-                    KeyCode::Left => Some(Action::NavigateLeft),
-                    KeyCode::Right => Some(Action::NavigateRight),
-                    KeyCode::PageUp => Some(Action::NavigatePageUp),
-                    KeyCode::PageDown => Some(Action::NavigatePageDown),
-                    KeyCode::Enter => Some(Action::Select),
-                    KeyCode::Tab => Some(Action::SwitchPanel), // Switch between Commit Log and Branches
-                    KeyCode::Esc => Some(Action::Deselect),
-                    KeyCode::Char('c') => Some(Action::CommitWork),
-                    KeyCode::Char('b') => Some(Action::CreateBranch),
-                    KeyCode::Char('g') => Some(Action::ShowKeyGuide),
-                    KeyCode::Char('m') => Some(Action::MergeBranch),
-                    KeyCode::Char('f') => Some(Action::FilterCommitsByFile),
-                    _ => None,
-                });
+/// Waits briefly for a key press and looks it up in the keymap. Unbound characters are
+/// typed into text inputs. Clicks, drags and the scroll wheel give mouse actions, and resizing
+/// the terminal gives `Resize`.
+pub fn handle_user_input(
+    keymap: &mut Keymap,
+    contexts: &[KeyContext],
+    clicks: &mut ClickTracker,
+) -> Result<Option<Action>, std::io::Error> {
+    let is_text_mode = contexts.contains(&KeyContext::TextInput);
+    if !crossterm::event::poll(std::time::Duration::from_millis(200))? {
+        return Ok(keymap.flush_expired(contexts)); // A sequence was left unfinished
+    }
+    match crossterm::event::read()? {
+        Event::Paste(text) if is_text_mode => Ok(Some(Action::Paste(text))),
+        Event::Key(key) => {
+            let chord = KeyChord::from_event(&key);
+            let was_pending = keymap.is_pending();
+            if let Some(action) = keymap.resolve(contexts, chord) {
+                return Ok(Some(action));
+            }
+            if was_pending || keymap.is_pending() {
+                return Ok(None);
             }
+
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            let alt = key.modifiers.contains(KeyModifiers::ALT);
+            Ok(match key.code {
+                KeyCode::Char(c) if is_text_mode && ctrl == alt => Some(Action::TextInput(c)), // Ctrl+Alt is AltGr
+                KeyCode::Char(_) => None, // Unbound shortcut
+                // Other keys work the same with modifiers that aren't bound, like Shift+↑
+                code if !chord.modifiers.is_empty() => {
+                    keymap.resolve(contexts, KeyChord::new(code, KeyModifiers::NONE))
+                }
+                _ => None,
+            })
         }
+        Event::Mouse(mouse) => Ok(match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => Some(clicks.click(mouse.column, mouse.row)),
+            MouseEventKind::ScrollUp => Some(Action::ScrollUp(mouse.column, mouse.row)),
+            MouseEventKind::ScrollDown => Some(Action::ScrollDown(mouse.column, mouse.row)),
+            MouseEventKind::Drag(MouseButton::Left) => Some(Action::Drag(mouse.column, mouse.row)),
+            MouseEventKind::Up(MouseButton::Left) => Some(Action::Release(mouse.column, mouse.row)),
+            _ => None,
+        }),
+        Event::Resize(width, height) => Ok(Some(Action::Resize(width, height))),
+        _ => Ok(None),
     }
-    Ok(None)
 }
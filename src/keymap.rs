@@ -0,0 +1,828 @@
+use crate::input::Action;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use std::{env, fmt, fs};
+
+/// How long a started sequence like `g g` waits for its next key.
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where a binding applies. Command mode keys come from `Normal`, with `CommitDetails`
+/// taking precedence while the commit details are shown.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyContext {
+    Normal,
+    CommitDetails,
+    TextInput,
+}
+
+impl KeyContext {
+    const ALL: [KeyContext; 3] = [
+        KeyContext::Normal,
+        KeyContext::CommitDetails,
+        KeyContext::TextInput,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            KeyContext::Normal => "Everywhere",
+            KeyContext::CommitDetails => "In Commit Details",
+            KeyContext::TextInput => "In prompts and the commit message",
+        }
+    }
+}
+
+/// One key press with its modifiers, like `ctrl+s`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn from_event(key: &KeyEvent) -> Self {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        // Shifted characters arrive as uppercase characters, which is how they are bound
+        if key.modifiers.contains(KeyModifiers::SHIFT)
+            && !matches!(key.code, KeyCode::Char(_) | KeyCode::BackTab)
+        {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+        Self::new(key.code, modifiers)
+    }
+
+    /// Parses a chord like `q`, `G`, `pagedown`, `ctrl+s` or `alt+enter`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = text;
+        while let Some((modifier, rest)) = key.split_once('+') {
+            if rest.is_empty() {
+                break; // The `+` key itself
+            }
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, text)),
+            };
+            key = rest;
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "enter" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => {
+                        return Err(format!(
+                            "unknown key '{}' (separate the keys of a sequence with spaces, like \"g g\")",
+                            key
+                        ))
+                    }
+                },
+            },
+        };
+
+        // Shift+letter is the uppercase letter
+        if let KeyCode::Char(c) = code {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                modifiers -= KeyModifiers::SHIFT;
+                return Ok(Self::new(KeyCode::Char(c.to_ascii_uppercase()), modifiers));
+            }
+        }
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::Esc => f.write_str("Esc"),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+// Every bindable action with its name in keys.toml and its description in the key guide
+fn actions() -> Vec<(&'static str, Action, &'static str)> {
+    use Action::*;
+    vec![
+        ("quit", Quit, "Quit the application (asks for confirmation)"),
+        (
+            "refresh",
+            Refresh,
+            "Refresh the Commit Log and Branches list",
+        ),
+        (
+            "navigate_up",
+            NavigateUp,
+            "Move up through items, lines or earlier commit messages",
+        ),
+        (
+            "navigate_down",
+            NavigateDown,
+            "Move down through items, lines or later commit messages",
+        ),
+        (
+            "navigate_left",
+            NavigateLeft,
+            "Scroll left in the Commit Log",
+        ),
+        (
+            "navigate_right",
+            NavigateRight,
+            "Scroll right in the Commit Log",
+        ),
+        ("page_up", NavigatePageUp, "Jump one page up"),
+        ("page_down", NavigatePageDown, "Jump one page down"),
+        ("go_to_top", NavigateTop, "Jump to the first item"),
+        ("go_to_bottom", NavigateBottom, "Jump to the last item"),
+        (
+            "select",
+            Select,
+            "Select an item, confirm an action, or proceed",
+        ),
+        (
+            "switch_panel",
+            SwitchPanel,
+            "Switch between the Commit Log and Branches panels",
+        ),
+        (
+            "back",
+            Deselect,
+            "Cancel the current action, return to the previous screen, or close error messages",
+        ),
+        (
+            "commit",
+            CommitWork,
+            "Start the commit workflow (add, commit and push changes)",
+        ),
+        (
+            "create_branch",
+            CreateBranch,
+            "Create and switch to a new branch",
+        ),
+        ("key_guide", ShowKeyGuide, "Open this Key Guide"),
+        (
+            "messages",
+            ShowMessages,
+            "Show the results and errors of earlier operations",
+        ),
+        (
+            "merge",
+            MergeBranch,
+            "Merge the current branch into the main or master branch",
+        ),
+        (
+            "filter_by_file",
+            FilterCommitsByFile,
+            "Filter commits by a file, picked from a fuzzy-matched list of tracked files",
+        ),
+        ("next_file", NextFile, "Jump to the next file"),
+        ("previous_file", PreviousFile, "Jump to the previous file"),
+        ("next_hunk", NextHunk, "Jump to the next hunk"),
+        ("previous_hunk", PreviousHunk, "Jump to the previous hunk"),
+        (
+            "toggle_syntax_highlight",
+            ToggleSyntaxHighlight,
+            "Toggle syntax highlighting",
+        ),
+        (
+            "toggle_diff_layout",
+            ToggleDiffLayout,
+            "Switch between unified and side-by-side diffs",
+        ),
+        (
+            "toggle_diff_stat",
+            ToggleDiffStat,
+            "Show only the diffstat; select opens the highlighted file",
+        ),
+        (
+            "toggle_preview",
+            TogglePreview,
+            "Show or hide the details of the selected commit next to the Commit Log",
+        ),
+        (
+            "unstaged_changes",
+            ShowUnstagedChanges,
+            "Show unstaged changes in the working tree",
+        ),
+        (
+            "staged_changes",
+            ShowStagedChanges,
+            "Show staged changes in the index",
+        ),
+        (
+            "mark_for_compare",
+            MarkForCompare,
+            "Mark the selected commit or branch, then another one to compare them",
+        ),
+        (
+            "blame",
+            BlameFile,
+            "Blame the highlighted file; select opens a line's commit",
+        ),
+        (
+            "blame_parent",
+            BlameParent,
+            "In Blame, re-blame at the parent of the line's commit",
+        ),
+        (
+            "browse_files",
+            BrowseFiles,
+            "Browse the files of the selected commit or branch",
+        ),
+        (
+            "file_history",
+            FileHistory,
+            "In the file browser, show the commits that changed the file",
+        ),
+        (
+            "amend",
+            AmendCommit,
+            "Amend the last commit with the staged changes and a new message",
+        ),
+        ("reword", RewordCommit, "Reword the selected commit"),
+        (
+            "fixup",
+            FixupCommit,
+            "Create a fixup! commit for the selected commit from the staged changes",
+        ),
+        (
+            "autosquash",
+            Autosquash,
+            "When creating a fixup commit, also squash it into its commit",
+        ),
+        (
+            "bypass_hooks",
+            BypassHooks,
+            "After a hook stops a commit, fixup or merge, retry without the hooks (like --no-verify)",
+        ),
+        (
+            "set_identity",
+            SetIdentity,
+            "Set the name and email for commits in this repository",
+        ),
+        (
+            "backspace",
+            Backspace,
+            "Delete the character before the cursor",
+        ),
+        ("delete", Delete, "Delete the character under the cursor"),
+        (
+            "delete_word_backward",
+            DeleteWordBackward,
+            "Delete the word before the cursor",
+        ),
+        (
+            "delete_to_line_start",
+            DeleteToLineStart,
+            "Delete to the start of the line",
+        ),
+        ("cursor_left", CursorLeft, "Move the cursor left"),
+        ("cursor_right", CursorRight, "Move the cursor right"),
+        (
+            "cursor_word_left",
+            CursorWordLeft,
+            "Move the cursor one word left",
+        ),
+        (
+            "cursor_word_right",
+            CursorWordRight,
+            "Move the cursor one word right",
+        ),
+        ("cursor_home", CursorHome, "Jump to the start of the line"),
+        ("cursor_end", CursorEnd, "Jump to the end of the line"),
+        ("new_line", NewLine, "Add a line"),
+        ("undo", Undo, "Undo the last edit"),
+        (
+            "confirm",
+            Confirm,
+            "Confirm the input; adds a line in the commit message",
+        ),
+        ("cancel", Cancel, "Cancel the prompt"),
+        (
+            "complete",
+            Complete,
+            "Complete the file path, or switch between identity fields",
+        ),
+        ("submit", Submit, "Commit the message"),
+        ("sign_off", SignOff, "Add a Signed-off-by trailer"),
+        ("add_co_author", AddCoAuthor, "Add a Co-authored-by trailer"),
+        (
+            "open_editor",
+            OpenEditor,
+            "Edit the commit message in $EDITOR",
+        ),
+        (
+            "pick_commit_type",
+            PickCommitType,
+            "Pick a conventional commit type and scope",
+        ),
+    ]
+}
+
+const DEFAULT_KEYS: &str = r#"
+[normal]
+quit = "q"
+refresh = "r"
+navigate_up = "up"
+navigate_down = "down"
+navigate_left = "left"
+navigate_right = "right"
+page_up = "pageup"
+page_down = "pagedown"
+go_to_top = "home"
+go_to_bottom = "end"
+select = "enter"
+switch_panel = "tab"
+back = "esc"
+commit = "c"
+create_branch = "b"
+key_guide = "g"
+merge = "m"
+filter_by_file = "f"
+unstaged_changes = "d"
+staged_changes = "D"
+mark_for_compare = "x"
+blame = "B"
+blame_parent = "p"
+browse_files = "t"
+file_history = "L"
+amend = "A"
+reword = "R"
+fixup = "F"
+autosquash = "a"
+bypass_hooks = "V"
+set_identity = "I"
+toggle_preview = "P"
+messages = "M"
+
+[commit_details]
+next_file = "]"
+previous_file = "["
+next_hunk = "n"
+previous_hunk = "N"
+toggle_syntax_highlight = "H"
+toggle_diff_layout = "v"
+toggle_diff_stat = "S"
+
+[text_input]
+cursor_home = ["ctrl+a", "home"]
+cursor_end = ["ctrl+e", "end"]
+cursor_left = ["left", "ctrl+b"]
+cursor_right = ["right", "ctrl+f"]
+cursor_word_left = ["ctrl+left", "alt+left", "alt+b"]
+cursor_word_right = ["ctrl+right", "alt+right", "alt+f"]
+backspace = "backspace"
+delete = ["delete", "ctrl+d"]
+delete_word_backward = ["ctrl+w", "ctrl+backspace", "alt+backspace"]
+delete_to_line_start = "ctrl+u"
+undo = "ctrl+z"
+new_line = ["alt+enter", "shift+enter"]
+confirm = "enter"
+cancel = "esc"
+complete = "tab"
+navigate_up = "up"
+navigate_down = "down"
+submit = "ctrl+s"
+sign_off = "ctrl+o"
+add_co_author = "ctrl+t"
+open_editor = "ctrl+g"
+pick_commit_type = "ctrl+p"
+"#;
+
+// Applied on top of the defaults with `preset = "vim"`
+const VIM_KEYS: &str = r#"
+[normal]
+navigate_up = ["k", "up"]
+navigate_down = ["j", "down"]
+navigate_left = ["h", "left"]
+navigate_right = ["l", "right"]
+page_up = ["ctrl+u", "ctrl+b", "pageup"]
+page_down = ["ctrl+d", "ctrl+f", "pagedown"]
+go_to_top = ["g g", "home"]
+go_to_bottom = ["G", "end"]
+key_guide = "?"
+
+[commit_details]
+next_file = ["]", "J"]
+previous_file = ["[", "K"]
+"#;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct KeysFile {
+    preset: Option<String>,
+    #[serde(default)]
+    normal: BTreeMap<String, KeyList>,
+    #[serde(default)]
+    commit_details: BTreeMap<String, KeyList>,
+    #[serde(default)]
+    text_input: BTreeMap<String, KeyList>,
+}
+
+struct Binding {
+    context: KeyContext,
+    keys: Vec<KeyChord>, // More than one for sequences like `g g`
+    action: Action,
+}
+
+/// Maps key chords and sequences to actions, per context.
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    pending: Vec<KeyChord>,    // Keys typed so far of a sequence
+    last_key: Option<Instant>, // When the last key of `pending` was typed
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+            pending: Vec::new(),
+            last_key: None,
+        };
+        keymap
+            .apply(DEFAULT_KEYS)
+            .expect("the default keymap is valid");
+        keymap
+    }
+}
+
+impl Keymap {
+    /// The default keymap with a preset applied: `default` or `vim`.
+    pub fn preset(name: &str) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        match name {
+            "default" => {}
+            "vim" => keymap.apply(VIM_KEYS)?,
+            _ => return Err(format!("unknown preset '{}' (try \"vim\")", name)),
+        }
+        Ok(keymap)
+    }
+
+    /// The keymap from `keys.toml` in the ezgit config directory, or the default one if
+    /// there is no such file.
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = keys_path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                Self::from_toml(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        let file: KeysFile = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+        let mut keymap = Self::preset(file.preset.as_deref().unwrap_or("default"))?;
+        keymap.apply_file(file)?;
+        Ok(keymap)
+    }
+
+    fn apply(&mut self, contents: &str) -> Result<(), String> {
+        let file: KeysFile = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+        self.apply_file(file)
+    }
+
+    // Each action listed replaces the keys it had in that context
+    fn apply_file(&mut self, file: KeysFile) -> Result<(), String> {
+        let actions = actions();
+        for (context, section, entries) in [
+            (KeyContext::Normal, "normal", file.normal),
+            (
+                KeyContext::CommitDetails,
+                "commit_details",
+                file.commit_details,
+            ),
+            (KeyContext::TextInput, "text_input", file.text_input),
+        ] {
+            for (name, keys) in entries {
+                let Some((_, action, _)) = actions.iter().find(|(n, _, _)| *n == name) else {
+                    return Err(format!("unknown action '{}' in [{}]", name, section));
+                };
+                let keys = match keys {
+                    KeyList::One(key) => vec![key],
+                    KeyList::Many(keys) => keys,
+                };
+                let sequences = keys
+                    .iter()
+                    .map(|key| key.split_whitespace().map(KeyChord::parse).collect())
+                    .collect::<Result<Vec<Vec<KeyChord>>, String>>()
+                    .map_err(|e| format!("[{}] {}: {}", section, name, e))?;
+
+                self.bindings
+                    .retain(|binding| binding.context != context || binding.action != *action);
+                for keys in sequences.into_iter().filter(|keys| !keys.is_empty()) {
+                    // A key can only do one thing in a context
+                    self.bindings
+                        .retain(|binding| binding.context != context || binding.keys != keys);
+                    self.bindings.push(Binding {
+                        context,
+                        keys,
+                        action: action.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The action for a key press in the first of `contexts` that binds it. Keys that start
+    /// a longer sequence wait for the next key, or for `flush_expired` when none comes.
+    pub fn resolve(&mut self, contexts: &[KeyContext], chord: KeyChord) -> Option<Action> {
+        self.pending.push(chord);
+        self.last_key = Some(Instant::now());
+        let candidates: Vec<&Binding> = contexts
+            .iter()
+            .flat_map(|context| {
+                self.bindings
+                    .iter()
+                    .filter(move |binding| binding.context == *context)
+            })
+            .filter(|binding| binding.keys.starts_with(&self.pending))
+            .collect();
+
+        if candidates.is_empty() {
+            let retry = self.pending.len() > 1; // The key may start something new
+            self.pending.clear();
+            return if retry {
+                self.resolve(contexts, chord)
+            } else {
+                None
+            };
+        }
+        if candidates
+            .iter()
+            .any(|binding| binding.keys.len() > self.pending.len())
+        {
+            return None;
+        }
+        let action = candidates[0].action.clone();
+        self.pending.clear();
+        Some(action)
+    }
+
+    /// Gives up on a sequence once no key came for `SEQUENCE_TIMEOUT`, like `flush_pending`.
+    pub fn flush_expired(&mut self, contexts: &[KeyContext]) -> Option<Action> {
+        let expired = self
+            .last_key
+            .is_some_and(|last_key| last_key.elapsed() >= SEQUENCE_TIMEOUT);
+        if expired {
+            self.flush_pending(contexts)
+        } else {
+            None
+        }
+    }
+
+    /// Gives up waiting for the rest of a sequence, running the binding of the keys typed
+    /// so far if there is one.
+    pub fn flush_pending(&mut self, contexts: &[KeyContext]) -> Option<Action> {
+        self.last_key = None;
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            return None;
+        }
+        contexts.iter().find_map(|context| {
+            self.bindings
+                .iter()
+                .find(|binding| binding.context == *context && binding.keys == pending)
+                .map(|binding| binding.action.clone())
+        })
+    }
+
+    /// Keys bound to `action` in `context`, like `g g/Home`.
+    pub fn keys_for(&self, context: KeyContext, action: &Action) -> Option<String> {
+        let keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|binding| binding.context == context && binding.action == *action)
+            .map(|binding| {
+                binding
+                    .keys
+                    .iter()
+                    .map(|chord| chord.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        (!keys.is_empty()).then(|| keys.join("/"))
+    }
+
+    /// Key guide lines for the bindings of every context.
+    pub fn guide(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for context in KeyContext::ALL {
+            lines.push(format!("{}:", context.title()));
+            for (_, action, description) in actions() {
+                if let Some(keys) = self.keys_for(context, &action) {
+                    lines.push(format!("  - {}: {}", keys, description));
+                }
+            }
+            lines.push(String::new());
+        }
+        lines.pop();
+        lines
+    }
+}
+
+/// `keys.toml` in `$XDG_CONFIG_HOME/ezgit`, or `~/.config/ezgit`.
+pub fn keys_path() -> Option<PathBuf> {
+    config_dir().map(|directory| directory.join("keys.toml"))
+}
+
+/// The ezgit config directory: `$XDG_CONFIG_HOME/ezgit`, or `~/.config/ezgit`.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|directory| directory.join("ezgit"))
+}
+
+/// The name of `action` in `keys.toml`, for actions that can be bound.
+pub fn action_name(action: &Action) -> Option<&'static str> {
+    actions()
+        .into_iter()
+        .find(|(_, bound, _)| bound == action)
+        .map(|(name, _, _)| name)
+}
+
+/// The action called `name` in `keys.toml`.
+pub fn named_action(name: &str) -> Option<Action> {
+    actions()
+        .into_iter()
+        .find(|(bound, _, _)| *bound == name)
+        .map(|(_, action, _)| action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORMAL: &[KeyContext] = &[KeyContext::Normal];
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    #[test]
+    fn chords_parse_with_modifiers() {
+        assert_eq!(
+            chord("q"),
+            KeyChord::new(KeyCode::Char('q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            chord("Ctrl+s"),
+            KeyChord::new(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            chord("alt+enter"),
+            KeyChord::new(KeyCode::Enter, KeyModifiers::ALT)
+        );
+        assert_eq!(
+            chord("ctrl++"),
+            KeyChord::new(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            chord("space"),
+            KeyChord::new(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            chord("f5"),
+            KeyChord::new(KeyCode::F(5), KeyModifiers::NONE)
+        );
+        // Shift+letter is the uppercase letter, as the terminal sends it
+        assert_eq!(chord("shift+g"), chord("G"));
+        assert_eq!(chord("G").to_string(), "G");
+        assert_eq!(chord("ctrl+s").to_string(), "Ctrl+S");
+    }
+
+    #[test]
+    fn unknown_keys_and_modifiers_are_errors() {
+        assert!(KeyChord::parse("hyper+q").is_err());
+        assert!(KeyChord::parse("f13").is_err());
+        let err = KeyChord::parse("gg").unwrap_err();
+        assert!(err.contains("like \"g g\""), "{}", err);
+    }
+
+    #[test]
+    fn sequences_wait_for_their_next_key() {
+        let mut keymap = Keymap::preset("vim").unwrap();
+        assert_eq!(keymap.resolve(NORMAL, chord("g")), None);
+        assert!(keymap.is_pending());
+        // Too early to give up on the sequence
+        assert_eq!(keymap.flush_expired(NORMAL), None);
+        assert!(keymap.is_pending());
+        assert_eq!(
+            keymap.resolve(NORMAL, chord("g")),
+            Some(Action::NavigateTop)
+        );
+        assert!(!keymap.is_pending());
+    }
+
+    #[test]
+    fn a_broken_sequence_retries_the_last_key() {
+        let mut keymap = Keymap::preset("vim").unwrap();
+        keymap.resolve(NORMAL, chord("g"));
+        assert_eq!(
+            keymap.resolve(NORMAL, chord("j")),
+            Some(Action::NavigateDown)
+        );
+        assert!(!keymap.is_pending());
+    }
+
+    #[test]
+    fn an_unfinished_sequence_runs_its_own_binding() {
+        let mut keymap = Keymap::from_toml(
+            r#"
+            [normal]
+            key_guide = "g"
+            go_to_top = "g g"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(keymap.resolve(NORMAL, chord("g")), None);
+        assert_eq!(keymap.flush_pending(NORMAL), Some(Action::ShowKeyGuide));
+        assert_eq!(keymap.flush_pending(NORMAL), None);
+    }
+
+    #[test]
+    fn later_contexts_only_apply_when_earlier_ones_do_not_bind_the_key() {
+        let mut keymap = Keymap::default();
+        let contexts = [KeyContext::CommitDetails, KeyContext::Normal];
+        assert_eq!(
+            keymap.resolve(&contexts, chord("n")),
+            Some(Action::NextHunk)
+        );
+        assert_eq!(keymap.resolve(&contexts, chord("q")), Some(Action::Quit));
+        assert_eq!(keymap.resolve(NORMAL, chord("n")), None);
+    }
+
+    #[test]
+    fn bindings_replace_the_defaults_of_their_action() {
+        let keymap = Keymap::from_toml("[normal]\nquit = [\"ctrl+c\"]\ncommit = []").unwrap();
+        assert_eq!(
+            keymap.keys_for(KeyContext::Normal, &Action::Quit),
+            Some("Ctrl+C".to_string())
+        );
+        assert_eq!(
+            keymap.keys_for(KeyContext::Normal, &Action::CommitWork),
+            None
+        );
+        let err = Keymap::from_toml("[normal]\nfly = \"f\"").err().unwrap();
+        assert_eq!(err, "unknown action 'fly' in [normal]");
+    }
+}
@@ -1,5 +1,19 @@
 pub mod app_state;
+pub mod commit_message;
+pub mod config;
+pub mod details_cache;
+pub mod diff;
+pub mod event_source;
 pub mod events;
+pub mod fuzzy;
 pub mod git_commands;
+pub mod highlight;
+pub mod hooks;
+pub mod identity;
 pub mod input;
+pub mod keymap;
 pub mod logger;
+pub mod signing;
+pub mod text_input;
+pub mod theme;
+pub mod ui;
@@ -6,11 +6,18 @@ pub struct Logger;
 impl Logger {
     /// Initializes the logger to write logs only to a file.
     pub fn init(log_file: &str, log_level: LevelFilter) {
+        // Write every level so that `set_level` can raise it later
         CombinedLogger::init(vec![WriteLogger::new(
-            log_level,
+            LevelFilter::Trace,
             Config::default(),
             File::create(log_file).expect("Failed to create log file"),
         )])
         .expect("Failed to initialize logger");
+        Self::set_level(log_level);
+    }
+
+    /// Changes which messages are logged from now on.
+    pub fn set_level(log_level: LevelFilter) {
+        log::set_max_level(log_level);
     }
 }
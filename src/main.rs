@@ -1,311 +1,254 @@
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
 use crossterm::{execute, terminal, ExecutableCommand};
-use ezgit_rs::app_state::{AppState, Panel, UIState};
-use ezgit_rs::events::handle_event;
+use ezgit_rs::app_state::{AppState, NotificationKind, UIState};
+use ezgit_rs::commit_message;
+use ezgit_rs::config::{self, ConfigWatcher};
+use ezgit_rs::event_source::{EventSource, ScriptSource, SessionRecorder, TerminalSource};
+use ezgit_rs::events::{self, Effect};
 use ezgit_rs::git_commands;
+use ezgit_rs::keymap::Keymap;
 use ezgit_rs::logger::Logger;
-use log::info;
+use ezgit_rs::ui::{self, ViewLayout};
+use log::{debug, info};
 use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Constraint, Direction, Layout};
-use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
 use ratatui::Terminal;
 use std::io;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: ezgit-rs [--record <file>] [--replay <file>]";
+
+// Command line options
+#[derive(Default)]
+struct Options {
+    record: Option<PathBuf>, // Save the session's actions to this file
+    replay: Option<PathBuf>, // Run the actions in this file before reading keys
+}
 
 fn main() -> Result<(), io::Error> {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        std::process::exit(2);
+    });
+    let mut source: Box<dyn EventSource> = match &options.replay {
+        Some(path) => match ScriptSource::load(path) {
+            Ok(script) => Box::new(script),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(TerminalSource::default()),
+    };
+    let mut recorder = match options
+        .record
+        .as_deref()
+        .map(SessionRecorder::create)
+        .transpose()
+    {
+        Ok(recorder) => recorder,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Work from the root of the repository, wherever in it ezgit was started
+    let repo_path = git_commands::find_workdir(".").unwrap_or_else(|| ".".to_string());
+
+    // Read the config first, since it names the log file
+    let loaded_config = config::load(&repo_path);
+    let initial_config = loaded_config.clone().unwrap_or_default();
+
     // Initialize the logger
-    Logger::init("debug.log", log::LevelFilter::Debug);
+    Logger::init(&initial_config.log.file, initial_config.log_level());
     info!("Logger initialized");
 
     // Setup terminal
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
     stdout.execute(terminal::EnterAlternateScreen)?;
+    stdout.execute(EnableBracketedPaste)?; // Receive pasted text in one piece
+    stdout.execute(EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let initial_commit_log = git_commands::get_commit_log(".");
-    let initial_branch = git_commands::get_branches(".");
-    let mut app_state = AppState::new(initial_commit_log, initial_branch, ".");
+    let initial_commit_log = git_commands::get_commit_log(&repo_path);
+    let initial_branch = git_commands::get_branches(&repo_path);
+    let mut app_state = AppState::new(initial_commit_log, initial_branch, &repo_path);
+    let mut startup_errors = Vec::new();
+    match Keymap::load() {
+        Ok(keymap) => app_state.keymap = keymap,
+        Err(err) => {
+            startup_errors.push(format!("Invalid key bindings, using the defaults: {}", err))
+        }
+    }
+    app_state.set_config(initial_config);
+    if let Err(err) = loaded_config {
+        startup_errors.push(format!("Invalid config, using the defaults:\n{}", err));
+    }
+    events::prompt_for_missing_identity(&mut app_state);
+    if !startup_errors.is_empty() {
+        // Shown over the identity prompt, which comes back once they are closed
+        events::show_error(&mut app_state, startup_errors.join("\n\n"));
+    }
+    events::refresh_preview(&mut app_state);
+    let mut config_watcher = ConfigWatcher::new(&repo_path);
+    let mut replaying = options.replay.is_some();
 
     // Main event loop
+    let mut layout = ViewLayout::default();
+    let mut draw_passes = 0;
     loop {
-        // Draw UI
-        terminal.draw(|f    | {
-
-            match app_state.ui_state {
-                UIState::Normal => {
-                    let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints([
-                        Constraint::Percentage(70), // Commit Log
-                        Constraint::Percentage(30), // Branch List
-                    ])
-                    .split(f.area());
-
-                    // Style for focused and unfocused panels
-                    let focused_style = Style::default().fg(Color::Yellow);
-                    let unfocused_style = Style::default();
-
-                    // Adjust commit chunk height
-                    let commit_chunk_height = (chunks[0].height.saturating_sub(2)) as usize; // Account for borders
-                    app_state.visible_count = commit_chunk_height; // Dynamically update visible count
-                    app_state.update_visible_range();             // Update visible range based on selected index
-
-                    // Render Commit Log with horizontal scrolling
-                    let visible_commits = &app_state.commit_log[app_state.visible_range.0..app_state.visible_range.1];
-                    let commit_items: Vec<ListItem> = visible_commits
-                        .iter()
-                        .enumerate()
-                        .map(|(i, commit)| {
-                            let global_index = app_state.visible_range.0 + i;
-
-                            // Apply horizontal offset
-                            let truncated_commit = if app_state.horizontal_offset < commit.len() {
-                                &commit[app_state.horizontal_offset..]
-                            } else {
-                                ""
-                            };
-
-                            // Highlight selected commit
-                            if global_index == app_state.selected_index {
-                                ListItem::new(truncated_commit.to_string())
-                                    .style(ratatui::style::Style::default().fg(ratatui::style::Color::Yellow))
-                            } else {
-                                ListItem::new(truncated_commit.to_string())
-                            }
-                        })
-                        .collect();
-
-                    let commit_list = List::new(commit_items).block(
-                        Block::default()
-                            .title("Commit Log")
-                            .borders(Borders::ALL)
-                            .border_style(if matches!(app_state.focused_panel, Panel::CommitLog) {
-                                focused_style
-                            } else {
-                                unfocused_style
-                            }),
-                    );
-
-                    f.render_widget(commit_list, chunks[0]);
-
-                    let branch_chunk_height = chunks[1].height as usize; // Height of the branches chunk
-                    app_state.branch_visible_count = branch_chunk_height; // Dynamically set visible count
-                    app_state.update_branch_visible_range(); // Update visible range
-
-                    // Render only visible branches
-                    let visible_branches = &app_state.branches[app_state.branch_visible_range.0..app_state.branch_visible_range.1];
-                    let branch_items: Vec<ListItem> = visible_branches
-                        .iter()
-                        .enumerate()
-                        .map(|(i, branch)| {
-                            let global_index = app_state.branch_visible_range.0 + i;
-
-                            // Determine style for the current branch
-                            let style = if branch == &app_state.branch_name {
-                                // Current branch style
-                                Style::default()
-                                    .fg(Color::White)
-                                    .bg(Color::Green)
-                                    .add_modifier(Modifier::BOLD)
-                            } else if global_index == app_state.selected_branch {
-                                // Navigated branch style
-                                Style::default().fg(Color::Yellow)
-                            } else {
-                                // Default style
-                                Style::default()
-                            };
-
-                            ListItem::new(branch.clone()).style(style)
-                        })
-                        .collect();
-
-                    let branch_list = List::new(branch_items)
-                        .block(Block::default().title("Branches").borders(Borders::ALL).border_style(if matches!(app_state.focused_panel, Panel::Branches) {
-                            focused_style
-                        } else {
-                            unfocused_style
-                        }));
-
-                    f.render_widget(branch_list, chunks[1]);
-
-                },
-
-                UIState::CommitMessage => {
-                    // Render UI for entering commit message
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)]) // Full area for input
-                        .split(f.area());
-
-                    // Get the current commit message from CommitState
-                    let commit_message = app_state
-                        .commit_state
-                        .as_ref()
-                        .map_or("", |state| &state.message);
-
-                    // Create a paragraph to display the message
-                    let input_prompt = Paragraph::new(commit_message)
-                        .block(
-                            Block::default()
-                                .title("Enter Commit Message (Press Enter to Confirm, Esc to Cancel)")
-                                .borders(Borders::ALL),
-                        );
+        // Show the output of running hooks, and the result once the operation is done
+        events::poll_operation(&mut app_state);
+
+        // Scroll for the sizes the views were last drawn at, then draw the UI
+        app_state.apply_layout(&layout);
+        let mut drawn = layout;
+        terminal.draw(|f| drawn = ui::draw(f, &app_state))?;
+        draw_passes += 1;
+        if drawn != layout {
+            // The views changed size; draw again with the new sizes before waiting for input
+            layout = drawn;
+            if draw_passes < ui::MAX_DRAW_PASSES {
+                continue;
+            }
+        }
+        draw_passes = 0;
 
-                    // Render the input prompt
-                    f.render_widget(input_prompt, chunks[0]);
+        // A replayed session hands over to the keyboard once it has run
+        if source.is_finished() {
+            source = Box::new(TerminalSource::default());
+            replaying = false;
+        }
+        if replaying {
+            // Replayed actions don't wait for the user, so let each operation finish first
+            events::wait_for_operation(&mut app_state);
+        }
+        let contexts = events::key_contexts(&app_state);
+        if let Some(action) = source.next_action(&mut app_state.keymap, contexts)? {
+            if let Some(Err(err)) = recorder.as_mut().map(|recorder| recorder.record(&action)) {
+                // Losing the recording is no reason to lose the session
+                debug!("Failed to record {:?}: {}", action, err);
+                app_state.notify(
+                    NotificationKind::Error,
+                    format!("Stopped recording the session: {}", err),
+                );
+                recorder = None;
+            }
+            let mut quit = false;
+            for effect in events::update(&mut app_state, action) {
+                match effect {
+                    Effect::Quit => quit = true,
+                    Effect::OpenEditor => {
+                        edit_commit_message_externally(&mut terminal, &mut app_state)?
+                    }
                 }
-                UIState::ConfirmCommit => {
-                    // Render UI for confirming commit and push
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)])
-                        .split(f.area());
-                    let confirm_prompt = Block::default()
-                        .title("Are you sure you want to commit and push? (Press Enter to Confirm)")
-                        .borders(Borders::ALL);
-                    f.render_widget(confirm_prompt, chunks[0]);
-                },
-                UIState::ConfirmQuit => {
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)])
-                        .split(f.area());
-
-                    let quit_prompt = Block::default()
-                        .title("Are you sure you want to quit? (Press Enter to Confirm, Esc to Cancel)")
-                        .borders(Borders::ALL);
-
-                    f.render_widget(quit_prompt, chunks[0]);
-                },
-                UIState::CommitDetails => {
-                    let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints([Constraint::Percentage(100)])
-                    .split(f.area());
-
-                    let details = app_state.selected_commit_details.clone().unwrap_or("No details available".to_string());
-                    let details_lines: Vec<&str> = details.lines().collect();
-                    app_state.commit_details_total_lines = details_lines.len();
-
-                    // Update visible range based on chunk height
-                    let height = (chunks[0].height as usize).saturating_sub(2); // Account for borders
-                    app_state.update_commit_details_visible_range(height);
-
-                    let (start, end) = app_state.commit_details_visible_range;
-                    let visible_lines: Vec<&str> = details_lines[start..end].to_vec();
-
-                    let details_widget = Block::default()
-                        .title("Commit Details (Press Esc to Return)")
-                        .borders(Borders::ALL);
-
-                    let details_paragraph = ratatui::widgets::Paragraph::new(visible_lines.join("\n"))
-                        .block(details_widget)
-                        .wrap(ratatui::widgets::Wrap { trim: false });
-
-                    f.render_widget(details_paragraph, chunks[0]);
-                },
-                UIState::CreateBranch => {
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)])
-                        .split(f.area());
-
-                    let input = app_state.branch_name.clone();
-                    let prompt = format!("Enter new branch name: {}", input);
-
-                    let branch_prompt = Paragraph::new(prompt)
-                        .block(Block::default().title("Create Branch").borders(Borders::ALL));
-
-                    f.render_widget(branch_prompt, chunks[0]);
-                },
-                UIState::KeyGuide => {
-                    let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints([Constraint::Percentage(100)])
-                    .split(f.area());
-
-                    let key_guide_text = vec![
-                        "  - q: Quit the application",
-                        "  - Esc: Cancel current action, return to the previous screen, or exit error messages",
-                        "  - Enter: Select item, confirm action, or proceed",
-                        "  - Tab: Switch between Commit Log and Branches panel",
-                        "  - ↑/↓: Navigate through items in the current panel",
-                        "  - ←/→: Scroll horizontally in the Commit Log",
-                        "  - PageUp/PageDown: Jump one page up or down in Commit Log or Commit Details",
-                        "  - c: Start the commit workflow (add, commit, and push changes)",
-                        "  - b: Create and switch to a new branch",
-                        "  - r: Refresh the Commit Log and Branches list",
-                        "  - g: Open this Key Guide",
-                        "  - m: Merge the current branch into the main or master branch",
-                        "  - f: Filter commits by a specific file",
-                    ];
-
-                    let key_guide = Paragraph::new(key_guide_text.join("\n"))
-                        .block(Block::default().title("Key Guide").borders(Borders::ALL))
-                        .wrap(ratatui::widgets::Wrap { trim: false });
-
-                    f.render_widget(key_guide, chunks[0]);
-                },
-                UIState::ConfirmMerge => {
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)])
-                        .split(f.area());
-
-                    let confirmation_text = "Are you sure you want to merge into the main/master branch?\nPress Enter to confirm or Esc to cancel.";
-                    let confirmation = Paragraph::new(confirmation_text)
-                        .block(Block::default().title("Confirm Merge").borders(Borders::ALL))
-                        .wrap(ratatui::widgets::Wrap { trim: false });
-
-                    f.render_widget(confirmation, chunks[0]);
-                },
-                UIState::Error => {
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)])
-                        .split(f.area());
-
-                    let error_message = app_state.error_message.clone().unwrap_or("Unknown error".to_string());
-                    let error_paragraph = Paragraph::new(error_message)
-                        .block(Block::default().title("Error").borders(Borders::ALL))
-                        .wrap(ratatui::widgets::Wrap { trim: false });
+            }
+            if quit {
+                break;
+            }
+        } else {
+            // No keys are coming in, so catch the preview up with the selection
+            events::refresh_preview(&mut app_state);
+        }
 
-                    f.render_widget(error_paragraph, chunks[0]);
-                },
-                UIState::FilterByFile => {
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .margin(1)
-                        .constraints([Constraint::Percentage(100)])
-                        .split(f.area());
+        // Reload the config once the user is back in the main view
+        if matches!(app_state.ui_state, UIState::Normal) && config_watcher.changed() {
+            reload_config(&mut app_state);
+        }
+    }
 
-                    let input = app_state.branch_name.clone();
-                    let prompt = format!("Enter file name to filter commits by: {}", input);
-                    let filter_prompt = Paragraph::new(prompt)
-                        .block(Block::default().title("Filter Commits by File").borders(Borders::ALL));
+    // Restore terminal
+    terminal::disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        terminal::LeaveAlternateScreen
+    )?;
+    Ok(())
+}
 
-                    f.render_widget(filter_prompt, chunks[0]);
-                },
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--record" => &mut options.record,
+            "--replay" => &mut options.replay,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
             }
-        })?;
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        };
+        let path = args.next().ok_or(format!("{} needs a file", arg))?;
+        *value = Some(PathBuf::from(path));
+    }
+    Ok(options)
+}
 
-        if handle_event(&mut app_state)? {
-            break;
+// Reload config.toml, .ezgit.toml and keys.toml, keeping the settings that were working
+// for any that have errors
+fn reload_config(app_state: &mut AppState) {
+    let mut errors = Vec::new();
+    match config::load(&app_state.repo_path) {
+        Ok(new_config) => {
+            Logger::set_level(new_config.log_level());
+            app_state.set_config(new_config);
+            events::refresh_preview(app_state); // The preview may have been turned on
         }
+        Err(err) => errors.push(format!(
+            "Invalid config, keeping the previous settings:\n{}",
+            err
+        )),
+    }
+    match Keymap::load() {
+        Ok(keymap) => app_state.keymap = keymap,
+        Err(err) => errors.push(format!(
+            "Invalid key bindings, keeping the previous ones: {}",
+            err
+        )),
     }
 
-    // Restore terminal
+    if errors.is_empty() {
+        app_state.notify(NotificationKind::Info, "Config reloaded");
+    } else {
+        events::show_error(app_state, errors.join("\n\n"));
+    }
+}
+
+// Hand the terminal over to $EDITOR to edit the commit message, then take it back
+fn edit_commit_message_externally(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app_state: &mut AppState,
+) -> Result<(), io::Error> {
+    let Some(commit_state) = app_state.commit_state.as_mut() else {
+        return Ok(());
+    };
+
     terminal::disable_raw_mode()?;
-    execute!(terminal.backend_mut(), terminal::LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        terminal::LeaveAlternateScreen
+    )?;
+    let result = commit_message::edit_in_editor(&app_state.repo_path, commit_state.message.text());
+    terminal::enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        terminal::EnterAlternateScreen,
+        EnableBracketedPaste,
+        EnableMouseCapture
+    )?;
+    terminal.clear()?;
+
+    match result {
+        Ok(message) => commit_state.message.set_text(&message),
+        Err(err) => debug!("Error editing commit message: {}", err),
+    }
     Ok(())
 }
@@ -0,0 +1,268 @@
+use git2::{Oid, Repository};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SignatureFormat {
+    OpenPgp,
+    Ssh,
+}
+
+/// How to sign commits, from `commit.gpgsign`, `gpg.format` and `user.signingkey`.
+pub struct SigningConfig {
+    pub format: SignatureFormat,
+    pub key: Option<String>,
+    pub program: String, // gpg or ssh-keygen, unless configured otherwise
+}
+
+/// Result of checking a commit's signature.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SignatureStatus {
+    Unsigned,
+    Good(String),    // Signer
+    Bad(String),     // Why the signature is invalid
+    Unknown(String), // Why the signature couldn't be checked
+}
+
+impl SignatureStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            SignatureStatus::Unsigned => "Not signed".to_string(),
+            SignatureStatus::Good(signer) => format!("Good signature from {}", signer),
+            SignatureStatus::Bad(reason) => format!("BAD signature: {}", reason),
+            SignatureStatus::Unknown(reason) => format!("Signed, but not verified: {}", reason),
+        }
+    }
+}
+
+/// The signing setup of the repository, or `None` if commits shouldn't be signed.
+pub fn signing_config(repo: &Repository) -> Option<SigningConfig> {
+    let config = repo.config().ok()?;
+    if !config.get_bool("commit.gpgsign").unwrap_or(false) {
+        return None;
+    }
+
+    let format = match config.get_string("gpg.format").as_deref() {
+        Ok("ssh") => SignatureFormat::Ssh,
+        _ => SignatureFormat::OpenPgp,
+    };
+    let program = match format {
+        SignatureFormat::OpenPgp => config
+            .get_string("gpg.openpgp.program")
+            .or_else(|_| config.get_string("gpg.program"))
+            .unwrap_or_else(|_| "gpg".to_string()),
+        SignatureFormat::Ssh => config
+            .get_string("gpg.ssh.program")
+            .unwrap_or_else(|_| "ssh-keygen".to_string()),
+    };
+    Some(SigningConfig {
+        format,
+        key: config.get_string("user.signingkey").ok(),
+        program,
+    })
+}
+
+// Scratch files for signing live in .git/ezgit
+fn scratch_path(repo: &Repository, name: &str) -> Result<PathBuf, String> {
+    let directory = repo.path().join("ezgit");
+    fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+    Ok(directory.join(name))
+}
+
+// Run a signing program with `input` on stdin, returning its stdout
+fn run_with_input(program: &str, args: &[&str], input: &str) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Signs a commit buffer, returning the armored signature.
+pub fn sign(repo: &Repository, config: &SigningConfig, content: &str) -> Result<String, String> {
+    match config.format {
+        SignatureFormat::OpenPgp => {
+            let mut args = vec!["--status-fd=2", "-bsa"];
+            if let Some(key) = &config.key {
+                args.extend(["-u", key.as_str()]);
+            }
+            run_with_input(&config.program, &args, content)
+        }
+        SignatureFormat::Ssh => {
+            let key = config
+                .key
+                .as_deref()
+                .ok_or("SSH signing needs user.signingkey to be set")?;
+
+            // A literal public key means the private key is in the SSH agent
+            let literal = key
+                .strip_prefix("key::")
+                .or_else(|| (key.starts_with("ssh-") || key.starts_with("ecdsa-")).then_some(key));
+            let key_file = match literal {
+                Some(public_key) => {
+                    let path = scratch_path(repo, "signing_key.pub")?;
+                    fs::write(&path, public_key)
+                        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                    path
+                }
+                None => PathBuf::from(shellexpand_home(key)),
+            };
+
+            let key_file = key_file.to_string_lossy();
+            let mut args = vec!["-Y", "sign", "-n", "git", "-f", key_file.as_ref()];
+            if literal.is_some() {
+                args.push("-U");
+            }
+            run_with_input(&config.program, &args, content)
+        }
+    }
+}
+
+fn shellexpand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+/// Marker for signed commits in the commit log. It only says that a signature exists:
+/// checking every signature would be too slow for the log, so the details view does that.
+pub fn log_badge(repo: &Repository, oid: Oid) -> &'static str {
+    if is_signed(repo, oid) {
+        "[signed] "
+    } else {
+        ""
+    }
+}
+
+/// Whether a commit carries a signature, without checking it.
+pub fn is_signed(repo: &Repository, oid: Oid) -> bool {
+    repo.extract_signature(&oid, None).is_ok()
+}
+
+/// Checks the signature of a commit with gpg, or ssh-keygen and `gpg.ssh.allowedSignersFile`.
+pub fn verify_commit(repo: &Repository, oid: Oid) -> SignatureStatus {
+    let Ok((signature, signed_data)) = repo.extract_signature(&oid, None) else {
+        return SignatureStatus::Unsigned;
+    };
+    let signature = String::from_utf8_lossy(&signature).into_owned();
+    let signed_data = String::from_utf8_lossy(&signed_data).into_owned();
+    let config = repo.config().ok();
+    let get = |name: &str| {
+        config
+            .as_ref()
+            .and_then(|config| config.get_string(name).ok())
+    };
+
+    // Each check gets its own file, removed when it is dropped, so checks never share one
+    let signature_file = tempfile::Builder::new()
+        .prefix("ezgit-verify")
+        .suffix(".sig")
+        .tempfile()
+        .and_then(|mut file| file.write_all(signature.as_bytes()).map(|_| file));
+    let Ok(signature_file) = signature_file else {
+        return SignatureStatus::Unknown("cannot write the signature to check".to_string());
+    };
+    let signature_path = signature_file.path().to_string_lossy();
+
+    if signature.starts_with("-----BEGIN SSH SIGNATURE-----") {
+        let program = get("gpg.ssh.program").unwrap_or_else(|| "ssh-keygen".to_string());
+        let Some(allowed_signers) = get("gpg.ssh.allowedSignersFile") else {
+            return SignatureStatus::Unknown("gpg.ssh.allowedSignersFile is not set".to_string());
+        };
+        let allowed_signers = shellexpand_home(&allowed_signers);
+        let principal = Command::new(&program)
+            .args(["-Y", "find-principals", "-f", &allowed_signers, "-s"])
+            .arg(signature_path.as_ref())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .next()
+                    .map(String::from)
+            });
+        let Some(principal) = principal else {
+            return SignatureStatus::Unknown("no allowed signer matches the key".to_string());
+        };
+        let args = [
+            "-Y",
+            "verify",
+            "-n",
+            "git",
+            "-f",
+            &allowed_signers,
+            "-I",
+            &principal,
+            "-s",
+            signature_path.as_ref(),
+        ];
+        match run_with_input(&program, &args, &signed_data) {
+            Ok(_) => SignatureStatus::Good(principal),
+            Err(err) => SignatureStatus::Bad(err),
+        }
+    } else {
+        let program = get("gpg.openpgp.program")
+            .or_else(|| get("gpg.program"))
+            .unwrap_or_else(|| "gpg".to_string());
+        let args = ["--status-fd=1", "--verify", signature_path.as_ref(), "-"];
+        let output = Command::new(&program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(signed_data.as_bytes())?;
+                }
+                child.wait_with_output()
+            });
+        let Ok(output) = output else {
+            return SignatureStatus::Unknown(format!("cannot run {}", program));
+        };
+
+        // gpg reports the outcome on its status lines
+        let status = String::from_utf8_lossy(&output.stdout).into_owned();
+        let field = |tag: &str| {
+            status.lines().find_map(|line| {
+                let rest = line.strip_prefix("[GNUPG:] ")?.strip_prefix(tag)?;
+                // The key id comes before the signer
+                let signer = rest.trim().split_once(' ').map_or("", |(_, signer)| signer);
+                Some(signer.to_string())
+            })
+        };
+        if let Some(signer) = field("GOODSIG") {
+            SignatureStatus::Good(signer)
+        } else if let Some(signer) = field("BADSIG") {
+            SignatureStatus::Bad(format!("made by {}", signer))
+        } else if status.contains("NO_PUBKEY") {
+            SignatureStatus::Unknown("the public key is not available".to_string())
+        } else {
+            SignatureStatus::Unknown(format!("{} could not check it", program))
+        }
+    }
+}
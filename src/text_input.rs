@@ -0,0 +1,376 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const MAX_UNDO_STEPS: usize = 100;
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditKind {
+    Typing,
+    Deleting,
+    Other,
+}
+
+/// An editable line or text area with a cursor, word-wise editing and undo.
+/// The cursor is a byte offset that always sits on a grapheme cluster boundary.
+#[derive(Clone)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    multiline: bool,
+    undo_stack: Vec<(String, usize)>,
+    last_edit: Option<EditKind>, // Consecutive edits of the same kind are undone together
+}
+
+impl TextInput {
+    pub fn new(multiline: bool) -> Self {
+        Self {
+            text: String::new(),
+            cursor: 0,
+            multiline,
+            undo_stack: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    pub fn with_text(text: &str, multiline: bool) -> Self {
+        let mut input = Self::new(multiline);
+        input.text = input.sanitize(text);
+        input.cursor = input.text.len();
+        input
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    /// Replaces the whole text, leaving the cursor at the end. Can be undone.
+    pub fn set_text(&mut self, text: &str) {
+        self.save_undo(EditKind::Other);
+        self.text = self.sanitize(text);
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.undo_stack.clear();
+        self.last_edit = None;
+    }
+
+    // Single-line inputs turn line breaks into spaces
+    fn sanitize(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.multiline {
+            text
+        } else {
+            text.replace('\n', " ")
+        }
+    }
+
+    fn save_undo(&mut self, kind: EditKind) {
+        if self.last_edit != Some(kind) || kind == EditKind::Other {
+            self.undo_stack.push((self.text.clone(), self.cursor));
+            if self.undo_stack.len() > MAX_UNDO_STEPS {
+                self.undo_stack.remove(0);
+            }
+        }
+        self.last_edit = Some(kind);
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo_stack.pop() {
+            self.text = text;
+            self.cursor = cursor;
+        }
+        self.last_edit = None;
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        if c == '\n' && !self.multiline {
+            return;
+        }
+        self.save_undo(EditKind::Typing);
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        if c.is_whitespace() {
+            self.last_edit = None; // Undo typing word by word
+        }
+    }
+
+    /// Inserts pasted text at the cursor.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = self.sanitize(text);
+        if text.is_empty() {
+            return;
+        }
+        self.save_undo(EditKind::Other);
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    pub fn new_line(&mut self) {
+        self.insert_char('\n');
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    // Start of the word before the cursor, skipping whitespace first
+    fn previous_word_boundary(&self) -> usize {
+        let mut graphemes = self.text[..self.cursor]
+            .grapheme_indices(true)
+            .rev()
+            .peekable();
+        let mut boundary = self.cursor;
+        while let Some((i, _)) = graphemes.next_if(|(_, grapheme)| grapheme.trim().is_empty()) {
+            boundary = i;
+        }
+        // A run of word characters, or a single punctuation character
+        match graphemes.next() {
+            Some((i, grapheme)) if is_word(grapheme) => {
+                boundary = i;
+                while let Some((i, _)) = graphemes.next_if(|(_, grapheme)| is_word(grapheme)) {
+                    boundary = i;
+                }
+            }
+            Some((i, _)) => boundary = i,
+            None => {}
+        }
+        boundary
+    }
+
+    // End of the word after the cursor, skipping whitespace first
+    fn next_word_boundary(&self) -> usize {
+        let mut graphemes = self.text[self.cursor..].grapheme_indices(true).peekable();
+        let end_of = |(i, grapheme): (usize, &str)| self.cursor + i + grapheme.len();
+        let mut boundary = self.cursor;
+        while let Some(item) = graphemes.next_if(|(_, grapheme)| grapheme.trim().is_empty()) {
+            boundary = end_of(item);
+        }
+        match graphemes.next() {
+            Some(item) if is_word(item.1) => {
+                boundary = end_of(item);
+                while let Some(item) = graphemes.next_if(|(_, grapheme)| is_word(grapheme)) {
+                    boundary = end_of(item);
+                }
+            }
+            Some(item) => boundary = end_of(item),
+            None => {}
+        }
+        boundary
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |i| self.cursor + i)
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize, kind: EditKind) {
+        if start < end {
+            self.save_undo(kind);
+            self.text.replace_range(start..end, "");
+            self.cursor = start;
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.delete_range(self.previous_boundary(), self.cursor, EditKind::Deleting);
+    }
+
+    pub fn delete(&mut self) {
+        self.delete_range(self.cursor, self.next_boundary(), EditKind::Deleting);
+    }
+
+    pub fn delete_word_backward(&mut self) {
+        self.delete_range(self.previous_word_boundary(), self.cursor, EditKind::Other);
+    }
+
+    pub fn delete_to_line_start(&mut self) {
+        self.delete_range(self.line_start(), self.cursor, EditKind::Other);
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.previous_boundary();
+        self.last_edit = None;
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+        self.last_edit = None;
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.previous_word_boundary();
+        self.last_edit = None;
+    }
+
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.next_word_boundary();
+        self.last_edit = None;
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = self.line_start();
+        self.last_edit = None;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.line_end();
+        self.last_edit = None;
+    }
+
+    /// Moves to the end of the first line.
+    pub fn move_to_first_line_end(&mut self) {
+        self.cursor = self.text.find('\n').unwrap_or(self.text.len());
+        self.last_edit = None;
+    }
+
+    /// Moves to the previous line, keeping the display column. Returns false on the first line.
+    pub fn move_up(&mut self) -> bool {
+        let start = self.line_start();
+        if start == 0 {
+            return false;
+        }
+        let column = self.text[start..self.cursor].width();
+        let previous_start = self.text[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+        self.cursor = self.offset_at_column(previous_start, start - 1, column);
+        self.last_edit = None;
+        true
+    }
+
+    /// Moves to the next line, keeping the display column. Returns false on the last line.
+    pub fn move_down(&mut self) -> bool {
+        let end = self.line_end();
+        if end == self.text.len() {
+            return false;
+        }
+        let column = self.text[self.line_start()..self.cursor].width();
+        let next_start = end + 1;
+        let next_end = self.text[next_start..]
+            .find('\n')
+            .map_or(self.text.len(), |i| next_start + i);
+        self.cursor = self.offset_at_column(next_start, next_end, column);
+        self.last_edit = None;
+        true
+    }
+
+    // Byte offset of the grapheme at display `column` within the line `start..end`
+    fn offset_at_column(&self, start: usize, end: usize, column: usize) -> usize {
+        let mut width = 0;
+        for (i, grapheme) in self.text[start..end].grapheme_indices(true) {
+            if width >= column {
+                return start + i;
+            }
+            width += grapheme.width();
+        }
+        end
+    }
+
+    /// Row and display column of the cursor, for placing the terminal cursor.
+    pub fn cursor_position(&self) -> (usize, usize) {
+        let before = &self.text[..self.cursor];
+        let row = before.matches('\n').count();
+        let column = before[self.line_start()..].width();
+        (row, column)
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_line_inputs_drop_line_breaks() {
+        let mut input = TextInput::with_text("one\r\ntwo", false);
+        assert_eq!(input.text(), "one two");
+        input.new_line();
+        assert_eq!(input.text(), "one two");
+        input.insert_str("\nthree");
+        assert_eq!(input.text(), "one two three");
+    }
+
+    #[test]
+    fn editing_moves_over_whole_graphemes() {
+        let mut input = TextInput::with_text("ae\u{301}", false); // "e" with a combining accent
+        input.move_left();
+        assert_eq!(input.cursor_position(), (0, 1));
+        input.backspace();
+        assert_eq!(input.text(), "e\u{301}");
+        input.delete();
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn words_are_deleted_and_typing_is_undone_word_by_word() {
+        let mut input = TextInput::new(false);
+        for c in "fix the parser".chars() {
+            input.insert_char(c);
+        }
+        input.delete_word_backward();
+        assert_eq!(input.text(), "fix the ");
+        input.undo();
+        assert_eq!(input.text(), "fix the parser");
+        input.undo();
+        assert_eq!(input.text(), "fix the ");
+        input.undo();
+        assert_eq!(input.text(), "fix ");
+    }
+
+    #[test]
+    fn vertical_moves_keep_the_display_column() {
+        let mut input = TextInput::with_text(
+            "subject line
+日本語 body
+
+end",
+            true,
+        );
+        input.move_to_first_line_end();
+        assert_eq!(input.cursor_position(), (0, 12));
+        input.move_word_left();
+        input.move_right();
+        assert!(input.move_down());
+        assert_eq!(input.cursor_position(), (1, 9)); // Past "日本語 bo", 9 columns wide
+        assert!(input.move_down());
+        assert_eq!(input.cursor_position(), (2, 0)); // The blank line is shorter
+        assert!(input.move_down());
+        assert!(!input.move_down());
+        input.move_home();
+        assert!(input.move_up());
+        assert!(input.move_up());
+        assert!(input.move_up());
+        assert!(!input.move_up());
+        assert_eq!(input.cursor_position(), (0, 0));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::config::Config;
+use crate::highlight;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::str::FromStr;
+
+/// Names of the built-in themes, in the order the docs list them.
+pub const BUILT_IN: [&str; 4] = ["dark", "light", "high-contrast", "monochrome"];
+
+/// Styles for every colored part of the UI.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub focused_border: Style,
+    pub selected: Style,       // Selected commit or branch
+    pub current_branch: Style, // Checked out branch in the Branches panel
+    pub cursor_line: Style,    // Highlighted row in pickers, file trees, blame and the diffstat
+    pub muted: Style,          // Line numbers, sizes and descriptions
+    pub error: Style,
+    pub warning: Style,
+    pub success: Style,
+    pub added: Style,            // Added lines and diffstat bars
+    pub removed: Style,          // Removed lines and diffstat bars
+    pub added_line: Style,       // Background of added lines with syntax colors
+    pub removed_line: Style,     // Background of removed lines with syntax colors
+    pub added_emphasis: Style,   // Changed words in added lines
+    pub removed_emphasis: Style, // Changed words in removed lines
+    pub file_header: Style,
+    pub file_status: Style,
+    pub hunk_header: Style,
+    pub directory: Style,
+    pub symlink: Style,
+    pub submodule: Style,
+    pub fuzzy_match: Style,           // Matched characters in the file filter
+    pub over_limit: Style,            // Commit message text past the 50/72 columns
+    pub missing_blank_line: Style,    // Non-empty second line of a commit message
+    pub status_bar: Style,            // Branch and repository state at the bottom
+    pub syntax_theme: Option<String>, // syntect theme for diffs, or none to turn them off
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        let plain = Style::default();
+        Self {
+            focused_border: plain.fg(Color::Yellow),
+            selected: plain.fg(Color::Yellow),
+            current_branch: plain
+                .fg(Color::White)
+                .bg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            cursor_line: plain.add_modifier(Modifier::REVERSED),
+            muted: plain.fg(Color::DarkGray),
+            error: plain.fg(Color::Red),
+            warning: plain.fg(Color::Yellow),
+            success: plain.fg(Color::Green),
+            added: plain.fg(Color::Green),
+            removed: plain.fg(Color::Red),
+            added_line: plain.bg(Color::Rgb(0, 48, 0)),
+            removed_line: plain.bg(Color::Rgb(64, 0, 0)),
+            added_emphasis: plain.bg(Color::Rgb(0, 96, 0)).add_modifier(Modifier::BOLD),
+            removed_emphasis: plain.bg(Color::Rgb(128, 0, 0)).add_modifier(Modifier::BOLD),
+            file_header: plain.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            file_status: plain.fg(Color::Yellow),
+            hunk_header: plain.fg(Color::Magenta),
+            directory: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            symlink: plain.fg(Color::Cyan),
+            submodule: plain.fg(Color::Magenta),
+            fuzzy_match: plain.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            over_limit: plain.fg(Color::Yellow).add_modifier(Modifier::UNDERLINED),
+            missing_blank_line: plain.bg(Color::Red),
+            status_bar: plain.fg(Color::Cyan),
+            syntax_theme: Some("base16-ocean.dark".to_string()),
+        }
+    }
+
+    /// Dark text on a light background. Yellow and cyan are unreadable there, so they
+    /// become blue and darker shades.
+    pub fn light() -> Self {
+        let plain = Style::default();
+        let amber = Color::Rgb(150, 90, 0);
+        Self {
+            focused_border: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            selected: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            current_branch: plain
+                .fg(Color::White)
+                .bg(Color::Rgb(0, 120, 0))
+                .add_modifier(Modifier::BOLD),
+            cursor_line: plain.add_modifier(Modifier::REVERSED),
+            muted: plain.fg(Color::Rgb(110, 110, 110)),
+            error: plain.fg(Color::Rgb(180, 0, 0)),
+            warning: plain.fg(amber),
+            success: plain.fg(Color::Rgb(0, 120, 0)),
+            added: plain.fg(Color::Rgb(0, 120, 0)),
+            removed: plain.fg(Color::Rgb(180, 0, 0)),
+            added_line: plain.bg(Color::Rgb(220, 255, 220)),
+            removed_line: plain.bg(Color::Rgb(255, 225, 225)),
+            added_emphasis: plain
+                .bg(Color::Rgb(160, 235, 160))
+                .add_modifier(Modifier::BOLD),
+            removed_emphasis: plain
+                .bg(Color::Rgb(255, 180, 180))
+                .add_modifier(Modifier::BOLD),
+            file_header: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            file_status: plain.fg(amber),
+            hunk_header: plain.fg(Color::Magenta),
+            directory: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            symlink: plain.fg(Color::Rgb(0, 110, 110)),
+            submodule: plain.fg(Color::Magenta),
+            fuzzy_match: plain.fg(Color::Blue).add_modifier(Modifier::BOLD),
+            over_limit: plain.fg(amber).add_modifier(Modifier::UNDERLINED),
+            missing_blank_line: plain.fg(Color::White).bg(Color::Rgb(180, 0, 0)),
+            status_bar: plain.fg(Color::Blue),
+            syntax_theme: Some("InspiredGitHub".to_string()),
+        }
+    }
+
+    /// Bright colors and bold text on a dark background.
+    pub fn high_contrast() -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        Self {
+            focused_border: bold.fg(Color::LightYellow),
+            selected: bold.fg(Color::Black).bg(Color::LightYellow),
+            current_branch: bold.fg(Color::Black).bg(Color::LightGreen),
+            cursor_line: bold.add_modifier(Modifier::REVERSED),
+            muted: plain.fg(Color::Gray),
+            error: bold.fg(Color::LightRed),
+            warning: bold.fg(Color::LightYellow),
+            success: bold.fg(Color::LightGreen),
+            added: plain.fg(Color::LightGreen),
+            removed: plain.fg(Color::LightRed),
+            added_line: plain.bg(Color::Black),
+            removed_line: plain.bg(Color::Black),
+            added_emphasis: bold.fg(Color::Black).bg(Color::LightGreen),
+            removed_emphasis: bold.fg(Color::Black).bg(Color::LightRed),
+            file_header: bold.fg(Color::LightCyan).add_modifier(Modifier::UNDERLINED),
+            file_status: plain.fg(Color::LightYellow),
+            hunk_header: bold.fg(Color::LightMagenta),
+            directory: bold.fg(Color::LightBlue),
+            symlink: plain.fg(Color::LightCyan),
+            submodule: plain.fg(Color::LightMagenta),
+            fuzzy_match: bold
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::UNDERLINED),
+            over_limit: bold
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::UNDERLINED),
+            missing_blank_line: bold.fg(Color::White).bg(Color::Red),
+            status_bar: bold.fg(Color::LightCyan),
+            syntax_theme: Some("base16-eighties.dark".to_string()),
+        }
+    }
+
+    /// No colors at all, only bold, underlined and reversed text. Used when `NO_COLOR` is set.
+    pub fn monochrome() -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let reversed = plain.add_modifier(Modifier::REVERSED);
+        let underlined = plain.add_modifier(Modifier::UNDERLINED);
+        Self {
+            focused_border: bold,
+            selected: reversed,
+            current_branch: bold.add_modifier(Modifier::UNDERLINED),
+            cursor_line: reversed,
+            muted: plain.add_modifier(Modifier::DIM),
+            error: bold,
+            warning: bold,
+            success: bold,
+            added: plain,
+            removed: plain,
+            added_line: plain,
+            removed_line: plain,
+            added_emphasis: underlined,
+            removed_emphasis: underlined,
+            file_header: bold,
+            file_status: plain.add_modifier(Modifier::ITALIC),
+            hunk_header: bold,
+            directory: bold,
+            symlink: plain.add_modifier(Modifier::ITALIC),
+            submodule: plain.add_modifier(Modifier::ITALIC),
+            fuzzy_match: bold.add_modifier(Modifier::UNDERLINED),
+            over_limit: underlined,
+            missing_blank_line: reversed,
+            status_bar: plain,
+            syntax_theme: None,
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "monochrome" => Some(Self::monochrome()),
+            _ => None,
+        }
+    }
+
+    // The style slot called `name` in theme files
+    fn slot_mut(&mut self, name: &str) -> Option<&mut Style> {
+        let slot = match name {
+            "focused_border" => &mut self.focused_border,
+            "selected" => &mut self.selected,
+            "current_branch" => &mut self.current_branch,
+            "cursor_line" => &mut self.cursor_line,
+            "muted" => &mut self.muted,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            "success" => &mut self.success,
+            "added" => &mut self.added,
+            "removed" => &mut self.removed,
+            "added_line" => &mut self.added_line,
+            "removed_line" => &mut self.removed_line,
+            "added_emphasis" => &mut self.added_emphasis,
+            "removed_emphasis" => &mut self.removed_emphasis,
+            "file_header" => &mut self.file_header,
+            "file_status" => &mut self.file_status,
+            "hunk_header" => &mut self.hunk_header,
+            "directory" => &mut self.directory,
+            "symlink" => &mut self.symlink,
+            "submodule" => &mut self.submodule,
+            "fuzzy_match" => &mut self.fuzzy_match,
+            "over_limit" => &mut self.over_limit,
+            "missing_blank_line" => &mut self.missing_blank_line,
+            "status_bar" => &mut self.status_bar,
+            _ => return None,
+        };
+        Some(slot)
+    }
+}
+
+/// A user theme from the `[themes.<name>]` section of the config.
+#[derive(Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSpec {
+    pub base: Option<String>,   // Built-in theme to start from; dark if not set
+    pub syntax: Option<String>, // syntect theme for diffs, or "none"
+    pub styles: BTreeMap<String, StyleSpec>,
+}
+
+/// One style slot. Colors are names like `red` or `light-blue`, `#rrggbb` or a 0-255 index.
+#[derive(Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleSpec {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub modifiers: Vec<String>, // bold, dim, italic, underlined or reversed
+}
+
+impl StyleSpec {
+    fn to_style(&self) -> Result<Style, String> {
+        let color =
+            |value: &str| Color::from_str(value).map_err(|_| format!("'{}' is not a color", value));
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(color(bg)?);
+        }
+        for modifier in &self.modifiers {
+            style = style.add_modifier(match modifier.as_str() {
+                "bold" => Modifier::BOLD,
+                "dim" => Modifier::DIM,
+                "italic" => Modifier::ITALIC,
+                "underlined" => Modifier::UNDERLINED,
+                "reversed" => Modifier::REVERSED,
+                other => {
+                    return Err(format!(
+                        "'{}' is not one of bold, dim, italic, underlined or reversed",
+                        other
+                    ))
+                }
+            });
+        }
+        Ok(style)
+    }
+}
+
+impl ThemeSpec {
+    /// Builds the theme: the base theme with every listed slot replaced.
+    pub fn build(&self) -> Result<Theme, String> {
+        let base = self.base.as_deref().unwrap_or("dark");
+        let mut theme = Theme::built_in(base)
+            .ok_or_else(|| format!("base '{}' is not a built-in theme", base))?;
+        for (name, spec) in &self.styles {
+            let slot = theme
+                .slot_mut(name)
+                .ok_or_else(|| format!("'{}' is not a style slot", name))?;
+            *slot = spec.to_style().map_err(|e| format!("{}: {}", name, e))?;
+        }
+        match self.syntax.as_deref() {
+            Some("none") => theme.syntax_theme = None,
+            Some(syntax) => {
+                highlight::check_syntax_theme(syntax)?;
+                theme.syntax_theme = Some(syntax.to_string());
+            }
+            None => {}
+        }
+        Ok(theme)
+    }
+}
+
+/// The theme called `name`: a user theme from `themes`, or a built-in one.
+pub fn find(name: &str, themes: &BTreeMap<String, ThemeSpec>) -> Result<Theme, String> {
+    match themes.get(name) {
+        Some(spec) => spec.build().map_err(|e| format!("themes.{}: {}", name, e)),
+        None => Theme::built_in(name).ok_or_else(|| {
+            format!(
+                "theme.name '{}' is not one of {} or a theme under [themes]",
+                name,
+                BUILT_IN.join(", ")
+            )
+        }),
+    }
+}
+
+/// The theme to draw with. `NO_COLOR` (https://no-color.org) overrides the config.
+pub fn active(config: &Config) -> Theme {
+    if env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()) {
+        return Theme::monochrome();
+    }
+    find(&config.theme.name, &config.themes).unwrap_or_default()
+}
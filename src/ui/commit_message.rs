@@ -0,0 +1,149 @@
+use super::{View, ViewLayout};
+use crate::app_state::{AppState, CommitKind, PickerStage};
+use crate::commit_message;
+use crate::theme::Theme;
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
+use ratatui::Frame;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The commit message editor, with the convention checks below it.
+pub struct CommitMessageView;
+
+impl View for CommitMessageView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(5)])
+            .split(area);
+
+        let Some(commit_state) = state.commit_state.as_ref() else {
+            return;
+        };
+        let theme = &state.theme;
+        let message = commit_state.message.text();
+        let scroll = super::place_cursor(frame, chunks[0], &commit_state.message, 0);
+
+        let message_lines: Vec<Line> = message
+            .split('\n')
+            .enumerate()
+            .map(|(row, line)| render_commit_message_line(row, line, theme))
+            .collect();
+        let subject_width = message.lines().next().map_or(0, UnicodeWidthStr::width);
+        let title = format!(
+            "{} {}/{} (Ctrl+S: Commit, Ctrl+P: Type/Scope, Ctrl+O: Sign Off, Ctrl+T: Co-Author, Ctrl+G: $EDITOR, Esc: Cancel)",
+            match &commit_state.kind {
+                CommitKind::New => "Commit Message".to_string(),
+                CommitKind::Amend => "Amend Last Commit".to_string(),
+                CommitKind::Reword(revision) => format!("Reword Commit {}", revision),
+            },
+            subject_width,
+            commit_message::SUBJECT_LIMIT
+        );
+        let input_prompt = Paragraph::new(message_lines)
+            .scroll((scroll, 0))
+            .block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(input_prompt, chunks[0]);
+
+        let check = commit_state.check();
+        let check_lines: Vec<Line> = check
+            .errors
+            .iter()
+            .map(|error| Line::styled(format!("✗ {}", error), theme.error))
+            .chain(
+                check
+                    .warnings
+                    .iter()
+                    .map(|warning| Line::styled(format!("! {}", warning), theme.warning)),
+            )
+            .collect();
+        let check_lines = if check_lines.is_empty() {
+            vec![Line::styled("✓ Ready to commit", theme.success)]
+        } else {
+            check_lines
+        };
+        let check_paragraph = Paragraph::new(check_lines).block(
+            Block::default()
+                .title("Message Checks")
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(check_paragraph, chunks[1]);
+
+        // Conventional commit picker, drawn over the editor
+        if let Some(picker) = &commit_state.picker {
+            let title = match picker.stage {
+                PickerStage::Type => {
+                    "Commit Type (↑/↓: Select, Enter: Choose, Esc: Close)".to_string()
+                }
+                PickerStage::Scope => format!(
+                    "Scope for '{}' (↑/↓: Select, Enter: Choose, Esc: Close)",
+                    picker.commit_type
+                ),
+            };
+            let area = chunks[0].inner(Margin::new(4, 1));
+            let height = super::inner_rows(area);
+            let start = (picker.selected + 1).saturating_sub(height);
+            let items: Vec<ListItem> = picker
+                .options
+                .iter()
+                .enumerate()
+                .skip(start)
+                .take(height)
+                .map(|(i, (option, description))| {
+                    let style = if i == picker.selected {
+                        theme.cursor_line
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!("{:<12}", option),
+                            style.add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(description.clone(), style.patch(theme.muted)),
+                    ]))
+                })
+                .collect();
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                List::new(items).block(Block::default().title(title).borders(Borders::ALL)),
+                area,
+            );
+        }
+    }
+}
+
+// Color the parts of a commit message line that break the 50/72 column conventions
+fn render_commit_message_line(row: usize, line: &str, theme: &Theme) -> Line<'static> {
+    let limit = match row {
+        0 => commit_message::SUBJECT_LIMIT,
+        1 if !line.trim().is_empty() => {
+            return Line::styled(line.to_string(), theme.missing_blank_line);
+        }
+        _ => commit_message::BODY_LIMIT,
+    };
+
+    // Split at the first char that ends past the limit
+    let mut width = 0;
+    let split = line
+        .char_indices()
+        .find(|(_, c)| {
+            width += c.width().unwrap_or(0);
+            width > limit
+        })
+        .map_or(line.len(), |(i, _)| i);
+    let base_style = if row == 0 {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    Line::from(vec![
+        Span::styled(line[..split].to_string(), base_style),
+        Span::styled(
+            line[split..].to_string(),
+            base_style.patch(theme.over_limit),
+        ),
+    ])
+}
@@ -0,0 +1,219 @@
+use super::{CommitLogView, Split, View, ViewLayout};
+use crate::app_state::{AppState, UIState};
+use crate::diff::{DetailLine, DiffLine, LineKind};
+use crate::theme::Theme;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::Frame;
+use unicode_width::UnicodeWidthChar;
+
+/// The Commit Log with the commit details beside it on wide terminals, or below it. Diffs
+/// that aren't of a commit in the log, like the staged changes, take the whole area.
+pub struct DetailsScreen;
+
+impl View for DetailsScreen {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        if !state.details_from_log {
+            CommitDetailsView.render(state, frame, area, layout);
+            return;
+        }
+        let settings = &state.config.layout;
+        let side_by_side = area.width >= settings.side_by_side_width;
+        let (commit_log_area, details_area, divider) = super::split(
+            area,
+            Split::Details,
+            100 - state.split_percent(Split::Details),
+            side_by_side,
+        );
+        layout.details_divider = Some(divider);
+
+        CommitLogView.render(state, frame, commit_log_area, layout);
+        CommitDetailsView.render(state, frame, details_area, layout);
+    }
+}
+
+/// Commit details: the header, the diffstat and the diff.
+pub struct CommitDetailsView;
+
+impl View for CommitDetailsView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.detail_rows = Some(super::inner_rows(area));
+        layout.details_area = Some(area);
+
+        let details_width = (area.width as usize).saturating_sub(2);
+        let (start, end) = state.commit_details_visible_range;
+        let visible_lines: Vec<Line> = if state.selected_commit_details.is_some() {
+            state.commit_details_lines[start..end]
+                .iter()
+                .map(|line| {
+                    render_detail_line(line, details_width, state.selected_diff_file, &state.theme)
+                })
+                .collect()
+        } else {
+            vec![Line::from("No details available")]
+        };
+
+        let title = state
+            .selected_commit_details
+            .as_ref()
+            .map_or("Commit Details".to_string(), |details| {
+                details.title.clone()
+            });
+        let keys = if state.ui_state == UIState::CommitDetails {
+            "]/[: Files, Enter: Open File, n/N: Hunks, B: Blame, S: Stat Only, v: Layout, H: Highlighting, Esc: Return"
+        } else {
+            "Enter: Open, P: Hide Preview" // Previewed in the main view
+        };
+        let details_widget = Block::default()
+            .title(format!("{} ({})", title, keys))
+            .borders(Borders::ALL);
+
+        let details_paragraph = Paragraph::new(visible_lines)
+            .block(details_widget)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details_paragraph, area);
+    }
+}
+
+// Render one row of the commit details view with diff coloring
+fn render_detail_line(
+    line: &DetailLine,
+    width: usize,
+    selected_file: usize,
+    theme: &Theme,
+) -> Line<'static> {
+    match line {
+        DetailLine::Text(text) => Line::from(text.clone()),
+        DetailLine::Stat {
+            file,
+            label,
+            added_bar,
+            deleted_bar,
+        } => {
+            let label_style = if *file == selected_file {
+                theme.cursor_line
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(label.clone(), label_style),
+                Span::styled("+".repeat(*added_bar), theme.added),
+                Span::styled("-".repeat(*deleted_bar), theme.removed),
+            ])
+        }
+        DetailLine::FileHeader(_, path) => {
+            Line::from(Span::styled(format!("File: {}", path), theme.file_header))
+        }
+        DetailLine::FileStatus(status) => {
+            Line::from(Span::styled(format!("      {}", status), theme.file_status))
+        }
+        DetailLine::HunkHeader(header) => {
+            Line::from(Span::styled(header.clone(), theme.hunk_header))
+        }
+        DetailLine::Diff(diff_line) => {
+            let gutter = format!(
+                "{} {} ",
+                format_lineno(diff_line.old_lineno),
+                format_lineno(diff_line.new_lineno)
+            );
+            Line::from(render_diff_line(diff_line, gutter, theme))
+        }
+        DetailLine::Split(old, new) => {
+            let left_width = width.saturating_sub(1) / 2;
+            let right_width = width.saturating_sub(left_width + 1);
+
+            let old_spans = old.as_ref().map_or(Vec::new(), |line| {
+                render_diff_line(line, format!("{} ", format_lineno(line.old_lineno)), theme)
+            });
+            let new_spans = new.as_ref().map_or(Vec::new(), |line| {
+                render_diff_line(line, format!("{} ", format_lineno(line.new_lineno)), theme)
+            });
+
+            let mut spans = fit_spans(old_spans, left_width);
+            spans.push(Span::styled("│", theme.muted));
+            spans.extend(fit_spans(new_spans, right_width));
+            Line::from(spans)
+        }
+    }
+}
+
+fn format_lineno(lineno: Option<u32>) -> String {
+    lineno.map_or("    ".to_string(), |n| format!("{:>4}", n))
+}
+
+// Gutter, sign and content of a diff line, with syntax colors and word-level emphasis
+fn render_diff_line(line: &DiffLine, gutter: String, theme: &Theme) -> Vec<Span<'static>> {
+    let (sign, line_style, background, emphasis) = match line.kind {
+        LineKind::Added => ("+", theme.added, theme.added_line, theme.added_emphasis),
+        LineKind::Removed => (
+            "-",
+            theme.removed,
+            theme.removed_line,
+            theme.removed_emphasis,
+        ),
+        LineKind::Context => (" ", Style::default(), Style::default(), Style::default()),
+    };
+
+    let mut spans = vec![
+        Span::styled(gutter, theme.muted),
+        Span::styled(sign, line_style),
+    ];
+
+    // Split the content wherever the syntax color or the emphasis changes
+    let mut cuts = vec![0, line.content.len()];
+    cuts.extend(line.syntax.iter().flat_map(|span| [span.start, span.end]));
+    cuts.extend(line.emphasis.iter().flat_map(|&(start, end)| [start, end]));
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    for window in cuts.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let mut style = line_style;
+        if let Some(span) = line
+            .syntax
+            .iter()
+            .find(|span| span.start <= start && start < span.end)
+        {
+            let (r, g, b) = span.color;
+            style = style.fg(Color::Rgb(r, g, b)).patch(background);
+        }
+        if line
+            .emphasis
+            .iter()
+            .any(|&(emphasis_start, emphasis_end)| emphasis_start <= start && start < emphasis_end)
+        {
+            style = style.patch(emphasis);
+        }
+        spans.push(Span::styled(
+            super::expand_tabs(&line.content[start..end]),
+            style,
+        ));
+    }
+
+    spans
+}
+
+// Truncate or pad spans to exactly `width` terminal columns
+fn fit_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Span<'static>> {
+    let mut fitted = Vec::new();
+    let mut used = 0;
+    for span in spans {
+        let mut content = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if used + char_width > width {
+                break;
+            }
+            used += char_width;
+            content.push(c);
+        }
+        fitted.push(Span::styled(content, span.style));
+        if used >= width {
+            break;
+        }
+    }
+    fitted.push(Span::raw(" ".repeat(width - used)));
+    fitted
+}
@@ -0,0 +1,199 @@
+use super::{View, ViewLayout};
+use crate::app_state::{AppState, CommitKind, NotificationKind};
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::Frame;
+
+// A bordered box of wrapped text
+fn message_box<'a>(title: &'a str, text: String) -> Paragraph<'a> {
+    Paragraph::new(text)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .wrap(Wrap { trim: false })
+}
+
+pub struct ConfirmCommitView;
+
+impl View for ConfirmCommitView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let kind = state.commit_state.as_ref().map(|state| &state.kind);
+        let mut question = match kind {
+            Some(CommitKind::Amend) => "Are you sure you want to amend the last commit with the staged changes? It will not be pushed.".to_string(),
+            Some(CommitKind::Reword(revision)) => format!("Are you sure you want to reword commit {}? Commits after it are recreated and not pushed.", revision),
+            _ => "Are you sure you want to commit and push?".to_string(),
+        };
+        if let (Some(CommitKind::Amend | CommitKind::Reword(_)), Some(upstream)) =
+            (kind, &state.rewrite_upstream)
+        {
+            question.push_str(&format!(
+                " The commit is already on {}, so the branch will need a force push.",
+                upstream
+            ));
+        }
+        let confirm_prompt = Block::default()
+            .title(format!("{} (Press Enter to Confirm)", question))
+            .borders(Borders::ALL);
+        frame.render_widget(confirm_prompt, area);
+    }
+}
+
+pub struct ConfirmFixupView;
+
+impl View for ConfirmFixupView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let target = state.fixup_target.clone().unwrap_or_default();
+        let mut confirmation_text = format!(
+            "Create a fixup! commit for {} from the staged changes?\nPress Enter to create it, a to create it and squash it into {} right away, or Esc to cancel.",
+            target, target
+        );
+        if let Some(upstream) = &state.rewrite_upstream {
+            confirmation_text.push_str(&format!(
+                "\nWarning: {} is already on {}; squashing rewrites it, so the branch will need a force push.",
+                target, upstream
+            ));
+        }
+        frame.render_widget(message_box("Confirm Fixup", confirmation_text), area);
+    }
+}
+
+pub struct ConfirmQuitView;
+
+impl View for ConfirmQuitView {
+    fn render(&self, _state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let quit_prompt = Block::default()
+            .title("Are you sure you want to quit? (Press Enter to Confirm, Esc to Cancel)")
+            .borders(Borders::ALL);
+        frame.render_widget(quit_prompt, area);
+    }
+}
+
+pub struct ConfirmMergeView;
+
+impl View for ConfirmMergeView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let confirmation_text = format!(
+            "Are you sure you want to merge into the {} branch?\nPress Enter to confirm or Esc to cancel.",
+            state.config.merge_target(&state.branches)
+        );
+        frame.render_widget(message_box("Confirm Merge", confirmation_text), area);
+    }
+}
+
+pub struct ErrorView;
+
+impl View for ErrorView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let error_message = state
+            .error_message
+            .clone()
+            .unwrap_or("Unknown error".to_string());
+        frame.render_widget(message_box("Error", error_message), area);
+    }
+}
+
+/// The key guide, generated from the keymap so that it shows the keys actually bound.
+pub struct KeyGuideView;
+
+impl View for KeyGuideView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        let rows = super::inner_rows(area);
+        let key_guide = Paragraph::new(state.keymap.guide().join("\n"))
+            .block(Block::default().title("Key Guide").borders(Borders::ALL))
+            .wrap(Wrap { trim: false });
+        // Wrapped inside the side borders, counted without the top and bottom ones
+        let lines = key_guide
+            .line_count(area.width.saturating_sub(2))
+            .saturating_sub(2);
+        layout.key_guide_rows = Some(rows);
+        layout.key_guide_lines = Some(lines);
+
+        // Jumping to the bottom scrolls as far as possible; apply_layout clamps it afterwards
+        let max_scroll = lines.saturating_sub(rows).min(u16::MAX as usize) as u16;
+        let key_guide = key_guide.scroll((state.key_guide_scroll.min(max_scroll), 0));
+        frame.render_widget(key_guide, area);
+    }
+}
+
+/// Results of earlier operations, newest last.
+pub struct MessagesView;
+
+impl View for MessagesView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.messages_rows = Some(super::inner_rows(area));
+        let theme = &state.theme;
+
+        let lines: Vec<Line> = if state.notifications.is_empty() {
+            vec![Line::from("No messages yet")]
+        } else {
+            state
+                .notifications
+                .iter()
+                .map(|notification| {
+                    let (mark, style) = match notification.kind {
+                        NotificationKind::Info => ("•", Style::default()),
+                        NotificationKind::Success => ("✓", theme.success),
+                        NotificationKind::Error => ("✗", theme.error),
+                    };
+                    let message = notification.message.replace('\n', " ");
+                    Line::from(vec![
+                        Span::styled(
+                            notification.time.format("%H:%M:%S ").to_string(),
+                            theme.muted,
+                        ),
+                        Span::styled(format!("{} {}", mark, message), style),
+                    ])
+                })
+                .collect()
+        };
+
+        let messages = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Messages (↑/↓: Scroll, Esc: Close)")
+                    .borders(Borders::ALL),
+            )
+            .scroll((state.messages_scroll, 0));
+        frame.render_widget(messages, area);
+    }
+}
+
+/// Output of the hooks that ran, with a pass or fail line for each.
+pub struct HookOutputView;
+
+impl View for HookOutputView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.list_rows = Some(super::inner_rows(area));
+        let Some(output) = &state.hook_output else {
+            return;
+        };
+        let theme = &state.theme;
+
+        let (start, end) = output.cursor.visible_range;
+        let output_lines: Vec<Line> = output.lines[start..end]
+            .iter()
+            .map(|(line, success)| match success {
+                Some(true) => Line::styled(
+                    format!("✓ {}", line),
+                    theme.success.add_modifier(Modifier::BOLD),
+                ),
+                Some(false) => Line::styled(
+                    format!("✗ {}", line),
+                    theme.error.add_modifier(Modifier::BOLD),
+                ),
+                None => Line::from(super::expand_tabs(line)),
+            })
+            .collect();
+
+        let title = if state.operation.is_some() {
+            "Hook Output (Running…, ↑/↓: Scroll)"
+        } else if output.retry.is_some() {
+            "Hook Output (↑/↓: Scroll, V: Retry Without Hooks, Esc: Close)"
+        } else {
+            "Hook Output (↑/↓: Scroll, Esc: Close)"
+        };
+        let output_widget =
+            Paragraph::new(output_lines).block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(output_widget, area);
+    }
+}
@@ -0,0 +1,150 @@
+use super::{View, ViewLayout};
+use crate::app_state::AppState;
+use crate::git_commands::TreeEntryKind;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use ratatui::Frame;
+
+/// Blame annotations next to each line of a file.
+pub struct BlameView;
+
+impl View for BlameView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.list_rows = Some(super::inner_rows(area));
+        let Some(blame) = &state.blame else {
+            return;
+        };
+        let theme = &state.theme;
+
+        let (start, end) = blame.cursor.visible_range;
+        let blame_lines: Vec<Line> = blame.lines[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let short_id = &line.commit_id[..line.commit_id.len().min(7)];
+                let annotation = format!(
+                    "{:<7} {:<15} {:<10} {:>5} │ ",
+                    short_id,
+                    line.author.chars().take(15).collect::<String>(),
+                    line.date,
+                    line.line_number
+                );
+                let style = if start + i == blame.cursor.selected {
+                    theme.cursor_line
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(annotation, style.patch(theme.muted)),
+                    Span::styled(super::expand_tabs(&line.content), style),
+                ])
+            })
+            .collect();
+
+        let blame_widget = Paragraph::new(blame_lines).block(
+            Block::default()
+                .title(format!(
+                    "Blame {} @ {} (Enter: Show Commit, p: Blame Parent, Esc: Return)",
+                    blame.path, blame.revision
+                ))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(blame_widget, area);
+    }
+}
+
+/// The entries of a directory at some revision.
+pub struct FileTreeView;
+
+impl View for FileTreeView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.list_rows = Some(super::inner_rows(area));
+        let Some(tree) = &state.file_tree else {
+            return;
+        };
+        let theme = &state.theme;
+
+        let (start, end) = tree.cursor.visible_range;
+        let entry_items: Vec<ListItem> = tree.entries[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let (name, style) = match entry.kind {
+                    TreeEntryKind::Directory => (format!("{}/", entry.name), theme.directory),
+                    TreeEntryKind::Symlink => (format!("{}@", entry.name), theme.symlink),
+                    TreeEntryKind::Submodule => {
+                        (format!("{} (submodule)", entry.name), theme.submodule)
+                    }
+                    TreeEntryKind::File => (entry.name.clone(), Style::default()),
+                };
+                let size = entry.size.map_or(String::new(), |size| size.to_string());
+                let style = if start + i == tree.cursor.selected {
+                    style.patch(theme.cursor_line)
+                } else {
+                    style
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>10}  ", size), theme.muted),
+                    Span::styled(name, style),
+                ]))
+            })
+            .collect();
+
+        let location = if tree.directory.is_empty() {
+            "/".to_string()
+        } else {
+            format!("/{}", tree.directory)
+        };
+        let tree_list = List::new(entry_items).block(
+            Block::default()
+                .title(format!(
+                    "Files {} @ {} (Enter/→: Open, ←: Parent, L: History, B: Blame, Esc: Return)",
+                    location, tree.revision
+                ))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(tree_list, area);
+    }
+}
+
+/// A file's contents at some revision, with line numbers.
+pub struct FileContentsView;
+
+impl View for FileContentsView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.list_rows = Some(super::inner_rows(area));
+        let Some(contents) = &state.file_contents else {
+            return;
+        };
+        let theme = &state.theme;
+
+        let (start, end) = contents.cursor.visible_range;
+        let content_lines: Vec<Line> = contents.lines[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let style = if start + i == contents.cursor.selected {
+                    theme.cursor_line
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:>5} │ ", start + i + 1), theme.muted),
+                    Span::styled(super::expand_tabs(line), style),
+                ])
+            })
+            .collect();
+
+        let contents_widget = Paragraph::new(content_lines).block(
+            Block::default()
+                .title(format!(
+                    "{} @ {} (L: History, B: Blame, Esc: Return)",
+                    contents.path, contents.revision
+                ))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(contents_widget, area);
+    }
+}
@@ -0,0 +1,128 @@
+use super::{CommitDetailsView, Split, View, ViewLayout};
+use crate::app_state::{AppState, Panel, UIState};
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::{Block, Borders, List, ListItem};
+use ratatui::Frame;
+
+/// The Commit Log above the Branches panel, with the selected commit's details beside or
+/// below them when the preview is on.
+pub struct MainView;
+
+impl View for MainView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        let settings = &state.config.layout;
+        let area = if settings.preview {
+            let (panels_area, preview_area, divider) = super::split(
+                area,
+                Split::Details,
+                100 - state.split_percent(Split::Details),
+                area.width >= settings.side_by_side_width,
+            );
+            layout.details_divider = Some(divider);
+            CommitDetailsView.render(state, frame, preview_area, layout);
+            panels_area
+        } else {
+            area
+        };
+
+        let (commit_log_area, branch_area, divider) = super::split(
+            area,
+            Split::CommitLog,
+            state.split_percent(Split::CommitLog),
+            false,
+        );
+        layout.commit_log_divider = Some(divider);
+
+        CommitLogView.render(state, frame, commit_log_area, layout);
+        BranchListView.render(state, frame, branch_area, layout);
+    }
+}
+
+// Border for a panel that can have the focus, which it only has in the main view
+fn panel_block<'a>(state: &AppState, focused: bool, title: String) -> Block<'a> {
+    Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(if focused && state.ui_state == UIState::Normal {
+            state.theme.focused_border
+        } else {
+            Style::default()
+        })
+}
+
+pub struct CommitLogView;
+
+impl View for CommitLogView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.commit_log_rows = Some(super::inner_rows(area));
+        layout.commit_log_area = Some(area);
+
+        // Render Commit Log with horizontal scrolling
+        let visible_commits = &state.commit_log[state.visible_range.0..state.visible_range.1];
+        let commit_items: Vec<ListItem> = visible_commits
+            .iter()
+            .enumerate()
+            .map(|(i, commit)| {
+                let global_index = state.visible_range.0 + i;
+
+                // Apply horizontal offset
+                let truncated_commit = commit.get(state.horizontal_offset..).unwrap_or("");
+
+                // Highlight selected commit
+                if global_index == state.selected_index {
+                    ListItem::new(truncated_commit.to_string()).style(state.theme.selected)
+                } else {
+                    ListItem::new(truncated_commit.to_string())
+                }
+            })
+            .collect();
+
+        let title = match &state.compare_base {
+            Some(base) => format!(
+                "Commit Log (Comparing from {}: press x on another commit or branch, Esc to cancel)",
+                base
+            ),
+            None => "Commit Log".to_string(),
+        };
+        let commit_list = List::new(commit_items).block(panel_block(
+            state,
+            matches!(state.focused_panel, Panel::CommitLog),
+            title,
+        ));
+        frame.render_widget(commit_list, area);
+    }
+}
+
+pub struct BranchListView;
+
+impl View for BranchListView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout) {
+        layout.branch_rows = Some(super::inner_rows(area));
+        layout.branch_area = Some(area);
+
+        // Render only visible branches
+        let (start, end) = state.branch_visible_range;
+        let branch_items: Vec<ListItem> = state.branches[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, branch)| {
+                let style = if branch == &state.branch_name {
+                    state.theme.current_branch
+                } else if start + i == state.selected_branch {
+                    state.theme.selected
+                } else {
+                    Style::default()
+                };
+                ListItem::new(branch.clone()).style(style)
+            })
+            .collect();
+
+        let branch_list = List::new(branch_items).block(panel_block(
+            state,
+            matches!(state.focused_panel, Panel::Branches),
+            "Branches".to_string(),
+        ));
+        frame.render_widget(branch_list, area);
+    }
+}
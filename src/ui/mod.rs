@@ -0,0 +1,183 @@
+mod commit_message;
+mod details;
+mod dialogs;
+mod files;
+mod main_view;
+mod prompts;
+mod status_bar;
+
+use crate::app_state::{AppState, UIState};
+use crate::text_input::TextInput;
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Position, Rect};
+use ratatui::Frame;
+
+pub use commit_message::CommitMessageView;
+pub use details::{CommitDetailsView, DetailsScreen};
+pub use dialogs::{
+    ConfirmCommitView, ConfirmFixupView, ConfirmMergeView, ConfirmQuitView, ErrorView,
+    HookOutputView, KeyGuideView, MessagesView,
+};
+pub use files::{BlameView, FileContentsView, FileTreeView};
+pub use main_view::{BranchListView, CommitLogView, MainView};
+pub use prompts::{CreateBranchView, FilterByFileView, SetIdentityView};
+pub use status_bar::StatusBarView;
+
+/// How many times a frame is drawn at most while the view sizes change. Scrolling for the
+/// new sizes can change what is drawn, and so the sizes again; a layout that never settles
+/// is shown as it is rather than redrawn forever.
+pub const MAX_DRAW_PASSES: usize = 3;
+
+/// A screen, or a part of one, drawn from the application state.
+pub trait View {
+    /// Draws into `area`. Views never change the state; sizes that scrolling depends on are
+    /// reported in `layout` instead.
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, layout: &mut ViewLayout);
+}
+
+/// Rows available to the scrolling views, and where the clickable panels are, as found while
+/// drawing. `None` for views that weren't drawn. `AppState::apply_layout` scrolls the views
+/// to match.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct ViewLayout {
+    pub commit_log_rows: Option<usize>,
+    pub branch_rows: Option<usize>,
+    pub commit_log_area: Option<Rect>,
+    pub branch_area: Option<Rect>,
+    pub commit_log_divider: Option<Divider>, // Between the Commit Log and the Branches
+    pub details_divider: Option<Divider>,    // Between the Commit Log and the Commit Details
+    pub details_area: Option<Rect>,          // Commit Details, in full or as the preview
+    pub detail_rows: Option<usize>,
+    pub list_rows: Option<usize>, // Blame, file tree, file contents and hook output
+    pub key_guide_rows: Option<usize>,
+    pub key_guide_lines: Option<usize>, // Rows the wrapped key guide takes
+    pub messages_rows: Option<usize>,
+}
+
+/// The split a divider changes when it is dragged, in place of its setting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Split {
+    CommitLog, // Overrides layout.commit_log_percent
+    Details,   // Overrides layout.details_percent
+}
+
+/// The boundary between two views sharing an area, which the mouse can drag.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Divider {
+    pub split: Split,
+    pub area: Rect,         // The area of both views
+    pub side_by_side: bool, // The views are next to each other rather than stacked
+    pub position: u16,      // Column or row where the second view starts
+}
+
+impl Divider {
+    /// Whether a screen position is on the borders that meet at the divider.
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        if !self.area.contains(Position::new(column, row)) {
+            return false;
+        }
+        let along = if self.side_by_side { column } else { row };
+        along == self.position || along + 1 == self.position
+    }
+
+    /// The share of the area, in percent, that the first view would get with the divider
+    /// moved to a screen position. Each view keeps at least a tenth.
+    pub fn percent_at(&self, column: u16, row: u16) -> u16 {
+        let (along, start, length) = if self.side_by_side {
+            (column, self.area.x, self.area.width)
+        } else {
+            (row, self.area.y, self.area.height)
+        };
+        let offset = along.saturating_sub(start) as u32;
+        let percent = (offset * 100 + length as u32 / 2) / (length.max(1) as u32);
+        (percent as u16).clamp(10, 90)
+    }
+}
+
+// Split `area` between two views, giving the first `percent` of it
+fn split(area: Rect, split: Split, percent: u16, side_by_side: bool) -> (Rect, Rect, Divider) {
+    let chunks = Layout::default()
+        .direction(if side_by_side {
+            Direction::Horizontal
+        } else {
+            Direction::Vertical
+        })
+        .constraints([
+            Constraint::Percentage(percent),
+            Constraint::Percentage(100 - percent),
+        ])
+        .split(area);
+    let divider = Divider {
+        split,
+        area,
+        side_by_side,
+        position: if side_by_side {
+            chunks[1].x
+        } else {
+            chunks[1].y
+        },
+    };
+    (chunks[0], chunks[1], divider)
+}
+
+/// The view for a screen.
+pub fn screen(ui_state: &UIState) -> &'static dyn View {
+    match ui_state {
+        UIState::Normal => &MainView,
+        UIState::CommitMessage => &CommitMessageView,
+        UIState::ConfirmCommit => &ConfirmCommitView,
+        UIState::ConfirmFixup => &ConfirmFixupView,
+        UIState::ConfirmQuit => &ConfirmQuitView,
+        UIState::CommitDetails => &DetailsScreen,
+        UIState::CreateBranch => &CreateBranchView,
+        UIState::SetIdentity => &SetIdentityView,
+        UIState::KeyGuide => &KeyGuideView,
+        UIState::ConfirmMerge => &ConfirmMergeView,
+        UIState::Error => &ErrorView,
+        UIState::FilterByFile => &FilterByFileView,
+        UIState::Blame => &BlameView,
+        UIState::FileTree => &FileTreeView,
+        UIState::FileContents => &FileContentsView,
+        UIState::HookOutput => &HookOutputView,
+        UIState::Messages => &MessagesView,
+    }
+}
+
+/// Draws the current screen with the status bar in the bottom margin, returning the sizes it
+/// was drawn at.
+pub fn draw(frame: &mut Frame, state: &AppState) -> ViewLayout {
+    let mut layout = ViewLayout::default();
+    let area = frame.area().inner(Margin::new(1, 1));
+    screen(&state.ui_state).render(state, frame, area, &mut layout);
+    if area.height > 0 {
+        let status_area = Rect::new(area.x, area.bottom(), area.width, 1);
+        StatusBarView.render(state, frame, status_area, &mut layout);
+    }
+    layout
+}
+
+// Rows inside a bordered block
+fn inner_rows(area: Rect) -> usize {
+    (area.height as usize).saturating_sub(2).max(1)
+}
+
+// Show the terminal cursor at the input's cursor inside a bordered block, after
+// `prefix_width` columns of prompt text. Returns how many rows to scroll the input
+// by so that the cursor stays visible.
+fn place_cursor(f: &mut Frame, area: Rect, input: &TextInput, prefix_width: usize) -> u16 {
+    let inner_height = area.height.saturating_sub(2).max(1) as usize;
+    let inner_width = area.width.saturating_sub(2).max(1) as usize;
+    let (row, column) = input.cursor_position();
+    let scroll = (row + 1).saturating_sub(inner_height);
+    let x = (prefix_width + column).min(inner_width - 1);
+    f.set_cursor_position(Position::new(
+        area.x + 1 + x as u16,
+        area.y + 1 + (row - scroll) as u16,
+    ));
+    scroll as u16
+}
+
+const TAB_WIDTH: usize = 4;
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
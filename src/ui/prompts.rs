@@ -0,0 +1,149 @@
+use super::{View, ViewLayout};
+use crate::app_state::AppState;
+use crate::identity::IdentityScope;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use ratatui::Frame;
+
+/// The name prompt for a new branch.
+pub struct CreateBranchView;
+
+impl View for CreateBranchView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let label = "Enter new branch name: ";
+        let prompt = format!("{}{}", label, state.branch_input.text());
+        super::place_cursor(frame, area, &state.branch_input, label.len());
+
+        let branch_prompt = Paragraph::new(prompt).block(
+            Block::default()
+                .title("Create Branch")
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(branch_prompt, area);
+    }
+}
+
+/// The name and email prompt for commits.
+pub struct SetIdentityView;
+
+impl View for SetIdentityView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(6), Constraint::Min(0)])
+            .split(area);
+        let Some(prompt) = &state.identity_prompt else {
+            return;
+        };
+
+        let (title, note) = match prompt.scope {
+            IdentityScope::Global => (
+                "Who Are You?",
+                "Git doesn't know who you are yet; saved to ~/.gitconfig as user.name/user.email.",
+            ),
+            IdentityScope::Repository => (
+                "Identity for This Repository",
+                "Used for commits in this repository; saved to .git/config as user.name/user.email.",
+            ),
+        };
+        let name_label = "Name:  ";
+        let email_label = "Email: ";
+        let text = format!(
+            "{}\n\n{}{}\n{}{}",
+            note,
+            name_label,
+            prompt.name.text(),
+            email_label,
+            prompt.email.text()
+        );
+
+        // The fields are on the third and fourth rows of the box
+        let (input, row) = if prompt.editing_email {
+            (&prompt.email, 3)
+        } else {
+            (&prompt.name, 2)
+        };
+        let field_area = Rect {
+            y: chunks[0].y + row,
+            height: 3,
+            ..chunks[0]
+        };
+        super::place_cursor(frame, field_area, input, name_label.len());
+
+        let identity_prompt = Paragraph::new(text).block(
+            Block::default()
+                .title(format!(
+                    "{} (Tab: Switch Field, Enter: Next/Save, Esc: Cancel)",
+                    title
+                ))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(identity_prompt, chunks[0]);
+    }
+}
+
+/// The path prompt for filtering commits by file, with matching tracked files below it.
+pub struct FilterByFileView;
+
+impl View for FilterByFileView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        let theme = &state.theme;
+
+        let label = "Enter file name to filter commits by: ";
+        let prompt = format!("{}{}", label, state.filter_input.text());
+        super::place_cursor(frame, chunks[0], &state.filter_input, label.len());
+        let filter_prompt = Paragraph::new(prompt).block(
+            Block::default()
+                .title("Filter Commits by File (Tab: Complete, ↑/↓: Select, Enter: Filter, Esc: Cancel)")
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(filter_prompt, chunks[0]);
+
+        // Keep the highlighted match visible
+        let match_height = super::inner_rows(chunks[1]);
+        let start = (state.selected_filter_match + 1).saturating_sub(match_height);
+        let match_items: Vec<ListItem> = state
+            .filter_matches
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(match_height)
+            .map(|(i, matched)| {
+                let path = &state.tracked_paths[matched.index];
+                let base_style = if i == state.selected_filter_match {
+                    theme.cursor_line
+                } else {
+                    Style::default()
+                };
+                let spans: Vec<Span> = path
+                    .chars()
+                    .enumerate()
+                    .map(|(position, c)| {
+                        if matched.positions.contains(&position) {
+                            Span::styled(c.to_string(), base_style.patch(theme.fuzzy_match))
+                        } else {
+                            Span::styled(c.to_string(), base_style)
+                        }
+                    })
+                    .collect();
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let match_list = List::new(match_items).block(
+            Block::default()
+                .title(format!(
+                    "Tracked Files ({} matches)",
+                    state.filter_matches.len()
+                ))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(match_list, chunks[1]);
+    }
+}
@@ -0,0 +1,72 @@
+use super::{View, ViewLayout};
+use crate::app_state::{AppState, NotificationKind};
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+/// One row with the branch, HEAD, upstream, operation in progress and working tree changes,
+/// followed by the latest notification while it is recent.
+pub struct StatusBarView;
+
+impl View for StatusBarView {
+    fn render(&self, state: &AppState, frame: &mut Frame, area: Rect, _layout: &mut ViewLayout) {
+        let theme = &state.theme;
+        let mut spans = Vec::new();
+
+        match &state.repo_status {
+            Some(status) => {
+                let branch = status.branch.as_deref().unwrap_or("detached HEAD");
+                spans.push(Span::styled(
+                    branch.to_string(),
+                    theme.status_bar.add_modifier(Modifier::BOLD),
+                ));
+                spans.push(Span::styled(
+                    format!(" {}", status.head.as_deref().unwrap_or("no commits")),
+                    theme.status_bar,
+                ));
+                if let Some((ahead, behind)) = status.ahead_behind {
+                    spans.push(Span::styled(
+                        format!(" ↑{} ↓{}", ahead, behind),
+                        theme.status_bar,
+                    ));
+                }
+                if let Some(operation) = status.operation {
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        operation,
+                        theme.warning.add_modifier(Modifier::BOLD),
+                    ));
+                }
+                let changes = match status.changed {
+                    0 => " clean".to_string(),
+                    1 => " 1 change".to_string(),
+                    count => format!(" {} changes", count),
+                };
+                spans.push(Span::styled(changes, theme.status_bar));
+            }
+            None => spans.push(Span::styled("Repository status unavailable", theme.error)),
+        }
+
+        if let Some(notification) = state.current_notification() {
+            let style = match notification.kind {
+                NotificationKind::Info => theme.status_bar,
+                NotificationKind::Success => theme.success,
+                NotificationKind::Error => theme.error,
+            };
+            spans.push(Span::styled(" │ ", theme.muted));
+            spans.push(Span::styled(
+                notification
+                    .message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                style,
+            ));
+        }
+
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
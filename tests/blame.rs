@@ -0,0 +1,82 @@
+mod common;
+
+use common::{run_actions, Fixture};
+use ezgit_rs::app_state::{AppState, UIState};
+use ezgit_rs::input::Action;
+use std::fs;
+use std::path::Path;
+
+// Blames the first file of the diff the actions show
+fn blame_first_file(state: &mut AppState, actions: Vec<Action>) {
+    run_actions(state, actions);
+    assert_eq!(state.ui_state, UIState::CommitDetails);
+    run_actions(state, vec![Action::BlameFile]);
+}
+
+#[test]
+fn a_staged_new_file_is_not_blamed_at_head() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.stage(&[("new.txt", "new\n")]);
+    let mut state = fixture.app_state();
+
+    blame_first_file(&mut state, vec![Action::ShowStagedChanges]);
+
+    assert_eq!(state.ui_state, UIState::Error);
+    assert_eq!(
+        state.error_message.as_deref(),
+        Some("Cannot blame 'new.txt': this version of it is not committed")
+    );
+}
+
+#[test]
+fn deleted_files_are_blamed_where_they_were_last_present() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+
+    // Deleted in the working tree only: the index holds the old side, which has no commit
+    fs::remove_file(fixture.dir.path().join("README.md")).unwrap();
+    blame_first_file(&mut state, vec![Action::ShowUnstagedChanges]);
+    assert_eq!(state.ui_state, UIState::Error);
+    assert!(state.blame.is_none());
+
+    // Once the deletion is staged, the old side is HEAD
+    let mut index = fixture.repo.index().unwrap();
+    index.remove_path(Path::new("README.md")).unwrap();
+    index.write().unwrap();
+    run_actions(&mut state, vec![Action::Deselect]);
+    blame_first_file(&mut state, vec![Action::ShowStagedChanges]);
+    assert_eq!(state.ui_state, UIState::Blame);
+    let blame = state.blame.as_ref().unwrap();
+    assert_eq!(blame.revision, "HEAD");
+    assert_eq!(blame.lines[0].content, "hello");
+}
+
+#[test]
+fn a_file_deleted_in_a_compare_range_is_blamed_at_its_start() {
+    let fixture = Fixture::new();
+    let old = fixture.commit("init", &[("README.md", "hello\n")]);
+    let new = fixture.commit("add", &[("added.txt", "added\n")]);
+    let mut state = fixture.app_state();
+
+    // From the newer commit back to the older one, where added.txt doesn't exist
+    blame_first_file(
+        &mut state,
+        vec![
+            Action::MarkForCompare,
+            Action::NavigateDown,
+            Action::MarkForCompare,
+        ],
+    );
+    let (new, old) = (&new.to_string()[..7], &old.to_string()[..7]);
+    assert_eq!(
+        state.selected_commit_details.as_ref().unwrap().title,
+        format!("Compare {}..{}", new, old)
+    );
+
+    assert_eq!(state.ui_state, UIState::Blame);
+    let blame = state.blame.as_ref().unwrap();
+    assert_eq!(blame.revision, new);
+    assert_eq!(blame.path, "added.txt");
+}
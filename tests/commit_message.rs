@@ -0,0 +1,52 @@
+mod common;
+
+use common::{run_actions, type_text, Fixture};
+use ezgit_rs::app_state::{CommitKind, NotificationKind, UIState};
+use ezgit_rs::input::Action;
+
+#[test]
+fn new_commits_need_a_body() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::CommitWork]);
+    type_text(&mut state, "Subject only");
+
+    run_actions(&mut state, vec![Action::Submit]);
+
+    assert_eq!(state.ui_state, UIState::CommitMessage);
+    let latest = state.current_notification().unwrap();
+    assert_eq!(latest.kind, NotificationKind::Error);
+    assert_eq!(
+        latest.message,
+        "Cannot commit yet: A message body is required"
+    );
+}
+
+#[test]
+fn subject_only_commits_can_be_amended_and_reworded() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.commit("more", &[("more.txt", "more\n")]);
+    let mut state = fixture.app_state();
+
+    run_actions(&mut state, vec![Action::AmendCommit, Action::Submit]);
+    assert_eq!(state.ui_state, UIState::ConfirmCommit);
+    let check = state.commit_state.as_ref().unwrap().check();
+    assert_eq!(check.warnings, vec!["The message has no body"]);
+
+    run_actions(
+        &mut state,
+        vec![
+            Action::Cancel,
+            Action::NavigateDown,
+            Action::RewordCommit,
+            Action::Submit,
+        ],
+    );
+    assert!(matches!(
+        state.commit_state.as_ref().unwrap().kind,
+        CommitKind::Reword(_)
+    ));
+    assert_eq!(state.ui_state, UIState::ConfirmCommit);
+}
@@ -0,0 +1,279 @@
+// Shared by the integration tests; each test file uses only some of the helpers
+#![allow(dead_code)]
+
+use ezgit_rs::app_state::AppState;
+use ezgit_rs::events::{self, Effect};
+use ezgit_rs::git_commands;
+use ezgit_rs::hooks;
+use ezgit_rs::input::Action;
+use ezgit_rs::ui::{self, ViewLayout};
+use git2::{BranchType, Oid, Repository, RepositoryInitOptions, Signature, Time};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+// Noon UTC, so the dates in the Commit Log are the same in every time zone
+const BASE_TIME: i64 = 1_699_963_200;
+
+/// A throwaway repository in a temporary directory, on a `main` branch with no commits yet.
+/// Commits get fixed authors and times, so their IDs are the same on every run.
+pub struct Fixture {
+    pub dir: TempDir,
+    pub repo: Repository,
+    commits: Cell<i64>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let dir = TempDir::new().expect("Failed to create a temporary directory");
+        let mut options = RepositoryInitOptions::new();
+        options.initial_head("main");
+        let repo = Repository::init_opts(dir.path(), &options).expect("Failed to init repository");
+
+        // Identity for commits made by the code under test
+        let mut config = repo.config().expect("Failed to open repository config");
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        config.set_bool("commit.gpgsign", false).unwrap();
+
+        // Only hooks installed with `hook` run, not global ones or those from a git template
+        let hooks = repo.path().join("test-hooks");
+        fs::create_dir(&hooks).unwrap();
+        config
+            .set_str("core.hooksPath", hooks.to_str().unwrap())
+            .unwrap();
+
+        Self {
+            dir,
+            repo,
+            commits: Cell::new(0),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        self.dir
+            .path()
+            .to_str()
+            .expect("Temporary path is not UTF-8")
+    }
+
+    // Each commit is a minute after the previous one
+    fn signature(&self) -> Signature<'static> {
+        let count = self.commits.get();
+        self.commits.set(count + 1);
+        let time = Time::new(BASE_TIME + count * 60, 0);
+        Signature::new("Test User", "test@example.com", &time).unwrap()
+    }
+
+    /// Writes `files` into the working tree, stages everything, including removals, and
+    /// commits it on HEAD.
+    pub fn commit(&self, message: &str, files: &[(&str, &str)]) -> Oid {
+        for (path, contents) in files {
+            let full_path = self.dir.path().join(path);
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(full_path, contents).unwrap();
+        }
+        let mut index = self.repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap(); // Stage removed files too
+        index.write().unwrap();
+        let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+        let parent = self.head_commit();
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let signature = self.signature();
+        self.repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+    }
+
+    /// Creates `name` at HEAD without switching to it.
+    pub fn branch(&self, name: &str) {
+        let head = self.head_commit().expect("Branching needs a commit");
+        self.repo.branch(name, &head, false).unwrap();
+    }
+
+    pub fn checkout(&self, name: &str) {
+        git_commands::checkout_branch(self.path(), name).unwrap();
+    }
+
+    /// Merges `branch` into the current branch with a two-parent commit. The branches must not
+    /// conflict.
+    pub fn merge(&self, branch: &str, message: &str) -> Oid {
+        let ours = self.head_commit().unwrap();
+        let theirs = self
+            .repo
+            .find_branch(branch, BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        let mut index = self.repo.merge_commits(&ours, &theirs, None).unwrap();
+        assert!(!index.has_conflicts(), "Fixture merge has conflicts");
+        let tree = self
+            .repo
+            .find_tree(index.write_tree_to(&self.repo).unwrap())
+            .unwrap();
+        let signature = self.signature();
+        let oid = self
+            .repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &[&ours, &theirs],
+            )
+            .unwrap();
+        self.repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        oid
+    }
+
+    /// Creates an empty bare repository and adds it as the remote `name`.
+    pub fn bare_remote(&self, name: &str) -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let remote = Repository::init_bare(dir.path()).unwrap();
+        self.repo
+            .remote(name, dir.path().to_str().unwrap())
+            .unwrap();
+        (dir, remote)
+    }
+
+    /// Installs an executable hook running the shell `script`.
+    pub fn hook(&self, name: &str, script: &str) {
+        let dir = hooks::hooks_dir(&self.repo);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    /// Stages `files` without committing them.
+    pub fn stage(&self, files: &[(&str, &str)]) {
+        let mut index = self.repo.index().unwrap();
+        for (path, contents) in files {
+            fs::write(self.dir.path().join(path), contents).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+    }
+
+    pub fn head_commit(&self) -> Option<git2::Commit<'_>> {
+        self.repo.head().ok()?.peel_to_commit().ok()
+    }
+
+    pub fn head_branch(&self) -> Option<String> {
+        let head = self.repo.head().ok()?;
+        head.is_branch()
+            .then(|| head.shorthand().map(String::from))
+            .flatten()
+    }
+
+    pub fn branch_tip(&self, name: &str) -> Oid {
+        self.repo
+            .find_branch(name, BranchType::Local)
+            .unwrap()
+            .get()
+            .target()
+            .unwrap()
+    }
+
+    /// The app as it starts up in this repository.
+    pub fn app_state(&self) -> AppState {
+        let commit_log = git_commands::get_commit_log(self.path());
+        let branches = git_commands::get_branches(self.path());
+        AppState::new(commit_log, branches, self.path())
+    }
+}
+
+/// Applies the actions in order, as if their keys had been pressed, and returns the effects
+/// left for the event loop. Operations an action starts finish before the next action.
+pub fn run_actions(state: &mut AppState, actions: Vec<Action>) -> Vec<Effect> {
+    let mut effects = Vec::new();
+    for action in actions {
+        effects.extend(events::update(state, action));
+        events::wait_for_operation(state);
+    }
+    effects
+}
+
+/// Types `text` into the focused text input.
+pub fn type_text(state: &mut AppState, text: &str) {
+    run_actions(state, text.chars().map(Action::TextInput).collect());
+}
+
+/// Draws the current screen the way the event loop does, redrawing until the view sizes
+/// settle, and returns its text row by row.
+pub fn render(state: &mut AppState, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let mut layout = ViewLayout::default();
+    for _ in 0..ui::MAX_DRAW_PASSES {
+        state.apply_layout(&layout);
+        let mut drawn = ViewLayout::default();
+        terminal
+            .draw(|frame| drawn = ui::draw(frame, state))
+            .unwrap();
+        if drawn == layout {
+            break;
+        }
+        layout = drawn;
+    }
+
+    let buffer = terminal.backend().buffer();
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        let row: String = (0..buffer.area.width)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect();
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Compares `actual` with `tests/snapshots/<name>.txt`. Run with `UPDATE_SNAPSHOTS=1` to
+/// write the snapshots instead.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Missing snapshot {}; run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "Snapshot {} changed.\n--- expected\n{}\n--- actual\n{}",
+        name,
+        expected,
+        actual
+    );
+}
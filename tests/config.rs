@@ -0,0 +1,94 @@
+mod common;
+
+use common::{run_actions, Fixture};
+use ezgit_rs::app_state::{InputMode, UIState};
+use ezgit_rs::config;
+use ezgit_rs::events;
+use ezgit_rs::git_commands;
+use ezgit_rs::input::Action;
+use std::fs;
+
+#[test]
+fn the_repository_config_is_found_from_a_subdirectory() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("src/lib.rs", "\n")]);
+    fs::write(
+        fixture.dir.path().join(".ezgit.toml"),
+        "[git]\nremote = \"upstream\"\n",
+    )
+    .unwrap();
+    let subdirectory = fixture.dir.path().join("src");
+    let subdirectory = subdirectory.to_str().unwrap();
+
+    let workdir = git_commands::find_workdir(subdirectory).unwrap();
+    assert_eq!(
+        fs::canonicalize(&workdir).unwrap(),
+        fs::canonicalize(fixture.path()).unwrap()
+    );
+    assert_eq!(config::load(subdirectory).unwrap().git.remote, "upstream");
+}
+
+#[test]
+fn closing_an_error_over_the_identity_prompt_returns_to_it() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::SetIdentity]);
+    assert_eq!(state.ui_state, UIState::SetIdentity);
+
+    events::show_error(&mut state, "Invalid key bindings".to_string());
+    assert_eq!(state.ui_state, UIState::Error);
+    assert!(matches!(state.input_mode, InputMode::Command));
+
+    run_actions(&mut state, vec![Action::Deselect]);
+    assert_eq!(state.ui_state, UIState::SetIdentity);
+    assert!(matches!(state.input_mode, InputMode::Text));
+    assert_eq!(state.error_message, None);
+}
+
+#[test]
+#[cfg(feature = "syntax-highlighting")] // Any name will do without highlighting
+fn an_unknown_syntax_theme_is_a_config_error() {
+    let fixture = Fixture::new();
+    let config_file = fixture.dir.path().join(".ezgit.toml");
+    fs::write(
+        &config_file,
+        "[theme]\nname = \"mine\"\n[themes.mine]\nsyntax = \"Solarized (light)\"\n",
+    )
+    .unwrap();
+    assert!(config::load(fixture.path()).is_ok());
+
+    fs::write(
+        &config_file,
+        "[theme]\nname = \"mine\"\n[themes.mine]\nsyntax = \"Monokai\"\n",
+    )
+    .unwrap();
+    let err = config::load(fixture.path()).unwrap_err();
+    assert!(
+        err.contains("themes.mine: syntax 'Monokai' is not one of"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn the_side_by_side_width_must_fit_a_terminal() {
+    let fixture = Fixture::new();
+    let config_file = fixture.dir.path().join(".ezgit.toml");
+    for width in [0, 5000] {
+        fs::write(
+            &config_file,
+            format!("[layout]\nside_by_side_width = {}\n", width),
+        )
+        .unwrap();
+        let err = config::load(fixture.path()).unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "layout.side_by_side_width {} must be between 40 and 1000",
+                width
+            )),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,314 @@
+mod common;
+
+use common::Fixture;
+use ezgit_rs::git_commands;
+use ezgit_rs::hooks::Hooks;
+use ezgit_rs::identity::{self, IdentityScope, Role};
+
+// main: init, main work; feature (checked out): init, feature work
+fn diverged() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.branch("feature");
+    fixture.commit("main work", &[("main.txt", "main\n")]);
+    fixture.checkout("feature");
+    fixture.commit("feature work", &[("feature.txt", "feature\n")]);
+    fixture
+}
+
+#[test]
+fn commit_log_lists_commits_newest_first() {
+    let fixture = diverged();
+    let log = git_commands::get_commit_log(fixture.path());
+    assert_eq!(log.len(), 2);
+    assert!(log[0].ends_with("| feature work"), "{}", log[0]);
+    assert!(log[1].ends_with("| init"), "{}", log[1]);
+    assert_eq!(
+        git_commands::get_branches(fixture.path()),
+        vec!["feature", "main"]
+    );
+}
+
+#[test]
+fn merge_creates_a_merge_commit_on_the_target() {
+    let fixture = diverged();
+    let main_tip = fixture.branch_tip("main");
+    let feature_tip = fixture.branch_tip("feature");
+
+    git_commands::merge_into_branch(fixture.path(), "main", &mut Hooks::new(true)).unwrap();
+
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    let merge = fixture.head_commit().unwrap();
+    let parents: Vec<_> = merge.parent_ids().collect();
+    assert_eq!(parents, vec![main_tip, feature_tip]);
+    assert_eq!(merge.summary(), Some("Merge branch 'feature' into main"));
+    assert_eq!(fixture.branch_tip("feature"), feature_tip);
+
+    // Both sides' files are in the merge and checked out
+    let tree = merge.tree().unwrap();
+    assert!(tree.get_name("main.txt").is_some());
+    assert!(tree.get_name("feature.txt").is_some());
+    assert!(fixture.dir.path().join("feature.txt").exists());
+    assert_eq!(fixture.repo.state(), git2::RepositoryState::Clean);
+}
+
+#[test]
+fn merge_into_itself_is_refused() {
+    let fixture = diverged();
+    fixture.checkout("main");
+    let main_tip = fixture.branch_tip("main");
+
+    let err =
+        git_commands::merge_into_branch(fixture.path(), "main", &mut Hooks::new(true)).unwrap_err();
+
+    assert!(err.contains("into itself"), "{}", err);
+    assert_eq!(fixture.branch_tip("main"), main_tip);
+}
+
+#[test]
+fn merge_of_an_already_merged_branch_changes_nothing() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.branch("old");
+    fixture.commit("more", &[("more.txt", "more\n")]);
+    let main_tip = fixture.branch_tip("main");
+    fixture.checkout("old");
+
+    let err =
+        git_commands::merge_into_branch(fixture.path(), "main", &mut Hooks::new(true)).unwrap_err();
+
+    assert_eq!(err, "'old' is already merged into 'main'");
+    assert_eq!(fixture.branch_tip("main"), main_tip);
+    // Still on the merged branch, with its files
+    assert_eq!(fixture.head_branch().as_deref(), Some("old"));
+    assert!(!fixture.dir.path().join("more.txt").exists());
+    assert_eq!(fixture.repo.state(), git2::RepositoryState::Clean);
+}
+
+#[test]
+fn merge_with_conflicts_reports_them() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("shared.txt", "base\n")]);
+    fixture.branch("feature");
+    fixture.commit("main edit", &[("shared.txt", "main\n")]);
+    let main_tip = fixture.branch_tip("main");
+    fixture.checkout("feature");
+    fixture.commit("feature edit", &[("shared.txt", "feature\n")]);
+
+    let err =
+        git_commands::merge_into_branch(fixture.path(), "main", &mut Hooks::new(true)).unwrap_err();
+
+    assert!(err.contains("conflicts"), "{}", err);
+    assert_eq!(fixture.branch_tip("main"), main_tip);
+    assert_eq!(fixture.repo.state(), git2::RepositoryState::Merge); // Left for the user to resolve
+}
+
+#[test]
+fn push_sends_the_current_branch() {
+    let fixture = diverged();
+    let (_remote_dir, remote) = fixture.bare_remote("origin");
+
+    git_commands::push_to_remote(fixture.path(), "origin", &mut Hooks::new(true)).unwrap();
+
+    let pushed = remote.find_reference("refs/heads/feature").unwrap();
+    assert_eq!(pushed.target(), Some(fixture.branch_tip("feature")));
+    assert!(remote.find_reference("refs/heads/main").is_err());
+}
+
+#[test]
+fn push_from_a_detached_head_is_refused() {
+    let fixture = diverged();
+    let (_remote_dir, remote) = fixture.bare_remote("origin");
+    let head = fixture.head_commit().unwrap().id();
+    fixture.repo.set_head_detached(head).unwrap();
+
+    let err =
+        git_commands::push_to_remote(fixture.path(), "origin", &mut Hooks::new(true)).unwrap_err();
+
+    assert!(err.contains("detached"), "{}", err);
+    assert!(remote.references().unwrap().next().is_none());
+}
+
+#[test]
+fn push_to_a_missing_remote_names_it() {
+    let fixture = diverged();
+
+    let err = git_commands::push_to_remote(fixture.path(), "upstream", &mut Hooks::new(true))
+        .unwrap_err();
+
+    assert!(err.contains("'upstream'"), "{}", err);
+}
+
+#[test]
+fn file_history_follows_the_given_revision() {
+    let fixture = diverged();
+    fixture.checkout("main");
+    fixture.commit("main readme", &[("README.md", "main\n")]);
+    fixture.checkout("feature");
+    fixture.commit("feature readme", &[("README.md", "feature\n")]);
+
+    let history = git_commands::get_commits_for_file(fixture.path(), "main", "README.md").unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].ends_with("| main readme"), "{}", history[0]);
+    assert!(history[1].ends_with("| init"), "{}", history[1]);
+
+    let err = git_commands::get_commits_for_file(fixture.path(), "nope", "README.md").unwrap_err();
+    assert!(err.starts_with("Failed to resolve 'nope'"), "{}", err);
+}
+
+#[test]
+fn pushed_commits_are_found_on_the_upstream() {
+    let fixture = diverged();
+    let init = fixture.head_commit().unwrap().parent_id(0).unwrap();
+    let mut feature = fixture
+        .repo
+        .find_branch("feature", git2::BranchType::Local)
+        .unwrap();
+    feature.set_upstream(Some("main")).unwrap();
+
+    assert_eq!(
+        git_commands::upstream_containing(fixture.path(), &init.to_string()).unwrap(),
+        Some("main".to_string())
+    );
+    assert_eq!(
+        git_commands::upstream_containing(fixture.path(), "HEAD").unwrap(),
+        None
+    );
+}
+
+#[test]
+fn autosquash_with_unstaged_changes_commits_nothing() {
+    let fixture = Fixture::new();
+    let target = fixture.commit("init", &[("a.txt", "a\n"), ("b.txt", "b\n")]);
+    fixture.commit("more", &[("c.txt", "c\n")]);
+    let head = fixture.head_commit().unwrap().id();
+    fixture.stage(&[("a.txt", "fixed\n")]);
+    std::fs::write(fixture.dir.path().join("b.txt"), "unstaged\n").unwrap();
+
+    let err = git_commands::fixup_and_autosquash(
+        fixture.path(),
+        &target.to_string(),
+        &mut Hooks::new(true),
+    )
+    .unwrap_err();
+
+    assert!(err.contains("clean working tree"), "{}", err);
+    assert_eq!(fixture.head_commit().unwrap().id(), head);
+}
+
+#[test]
+fn autosquash_squashes_the_staged_changes_into_the_target() {
+    let fixture = Fixture::new();
+    let target = fixture.commit("init", &[("a.txt", "a\n")]);
+    fixture.commit("more", &[("c.txt", "c\n")]);
+    fixture.stage(&[("a.txt", "fixed\n")]);
+
+    git_commands::fixup_and_autosquash(fixture.path(), &target.to_string(), &mut Hooks::new(true))
+        .unwrap();
+
+    let head = fixture.head_commit().unwrap();
+    assert_eq!(head.summary(), Some("more"));
+    let squashed = head.parent(0).unwrap();
+    assert_eq!(squashed.summary(), Some("init"));
+    let blob = squashed.tree().unwrap().get_name("a.txt").unwrap().id();
+    assert_eq!(fixture.repo.find_blob(blob).unwrap().content(), b"fixed\n");
+}
+
+#[test]
+fn reword_runs_the_commit_msg_hook() {
+    let fixture = diverged();
+    let target = fixture.head_commit().unwrap().parent_id(0).unwrap();
+    fixture.hook("commit-msg", "echo 'Checked-by: hook' >> \"$1\"");
+
+    git_commands::reword_commit(
+        fixture.path(),
+        &target.to_string(),
+        "Initial commit",
+        &mut Hooks::new(true),
+    )
+    .unwrap();
+
+    let reworded = fixture.head_commit().unwrap().parent(0).unwrap();
+    assert_eq!(
+        reworded.message(),
+        Some("Initial commit\nChecked-by: hook\n")
+    );
+
+    fixture.hook("commit-msg", "exit 1");
+    let mut hooks = Hooks::new(true);
+    let err =
+        git_commands::reword_commit(fixture.path(), "HEAD", "Rejected", &mut hooks).unwrap_err();
+    assert!(err.contains("commit-msg"), "{}", err);
+    assert!(hooks.failed());
+}
+
+#[test]
+fn ssh_signed_commits_are_marked_and_verified() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let key = fixture.dir.path().join("key");
+    let generated = std::process::Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "", "-f"])
+        .arg(&key)
+        .status();
+    if !generated.is_ok_and(|status| status.success()) {
+        return; // No ssh-keygen to sign with
+    }
+    let public_key = std::fs::read_to_string(key.with_extension("pub")).unwrap();
+    let allowed_signers = fixture.dir.path().join("allowed_signers");
+    std::fs::write(&allowed_signers, format!("test@example.com {}", public_key)).unwrap();
+    let mut config = fixture.repo.config().unwrap();
+    config.set_bool("commit.gpgsign", true).unwrap();
+    config.set_str("gpg.format", "ssh").unwrap();
+    config
+        .set_str("user.signingkey", key.to_str().unwrap())
+        .unwrap();
+    config
+        .set_str(
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().unwrap(),
+        )
+        .unwrap();
+
+    git_commands::amend_head(fixture.path(), "signed", &mut Hooks::new(true)).unwrap();
+
+    let log = git_commands::get_commit_log(fixture.path());
+    assert!(log[0].ends_with("| [signed] signed"), "{}", log[0]);
+    let details = git_commands::get_commit_details(fixture.path(), "HEAD").unwrap();
+    assert!(
+        details
+            .header
+            .contains("Signature: Good signature from test@example.com"),
+        "{}",
+        details.header
+    );
+    let preview = git_commands::get_commit_preview(fixture.path(), "HEAD").unwrap();
+    assert!(
+        preview
+            .header
+            .contains("Signature: Signed; open the commit to verify it"),
+        "{}",
+        preview.header
+    );
+    // Nothing is left behind for the next check
+    assert!(!fixture.repo.path().join("ezgit/verify.sig").exists());
+}
+
+#[test]
+fn a_repository_identity_is_used_for_author_and_committer() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+
+    identity::save(
+        fixture.path(),
+        IdentityScope::Repository,
+        "Repo User",
+        " repo@example.com ",
+    )
+    .unwrap();
+
+    for role in [Role::Author, Role::Committer] {
+        let resolved = identity::resolve(&fixture.repo, role).unwrap();
+        assert_eq!(resolved.to_string(), "Repo User <repo@example.com>");
+    }
+}
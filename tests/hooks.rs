@@ -0,0 +1,97 @@
+mod common;
+
+use common::{run_actions, Fixture};
+use ezgit_rs::app_state::{AppState, HookRetry, NotificationKind, UIState};
+use ezgit_rs::events;
+use ezgit_rs::input::Action;
+use std::time::{Duration, Instant};
+
+fn hook_lines(state: &AppState) -> Vec<String> {
+    state
+        .hook_output
+        .as_ref()
+        .map(|output| output.lines.iter().map(|(line, _)| line.clone()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn hook_output_shows_while_the_hook_runs() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.hook(
+        "pre-commit",
+        "echo first; while [ ! -f release ]; do sleep 0.05; done; echo second",
+    );
+    fixture.stage(&[("new.txt", "new\n")]);
+    let mut state = fixture.app_state();
+    run_actions(
+        &mut state,
+        vec![
+            Action::AmendCommit,
+            Action::Paste("\n\nWith a new file.".to_string()),
+            Action::Submit,
+        ],
+    );
+
+    assert_eq!(state.ui_state, UIState::ConfirmCommit);
+    events::update(&mut state, Action::Confirm);
+    let start = Instant::now();
+    while !hook_lines(&state).contains(&"first".to_string()) {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "The hook never printed"
+        );
+        events::poll_operation(&mut state);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(state.operation.is_some());
+    assert_eq!(state.ui_state, UIState::HookOutput);
+    assert_eq!(hook_lines(&state)[0], "pre-commit (running…)");
+
+    // Other keys wait until the operation is done
+    events::update(&mut state, Action::Deselect);
+    assert_eq!(state.ui_state, UIState::HookOutput);
+
+    std::fs::write(fixture.dir.path().join("release"), "").unwrap();
+    events::wait_for_operation(&mut state);
+    assert!(state.operation.is_none());
+    let amended = fixture.head_commit().unwrap();
+    assert_eq!(amended.message(), Some("init\n\nWith a new file.\n"));
+    assert_eq!(
+        state.current_notification().unwrap().kind,
+        NotificationKind::Success
+    );
+    assert_eq!(
+        hook_lines(&state),
+        vec!["pre-commit (exit status: 0)", "first", "second", ""]
+    );
+}
+
+#[test]
+fn a_merge_stopped_by_a_hook_can_run_without_it() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.branch("feature");
+    fixture.commit("main work", &[("main.txt", "main\n")]);
+    fixture.checkout("feature");
+    fixture.commit("feature work", &[("feature.txt", "feature\n")]);
+    fixture.hook("pre-merge-commit", "echo 'Not today'; exit 1");
+    let mut state = fixture.app_state();
+
+    run_actions(&mut state, vec![Action::MergeBranch, Action::Select]);
+    assert_eq!(state.ui_state, UIState::HookOutput);
+    let output = state.hook_output.as_ref().unwrap();
+    assert!(matches!(
+        &output.retry,
+        Some(HookRetry::Merge { target }) if target == "main"
+    ));
+    assert!(hook_lines(&state).contains(&"Not today".to_string()));
+    assert_eq!(fixture.head_branch().as_deref(), Some("feature")); // Back where it started
+    assert_eq!(fixture.repo.state(), git2::RepositoryState::Clean);
+    assert!(!fixture.dir.path().join("main.txt").exists());
+
+    run_actions(&mut state, vec![Action::BypassHooks]);
+    assert_eq!(state.ui_state, UIState::Normal);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    assert_eq!(fixture.head_commit().unwrap().parent_count(), 2);
+}
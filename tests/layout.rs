@@ -0,0 +1,145 @@
+mod common;
+
+use common::{render, run_actions, Fixture};
+use ezgit_rs::app_state::UIState;
+use ezgit_rs::config::Config;
+use ezgit_rs::input::Action;
+use ezgit_rs::ui::Split;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..12 {
+        fixture.commit(&format!("commit {}", i), &[("file.txt", &i.to_string())]);
+    }
+    fixture
+}
+
+#[test]
+fn details_go_beside_the_log_on_wide_terminals() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::Select]);
+
+    render(&mut state, 200, 30);
+    let divider = state.layout.details_divider.unwrap();
+    assert!(divider.side_by_side);
+    assert_eq!(divider.position, 1 + 198 * 40 / 100); // Inside the 1-cell margin
+    assert!(state.layout.commit_log_area.is_some());
+
+    render(&mut state, 100, 30);
+    assert!(!state.layout.details_divider.unwrap().side_by_side);
+}
+
+#[test]
+fn resizing_keeps_the_selection_visible() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 30);
+    run_actions(&mut state, vec![Action::NavigateBottom]);
+    assert_eq!(state.selected_index, 11);
+
+    run_actions(&mut state, vec![Action::Resize(80, 12)]);
+    render(&mut state, 80, 12);
+    let (start, end) = state.visible_range;
+    assert!(start > 0);
+    assert!((start..end).contains(&11), "{:?}", state.visible_range);
+}
+
+#[test]
+fn dragging_a_divider_changes_the_split() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 22);
+    let divider = state.layout.commit_log_divider.unwrap();
+    assert_eq!(
+        state.divider_at(10, divider.position),
+        Some(Split::CommitLog)
+    );
+    assert_eq!(state.divider_at(10, divider.position - 2), None);
+
+    // Grab the Branches' top border and move it up to the middle
+    run_actions(&mut state, vec![Action::Click(10, divider.position)]);
+    assert_eq!(state.dragging, Some(Split::CommitLog));
+    run_actions(&mut state, vec![Action::Drag(10, 11)]);
+    assert_eq!(state.split_percent(Split::CommitLog), 50);
+    run_actions(&mut state, vec![Action::Release(10, 1)]);
+    assert_eq!(state.dragging, None);
+    assert_eq!(state.split_percent(Split::CommitLog), 10); // Never hides a panel
+
+    // Clicks only select again
+    render(&mut state, 80, 22);
+    run_actions(&mut state, vec![Action::Click(10, 10), Action::Drag(10, 5)]);
+    assert_eq!(state.split_percent(Split::CommitLog), 10);
+}
+
+#[test]
+fn dragging_beside_the_details_resizes_them() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::Select]);
+    render(&mut state, 200, 30);
+    let position = state.layout.details_divider.unwrap().position;
+
+    run_actions(
+        &mut state,
+        vec![Action::Click(position - 1, 10), Action::Release(149, 10)],
+    );
+    assert_eq!(state.split_percent(Split::Details), 25);
+
+    // Reloading the config keeps the dragged split
+    state.set_config(Config::default());
+    assert_eq!(state.split_percent(Split::Details), 25);
+}
+
+#[test]
+fn diffs_not_from_the_log_take_the_whole_screen() {
+    let fixture = fixture();
+    fixture.stage(&[("staged.txt", "staged\n")]);
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::ShowStagedChanges]);
+    render(&mut state, 200, 30);
+
+    assert_eq!(state.ui_state, UIState::CommitDetails);
+    assert_eq!(state.layout.details_divider, None);
+    assert_eq!(state.layout.commit_log_rows, None);
+
+    // Returning to a commit from the log shows the log again
+    run_actions(&mut state, vec![Action::Deselect, Action::Select]);
+    render(&mut state, 200, 30);
+    assert!(state.layout.details_divider.is_some());
+}
+
+#[test]
+fn clicking_the_log_beside_the_details_shows_that_commit() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::Select]);
+    render(&mut state, 200, 30);
+
+    run_actions(&mut state, vec![Action::Click(10, 4)]);
+
+    assert_eq!(state.ui_state, UIState::CommitDetails);
+    assert_eq!(state.selected_index, 2);
+    let details = state.selected_commit_details.as_ref().unwrap();
+    assert!(details.header.contains("commit 9"), "{}", details.header);
+}
+
+#[test]
+fn the_diffstat_selection_stays_visible() {
+    let fixture = Fixture::new();
+    let names: Vec<String> = (0..30).map(|i| format!("file{:02}.txt", i)).collect();
+    let files: Vec<(&str, &str)> = names.iter().map(|name| (name.as_str(), "x\n")).collect();
+    fixture.commit("many files", &files);
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::Select, Action::ToggleDiffStat]);
+    render(&mut state, 80, 20);
+
+    run_actions(&mut state, vec![Action::NavigateDown; 25]);
+    let screen = render(&mut state, 80, 20);
+    assert_eq!(state.selected_diff_file, 25);
+    assert!(screen.contains("file25.txt"), "{}", screen);
+
+    run_actions(&mut state, vec![Action::NavigateUp; 25]);
+    let screen = render(&mut state, 80, 20);
+    assert!(screen.contains("file00.txt"), "{}", screen);
+}
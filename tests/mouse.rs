@@ -0,0 +1,160 @@
+mod common;
+
+use common::{render, run_actions, Fixture};
+use ezgit_rs::app_state::{Panel, UIState};
+use ezgit_rs::input::Action;
+
+// At 80x16 the Commit Log's items start on row 2 and the Branches' on row 12
+const COMMIT_ROW: u16 = 2;
+const BRANCH_ROW: u16 = 12;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..12 {
+        fixture.commit(&format!("commit {}", i), &[("file.txt", &i.to_string())]);
+    }
+    fixture.branch("feature");
+    fixture
+}
+
+#[test]
+fn clicking_a_commit_selects_it() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+
+    run_actions(&mut state, vec![Action::Click(10, COMMIT_ROW + 3)]);
+
+    assert_eq!(state.selected_index, 3);
+    assert!(matches!(state.focused_panel, Panel::CommitLog));
+    assert_eq!(state.ui_state, UIState::Normal);
+}
+
+#[test]
+fn clicking_a_branch_focuses_the_branches() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+    assert_eq!(state.branches, vec!["feature", "main"]);
+
+    run_actions(&mut state, vec![Action::Click(10, BRANCH_ROW)]);
+
+    assert_eq!(state.selected_branch, 0);
+    assert!(matches!(state.focused_panel, Panel::Branches));
+    assert_eq!(state.branch_name, "main"); // Selecting doesn't check out
+}
+
+#[test]
+fn clicking_borders_and_empty_rows_only_focuses() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+    run_actions(&mut state, vec![Action::Click(10, COMMIT_ROW + 2)]);
+
+    // The Branches' border, then the row below the last branch
+    run_actions(&mut state, vec![Action::Click(10, BRANCH_ROW - 1)]);
+    assert!(matches!(state.focused_panel, Panel::Branches));
+    run_actions(&mut state, vec![Action::Click(10, BRANCH_ROW + 2)]);
+    assert_eq!(state.selected_branch, 1);
+
+    // Outside both panels
+    run_actions(&mut state, vec![Action::Click(0, 0)]);
+    assert!(matches!(state.focused_panel, Panel::Branches));
+    assert_eq!(state.selected_index, 2);
+}
+
+#[test]
+fn double_clicking_a_commit_opens_it() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+
+    run_actions(
+        &mut state,
+        vec![
+            Action::Click(10, COMMIT_ROW + 1),
+            Action::DoubleClick(10, COMMIT_ROW + 1),
+        ],
+    );
+
+    assert_eq!(state.ui_state, UIState::CommitDetails);
+    let details = state.selected_commit_details.as_ref().unwrap();
+    assert!(details.header.contains("commit 10"), "{}", details.header);
+}
+
+#[test]
+fn double_clicking_a_branch_does_not_check_it_out() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+
+    run_actions(&mut state, vec![Action::DoubleClick(10, BRANCH_ROW)]);
+
+    assert_eq!(state.ui_state, UIState::Normal);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn the_wheel_scrolls_the_panel_under_the_pointer() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+    assert_eq!(state.visible_range, (0, 8));
+
+    // The selection only moves when it would scroll out of view
+    run_actions(&mut state, vec![Action::ScrollDown(10, COMMIT_ROW)]);
+    assert_eq!(state.visible_range, (3, 11));
+    assert_eq!(state.selected_index, 3);
+    run_actions(&mut state, vec![Action::NavigateDown, Action::NavigateDown]);
+    run_actions(&mut state, vec![Action::ScrollUp(10, COMMIT_ROW)]);
+    render(&mut state, 80, 16);
+    assert_eq!(state.visible_range, (0, 8));
+    assert_eq!(state.selected_index, 5);
+    run_actions(&mut state, vec![Action::ScrollDown(10, COMMIT_ROW)]);
+    assert_eq!(state.visible_range, (3, 11));
+    assert_eq!(state.selected_index, 5);
+
+    // Both branches fit, so there is nothing to scroll
+    run_actions(&mut state, vec![Action::ScrollDown(10, BRANCH_ROW)]);
+    assert!(matches!(state.focused_panel, Panel::Branches));
+    assert_eq!(state.selected_branch, 1);
+    assert_eq!(state.selected_index, 5);
+}
+
+#[test]
+fn the_wheel_scrolls_lists_without_moving_the_selection() {
+    let fixture = Fixture::new();
+    let contents: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+    fixture.commit("long file", &[("long.txt", &contents)]);
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::BrowseFiles, Action::Select]);
+    assert_eq!(state.ui_state, UIState::FileContents);
+    render(&mut state, 80, 16);
+    run_actions(&mut state, vec![Action::NavigateDown]);
+
+    run_actions(&mut state, vec![Action::ScrollDown(10, 5)]);
+    let cursor = &state.file_contents.as_ref().unwrap().cursor;
+    assert_eq!(cursor.visible_range.0, 3);
+    assert_eq!(cursor.selected, 3); // Pulled along once it left the view
+
+    run_actions(
+        &mut state,
+        vec![Action::ScrollDown(10, 5), Action::ScrollUp(10, 5)],
+    );
+    let cursor = &state.file_contents.as_ref().unwrap().cursor;
+    assert_eq!(cursor.visible_range.0, 3);
+    assert_eq!(cursor.selected, 6);
+}
+
+#[test]
+fn the_wheel_scrolls_commit_details() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    render(&mut state, 80, 16);
+    run_actions(&mut state, vec![Action::Select]);
+    render(&mut state, 80, 16);
+    assert_eq!(state.commit_details_visible_range.0, 0);
+
+    run_actions(&mut state, vec![Action::ScrollDown(10, 5)]);
+    assert_eq!(state.commit_details_visible_range.0, 3);
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{render, run_actions, Fixture};
+use ezgit_rs::app_state::{AppState, UIState};
+use ezgit_rs::events;
+use ezgit_rs::input::Action;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..5 {
+        fixture.commit(&format!("commit {}", i), &[("file.txt", &i.to_string())]);
+    }
+    fixture
+}
+
+fn previewed_header(state: &AppState) -> &str {
+    &state.selected_commit_details.as_ref().unwrap().header
+}
+
+#[test]
+fn the_preview_follows_the_selection() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+
+    run_actions(&mut state, vec![Action::TogglePreview]);
+    assert!(previewed_header(&state).contains("commit 4"));
+
+    // Loaded once the keys stop coming, not for each commit on the way
+    run_actions(&mut state, vec![Action::NavigateDown, Action::NavigateDown]);
+    assert_eq!(state.ui_state, UIState::Normal);
+    assert!(previewed_header(&state).contains("commit 4"));
+    events::refresh_preview(&mut state);
+    assert!(previewed_header(&state).contains("commit 2"));
+    assert_eq!(state.details_cache.len(), 2);
+
+    let screen = render(&mut state, 160, 20);
+    assert!(
+        screen.contains("Enter: Open, P: Hide Preview"),
+        "{}",
+        screen
+    );
+    assert!(state.layout.details_divider.unwrap().side_by_side);
+
+    run_actions(&mut state, vec![Action::TogglePreview]);
+    render(&mut state, 160, 20);
+    assert_eq!(state.layout.details_area, None);
+}
+
+#[test]
+fn details_are_cached_by_commit() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::TogglePreview]);
+
+    for action in [
+        Action::NavigateDown,
+        Action::NavigateUp,
+        Action::NavigateDown,
+    ] {
+        run_actions(&mut state, vec![action]);
+        events::refresh_preview(&mut state);
+    }
+    assert!(previewed_header(&state).contains("commit 3"));
+    assert_eq!(state.details_cache.len(), 2);
+
+    // Opening the commit reads it afresh, since the preview doesn't check signatures
+    run_actions(&mut state, vec![Action::Select]);
+    assert_eq!(state.ui_state, UIState::CommitDetails);
+    assert!(previewed_header(&state).contains("commit 3"));
+    assert!(previewed_header(&state).contains("Signature: Not signed"));
+    assert_eq!(state.details_cache.len(), 2);
+
+    // A branch name and an abbreviated ID of the same commit share an entry
+    let head = fixture.head_commit().unwrap().id().to_string();
+    state.details_cache.get(fixture.path(), "main").unwrap();
+    state.details_cache.get(fixture.path(), &head[..7]).unwrap();
+    assert_eq!(state.details_cache.len(), 2);
+}
+
+#[test]
+fn returning_from_another_diff_restores_the_preview() {
+    let fixture = fixture();
+    std::fs::write(fixture.dir.path().join("file.txt"), "changed").unwrap();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::TogglePreview]);
+
+    run_actions(&mut state, vec![Action::ShowUnstagedChanges]);
+    assert_eq!(state.ui_state, UIState::CommitDetails);
+    assert!(!previewed_header(&state).contains("commit 4"));
+
+    run_actions(&mut state, vec![Action::Deselect]);
+    events::refresh_preview(&mut state);
+    assert_eq!(state.ui_state, UIState::Normal);
+    assert!(previewed_header(&state).contains("commit 4"));
+}
+
+#[test]
+fn the_wheel_scrolls_the_preview_under_the_pointer() {
+    let fixture = fixture();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::TogglePreview]);
+    render(&mut state, 80, 16); // Stacked: the preview is the bottom 60%
+    let preview = state.layout.details_area.unwrap();
+
+    run_actions(&mut state, vec![Action::ScrollDown(10, preview.y + 2)]);
+    render(&mut state, 80, 16);
+
+    assert_eq!(state.commit_details_visible_range.0, 3);
+    assert_eq!(state.selected_index, 0);
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{assert_snapshot, render, run_actions, type_text, Fixture};
+use ezgit_rs::app_state::UIState;
+use ezgit_rs::input::Action;
+
+// main: init, docs, merge of topic; topic: topic work
+fn history() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.branch("topic");
+    fixture.commit("docs", &[("docs/guide.md", "# Guide\n")]);
+    fixture.checkout("topic");
+    fixture.commit("topic work", &[("src/topic.rs", "fn topic() {}\n")]);
+    fixture.checkout("main");
+    fixture.merge("topic", "Merge branch 'topic'");
+    fixture
+}
+
+#[test]
+fn main_screen() {
+    let fixture = history();
+    let mut state = fixture.app_state();
+    assert_snapshot("main_screen", &render(&mut state, 80, 16));
+}
+
+#[test]
+fn switching_branch_from_the_branches_panel() {
+    let fixture = history();
+    let mut state = fixture.app_state();
+    run_actions(
+        &mut state,
+        vec![Action::SwitchPanel, Action::NavigateDown, Action::Select],
+    );
+    assert_eq!(fixture.head_branch().as_deref(), Some("topic"));
+    assert_snapshot("switched_to_topic", &render(&mut state, 80, 16));
+}
+
+#[test]
+fn key_guide() {
+    let fixture = history();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::ShowKeyGuide]);
+    assert_snapshot("key_guide", &render(&mut state, 80, 16));
+}
+
+#[test]
+fn the_key_guide_wraps_and_scrolls_to_its_last_line() {
+    let fixture = history();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::ShowKeyGuide]);
+    render(&mut state, 40, 12);
+
+    run_actions(&mut state, vec![Action::NavigateBottom]);
+    let screen = render(&mut state, 40, 12);
+    assert!(
+        screen.contains("│  - Ctrl+P: Pick a conventional     │"),
+        "{}",
+        screen
+    );
+    assert!(screen.contains("│commit type and scope"), "{}", screen);
+}
+
+#[test]
+fn filter_by_file_prompt() {
+    let fixture = history();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::FilterCommitsByFile]);
+    type_text(&mut state, "rs");
+    assert_snapshot("filter_by_file_prompt", &render(&mut state, 80, 12));
+}
+
+#[test]
+fn filtering_by_a_typed_or_picked_path() {
+    let fixture = history();
+    std::fs::remove_file(fixture.dir.path().join("docs/guide.md")).unwrap();
+    fixture.commit("replace guide", &[("docs/guide-old.md", "# Old\n")]);
+    let mut state = fixture.app_state();
+
+    // A deleted file isn't tracked any more, so it must be typed in full and isn't
+    // replaced by a similar tracked path
+    run_actions(&mut state, vec![Action::FilterCommitsByFile]);
+    type_text(&mut state, "docs/guide.md");
+    run_actions(&mut state, vec![Action::Confirm]);
+    assert_eq!(state.commit_log.len(), 2);
+    assert!(state.commit_log[0].ends_with("| replace guide"));
+
+    run_actions(&mut state, vec![Action::FilterCommitsByFile]);
+    type_text(&mut state, "rs");
+    run_actions(
+        &mut state,
+        vec![Action::NavigateDown, Action::NavigateUp, Action::Confirm],
+    );
+    assert_eq!(state.commit_log.len(), 2); // The merge brought it into main
+    assert!(state.commit_log[1].ends_with("| topic work"));
+}
+
+#[test]
+fn merging_into_itself_shows_an_error() {
+    let fixture = history();
+    let mut state = fixture.app_state();
+    run_actions(&mut state, vec![Action::MergeBranch]);
+    assert_snapshot("confirm_merge", &render(&mut state, 80, 8));
+
+    run_actions(&mut state, vec![Action::Select]);
+    assert_eq!(state.ui_state, UIState::Error);
+    assert_snapshot("merge_into_itself", &render(&mut state, 80, 8));
+}
+
+#[test]
+fn merging_a_branch_returns_to_the_updated_log() {
+    let fixture = history();
+    fixture.commit("more docs", &[("docs/more.md", "More\n")]);
+    fixture.checkout("topic");
+    fixture.commit("topic fix", &[("src/fix.rs", "fn fix() {}\n")]);
+    let mut state = fixture.app_state();
+
+    run_actions(&mut state, vec![Action::MergeBranch, Action::Select]);
+
+    assert_eq!(state.ui_state, UIState::Normal);
+    assert_eq!(state.branch_name, "main");
+    assert_eq!(fixture.head_commit().unwrap().parent_count(), 2);
+    assert!(state.commit_log[0].ends_with("| Merge branch 'topic' into main"));
+}
+
+#[test]
+fn rewriting_a_pushed_commit_warns_about_the_force_push() {
+    let fixture = history();
+    fixture.checkout("topic");
+    fixture.commit("topic fix", &[("src/fix.rs", "fn fix() {}\n")]);
+    let mut topic = fixture
+        .repo
+        .find_branch("topic", git2::BranchType::Local)
+        .unwrap();
+    topic.set_upstream(Some("main")).unwrap();
+    let mut state = fixture.app_state();
+
+    run_actions(&mut state, vec![Action::FixupCommit]); // The unpushed "topic fix"
+    assert!(!render(&mut state, 80, 10).contains("force push"));
+
+    run_actions(&mut state, vec![Action::Deselect, Action::NavigateDown]);
+    run_actions(&mut state, vec![Action::FixupCommit]); // "topic work", merged into main
+    let screen = render(&mut state, 80, 10);
+    assert!(screen.contains("already on main"), "{}", screen);
+}
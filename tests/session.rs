@@ -0,0 +1,172 @@
+mod common;
+
+use common::Fixture;
+use ezgit_rs::app_state::{AppState, InputMode, UIState};
+use ezgit_rs::event_source::{
+    format_action, parse_action, ChannelSource, EventSource, ScriptSource, SessionRecorder,
+};
+use ezgit_rs::events::{self, Effect};
+use ezgit_rs::input::Action;
+use std::sync::mpsc;
+
+// Runs every action of the source through the reducer, like the event loop does
+fn replay(state: &mut AppState, source: &mut dyn EventSource) -> Vec<Effect> {
+    let mut effects = Vec::new();
+    while !source.is_finished() {
+        let contexts = events::key_contexts(state);
+        if let Some(action) = source.next_action(&mut state.keymap, contexts).unwrap() {
+            effects.extend(events::update(state, action));
+            events::wait_for_operation(state);
+        }
+    }
+    effects
+}
+
+#[test]
+fn actions_round_trip_through_the_session_format() {
+    let actions = vec![
+        Action::Quit,
+        Action::NavigateTop,
+        Action::Deselect,
+        Action::CommitWork,
+        Action::Submit,
+        Action::PickCommitType,
+        Action::TextInput('a'),
+        Action::TextInput(' '),
+        Action::TextInput('"'),
+        Action::TextInput('\\'),
+        Action::TextInput('é'),
+        Action::Paste("first line\n\tsecond \"line\"\u{1b}".to_string()),
+        Action::Click(0, 12),
+        Action::DoubleClick(80, 3),
+        Action::ScrollUp(5, 5),
+        Action::ScrollDown(65535, 0),
+        Action::Drag(3, 4),
+        Action::Release(3, 9),
+        Action::Resize(120, 40),
+    ];
+    for action in actions {
+        let line = format_action(&action);
+        assert_eq!(parse_action(&line), Ok(vec![action]), "{}", line);
+    }
+}
+
+#[test]
+fn session_lines_use_the_keys_toml_names() {
+    assert_eq!(format_action(&Action::Deselect), "back");
+    assert_eq!(format_action(&Action::TextInput('x')), "type \"x\"");
+    assert_eq!(
+        parse_action("type \"a b\""),
+        Ok(vec![
+            Action::TextInput('a'),
+            Action::TextInput(' '),
+            Action::TextInput('b')
+        ])
+    );
+}
+
+#[test]
+fn invalid_scripts_name_the_line() {
+    let err = ScriptSource::parse("# comment\n\nquit\nfly").err().unwrap();
+    assert_eq!(err, "line 4: unknown action 'fly'");
+
+    assert!(parse_action("type abc").is_err());
+    assert!(parse_action("type \"abc").is_err());
+    assert!(parse_action("type \"a\\q\"").is_err());
+    assert!(parse_action("quit now").is_err());
+    assert!(parse_action("click 10").is_err());
+    assert!(parse_action("click 10 -1").is_err());
+}
+
+#[test]
+fn recorded_sessions_replay_the_same_actions() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("session.txt");
+    let actions = vec![
+        Action::FilterCommitsByFile,
+        Action::TextInput('r'),
+        Action::Paste("s\n".to_string()),
+        Action::Cancel,
+    ];
+
+    let mut recorder = SessionRecorder::create(&path).unwrap();
+    for action in &actions {
+        recorder.record(action).unwrap();
+    }
+    drop(recorder);
+
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+    let mut source = ScriptSource::load(&path).unwrap();
+    let contexts = events::key_contexts(&state);
+    let mut replayed = Vec::new();
+    while let Some(action) = source.next_action(&mut state.keymap, contexts).unwrap() {
+        replayed.push(action);
+    }
+    assert_eq!(replayed, actions);
+    assert!(source.is_finished());
+}
+
+#[test]
+fn scripts_drive_the_app_without_a_terminal() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.commit("code", &[("src/main.rs", "fn main() {}\n")]);
+    let mut state = fixture.app_state();
+
+    let mut source =
+        ScriptSource::parse("filter_by_file\ntype \"main\"\nnavigate_down\nconfirm\nquit\n")
+            .unwrap();
+    let effects = replay(&mut state, &mut source);
+
+    // Quitting only asks for confirmation
+    assert!(effects.is_empty());
+    assert_eq!(state.ui_state, UIState::ConfirmQuit);
+    assert!(matches!(state.input_mode, InputMode::Command));
+    assert_eq!(state.commit_log.len(), 1);
+    assert!(state.commit_log[0].ends_with("| code"));
+
+    let effects = replay(&mut state, &mut ScriptSource::parse("select").unwrap());
+    assert_eq!(effects, vec![Effect::Quit]);
+}
+
+#[test]
+fn main_view_actions_do_nothing_in_other_views() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+
+    let mut source = ScriptSource::parse("select\nblame\nkey_guide\nmerge\n").unwrap();
+    replay(&mut state, &mut source);
+    assert_eq!(state.ui_state, UIState::Blame);
+
+    // Back out through the views that were opened, to the main view
+    replay(
+        &mut state,
+        &mut ScriptSource::parse("back\nback\n").unwrap(),
+    );
+    assert_eq!(state.ui_state, UIState::Normal);
+    replay(&mut state, &mut ScriptSource::parse("back\n").unwrap());
+    assert_eq!(state.ui_state, UIState::Normal);
+}
+
+#[test]
+fn channel_source_finishes_when_the_senders_are_gone() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut source = ChannelSource::new(receiver);
+    let thread = std::thread::spawn(move || {
+        sender.send(Action::ShowKeyGuide).unwrap();
+        sender.send(Action::NavigateBottom).unwrap();
+    });
+    thread.join().unwrap();
+
+    replay(&mut state, &mut source);
+    assert!(source.is_finished());
+    assert_eq!(state.ui_state, UIState::KeyGuide);
+    assert!(state.key_guide_scroll > 0);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::{render, run_actions, type_text, Fixture};
+use ezgit_rs::app_state::{NotificationKind, UIState};
+use ezgit_rs::git_commands::{self, RepoStatus};
+use ezgit_rs::input::Action;
+use std::fs;
+
+#[test]
+fn status_counts_changes_and_tracks_the_upstream() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.branch("topic");
+    fixture.commit("main work", &[("main.txt", "main\n")]);
+    fixture.checkout("topic");
+    let topic_tip = fixture.commit("topic work", &[("topic.txt", "topic\n")]);
+    let mut topic = fixture
+        .repo
+        .find_branch("topic", git2::BranchType::Local)
+        .unwrap();
+    topic.set_upstream(Some("main")).unwrap();
+    fs::write(fixture.dir.path().join("README.md"), "changed\n").unwrap();
+    fs::write(fixture.dir.path().join("new.txt"), "new\n").unwrap();
+
+    let status = git_commands::get_repo_status(fixture.path()).unwrap();
+
+    assert_eq!(
+        status,
+        RepoStatus {
+            branch: Some("topic".to_string()),
+            head: Some(topic_tip.to_string()[..7].to_string()),
+            ahead_behind: Some((1, 1)),
+            operation: None,
+            changed: 2,
+        }
+    );
+}
+
+#[test]
+fn status_of_detached_and_unborn_heads() {
+    let fixture = Fixture::new();
+    let status = git_commands::get_repo_status(fixture.path()).unwrap();
+    assert_eq!(status.branch.as_deref(), Some("main"));
+    assert_eq!(status.head, None);
+
+    let oid = fixture.commit("init", &[("README.md", "hello\n")]);
+    fixture.repo.set_head_detached(oid).unwrap();
+    let status = git_commands::get_repo_status(fixture.path()).unwrap();
+    assert_eq!(status.branch, None);
+    assert_eq!(status.ahead_behind, None);
+}
+
+#[test]
+fn a_conflicted_merge_shows_in_the_status_bar() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("shared.txt", "base\n")]);
+    fixture.branch("feature");
+    fixture.commit("main edit", &[("shared.txt", "main\n")]);
+    fixture.checkout("feature");
+    fixture.commit("feature edit", &[("shared.txt", "feature\n")]);
+    let mut state = fixture.app_state();
+
+    run_actions(&mut state, vec![Action::MergeBranch, Action::Select]);
+
+    assert_eq!(state.ui_state, UIState::Error);
+    let status = state.repo_status.as_ref().unwrap();
+    assert_eq!(status.operation, Some("MERGING"));
+    let screen = render(&mut state, 100, 8);
+    let status_bar = screen.lines().last().unwrap();
+    assert!(status_bar.starts_with(" main "), "{}", status_bar);
+    assert!(status_bar.contains("MERGING 1 change │ "), "{}", status_bar);
+}
+
+#[test]
+fn operation_results_are_kept_in_the_message_history() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let _remote = fixture.bare_remote("origin"); // New branches are pushed
+    let mut state = fixture.app_state();
+    assert!(state.current_notification().is_none());
+
+    run_actions(&mut state, vec![Action::CreateBranch]);
+    type_text(&mut state, "feature");
+    run_actions(&mut state, vec![Action::Confirm]);
+    run_actions(&mut state, vec![Action::CreateBranch]);
+    type_text(&mut state, "feature");
+    run_actions(&mut state, vec![Action::Confirm]);
+
+    let latest = state.current_notification().unwrap();
+    assert_eq!(latest.kind, NotificationKind::Error);
+    assert!(latest
+        .message
+        .starts_with("Failed to create branch 'feature'"));
+    assert_eq!(
+        state.repo_status.as_ref().unwrap().branch.as_deref(),
+        Some("feature")
+    );
+
+    run_actions(&mut state, vec![Action::ShowMessages]);
+    assert_eq!(state.ui_state, UIState::Messages);
+    let screen = render(&mut state, 100, 10);
+    assert!(
+        screen.contains("✓ Created and switched to branch 'feature'"),
+        "{}",
+        screen
+    );
+    assert!(
+        screen.contains("✗ Failed to create branch 'feature'"),
+        "{}",
+        screen
+    );
+
+    run_actions(&mut state, vec![Action::Deselect]);
+    assert_eq!(state.ui_state, UIState::Normal);
+}
+
+#[test]
+fn outside_changes_show_up_on_refresh() {
+    let fixture = Fixture::new();
+    fixture.commit("init", &[("README.md", "hello\n")]);
+    let mut state = fixture.app_state();
+    fs::write(fixture.dir.path().join("new.txt"), "new\n").unwrap();
+
+    // Messages that don't come from changing the repository leave the status as it was
+    run_actions(&mut state, vec![Action::MarkForCompare]);
+    assert_eq!(state.repo_status.as_ref().unwrap().changed, 0);
+
+    run_actions(&mut state, vec![Action::Refresh]);
+    assert_eq!(state.repo_status.as_ref().unwrap().changed, 1);
+}